@@ -0,0 +1,473 @@
+/// user blocklist, kept in AppData as blocklist.json. entries block single items, tags,
+/// titles (regex), whole sources or a picture by its thumbnail dhash, so the same image
+/// stays hidden when it turns up on another site. the content policy (safety.rs) runs
+/// it over everything that reaches the ui. export/import use the same json so a team
+/// can pass one list around
+use crate::dedup;
+use crate::models::{BlockEntry, BlockKind, HiddenItems, WallpaperItem};
+use crate::storage::get_app_data_dir;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+lazy_static::lazy_static! {
+    /// loaded lazily from AppData, replaced as a whole on every change
+    static ref BLOCKLIST: RwLock<Option<Arc<Blocklist>>> = RwLock::new(None);
+}
+
+/// entries with their matchers built
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    entries: Vec<BlockEntry>,
+    items: HashSet<String>,
+    tags: HashSet<String>,
+    titles: Vec<Regex>,
+    sources: HashSet<String>,
+    hashes: Vec<u64>,
+}
+
+fn get_blocklist_file() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("blocklist.json"))
+}
+
+/// booru tags use `_` where other sites use spaces
+fn normalize_tag(tag: &str) -> String {
+    tag.to_lowercase().replace('_', " ").trim().to_string()
+}
+
+fn title_regex(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(1 << 20)
+        .build()
+        .map_err(|e| format!("bad title pattern '{}': {}", pattern, e))
+}
+
+fn parse_hash(value: &str) -> Result<u64, String> {
+    let hex = value.trim().trim_start_matches("0x");
+    if hex.len() != 16 {
+        return Err(format!("hash '{}' should be 16 hex digits", value));
+    }
+    u64::from_str_radix(hex, 16).map_err(|_| format!("hash '{}' isn't hex", value))
+}
+
+/// check and tidy an entry's value, ids and regexes keep their case
+fn normalize(mut entry: BlockEntry) -> Result<BlockEntry, String> {
+    entry.value = match entry.kind {
+        BlockKind::Tag => normalize_tag(&entry.value),
+        BlockKind::Source => entry.value.trim().to_lowercase(),
+        BlockKind::Hash => format!("{:016x}", parse_hash(&entry.value)?),
+        BlockKind::Title => {
+            title_regex(&entry.value)?;
+            entry.value
+        }
+        BlockKind::Item => entry.value.trim().to_string(),
+    };
+    if entry.value.is_empty() {
+        return Err(format!("empty {} entry", entry.kind.as_str()));
+    }
+    Ok(entry)
+}
+
+impl Blocklist {
+    fn new(entries: Vec<BlockEntry>) -> Self {
+        let mut blocklist = Blocklist::default();
+        for entry in &entries {
+            match entry.kind {
+                BlockKind::Item => {
+                    blocklist.items.insert(entry.value.clone());
+                }
+                BlockKind::Tag => {
+                    blocklist.tags.insert(normalize_tag(&entry.value));
+                }
+                BlockKind::Title => match title_regex(&entry.value) {
+                    Ok(regex) => blocklist.titles.push(regex),
+                    Err(e) => eprintln!("[BLOCKLIST] skipping entry: {}", e),
+                },
+                BlockKind::Source => {
+                    blocklist.sources.insert(entry.value.to_lowercase());
+                }
+                BlockKind::Hash => match parse_hash(&entry.value) {
+                    Ok(hash) => blocklist.hashes.push(hash),
+                    Err(e) => eprintln!("[BLOCKLIST] skipping entry: {}", e),
+                },
+            }
+        }
+        blocklist.entries = entries;
+        blocklist
+    }
+
+    pub fn blocks_source(&self, id: &str) -> bool {
+        self.sources.contains(&id.to_lowercase())
+    }
+
+    /// everything but the thumbnail hash, that needs a download
+    fn block_reason(&self, item: &WallpaperItem) -> Option<String> {
+        if self.items.contains(&item.id) {
+            return Some("blocked item".to_string());
+        }
+        if self.blocks_source(&item.source) {
+            return Some(format!("blocked source: {}", item.source));
+        }
+        if let Some(tag) = item
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| normalize_tag(tag))
+            .find(|tag| self.tags.contains(tag))
+        {
+            return Some(format!("blocked tag: {}", tag));
+        }
+        let title = item.title.as_deref().unwrap_or("");
+        if self.titles.iter().any(|regex| regex.is_match(title)) {
+            return Some("blocked title".to_string());
+        }
+        None
+    }
+
+    /// drop blocked items into `hidden`. thumbnails are only hashed when there are hash
+    /// entries, from the same cache the near-duplicate check uses
+    pub async fn apply(&self, items: &mut Vec<WallpaperItem>, hidden: &mut HiddenItems) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        items.retain(|item| match self.block_reason(item) {
+            Some(reason) => {
+                hidden.add(reason);
+                false
+            }
+            None => true,
+        });
+
+        if !self.hashes.is_empty() && !items.is_empty() {
+            let hashes = dedup::thumbnail_hashes(items).await;
+            let mut hashes = hashes.into_iter();
+            items.retain(|_| {
+                let blocked = hashes.next().flatten().is_some_and(|hash| {
+                    self.hashes
+                        .iter()
+                        .any(|blocked| dedup::is_near(hash, *blocked))
+                });
+                if blocked {
+                    hidden.add("blocked image".to_string());
+                }
+                !blocked
+            });
+        }
+    }
+}
+
+pub fn current() -> Arc<Blocklist> {
+    if let Some(blocklist) = BLOCKLIST.read().unwrap().as_ref() {
+        return blocklist.clone();
+    }
+
+    let entries: Vec<BlockEntry> = get_blocklist_file()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let blocklist = Arc::new(Blocklist::new(entries));
+    *BLOCKLIST.write().unwrap() = Some(blocklist.clone());
+    blocklist
+}
+
+pub fn entries() -> Vec<BlockEntry> {
+    current().entries.clone()
+}
+
+fn save(entries: Vec<BlockEntry>) -> Result<Vec<BlockEntry>, String> {
+    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+    std::fs::write(get_blocklist_file()?, json)
+        .map_err(|e| format!("failed to save blocklist: {}", e))?;
+    *BLOCKLIST.write().unwrap() = Some(Arc::new(Blocklist::new(entries.clone())));
+    Ok(entries)
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// add entries that aren't listed yet, returns the new list and how many were added.
+/// a bad entry fails the whole call so nothing is half saved
+pub fn add(new: Vec<BlockEntry>) -> Result<(Vec<BlockEntry>, usize), String> {
+    let (entries, added) = merge(entries(), new)?;
+    Ok((save(entries)?, added))
+}
+
+/// `entries` with the new ones that aren't listed yet, nothing is saved
+fn merge(
+    mut entries: Vec<BlockEntry>,
+    new: Vec<BlockEntry>,
+) -> Result<(Vec<BlockEntry>, usize), String> {
+    let mut added = 0;
+    for entry in new {
+        let mut entry = normalize(entry)?;
+        if entry.added_at == 0 {
+            entry.added_at = now();
+        }
+        let listed = entries
+            .iter()
+            .any(|other| other.kind == entry.kind && other.value == entry.value);
+        if !listed {
+            println!(
+                "[BLOCKLIST] blocking {} {}",
+                entry.kind.as_str(),
+                entry.value
+            );
+            entries.push(entry);
+            added += 1;
+        }
+    }
+    Ok((entries, added))
+}
+
+pub fn remove(kind: BlockKind, value: &str) -> Result<Vec<BlockEntry>, String> {
+    let entries = without(entries(), kind, value)?;
+    println!("[BLOCKLIST] unblocking {} {}", kind.as_str(), value);
+    save(entries)
+}
+
+/// `entries` minus the one matching `kind` and `value`, an error when it isn't there
+fn without(
+    mut entries: Vec<BlockEntry>,
+    kind: BlockKind,
+    value: &str,
+) -> Result<Vec<BlockEntry>, String> {
+    // stored values are normalized, `Blue_Sky` should find `blue sky`
+    let entry = BlockEntry {
+        kind,
+        value: value.to_string(),
+        note: None,
+        added_at: 0,
+    };
+    let value = normalize(entry)
+        .map(|entry| entry.value)
+        .unwrap_or_else(|_| value.to_string());
+    let value = value.as_str();
+    let before = entries.len();
+    entries.retain(|entry| !(entry.kind == kind && entry.value == value));
+    if entries.len() == before {
+        return Err(format!(
+            "{} {} is not on the blocklist",
+            kind.as_str(),
+            value
+        ));
+    }
+    Ok(entries)
+}
+
+/// entries for an item: its id, and its thumbnail hash when it could be worked out
+pub async fn entries_for(item: &WallpaperItem) -> Vec<BlockEntry> {
+    let note = item.title.clone().or_else(|| Some(item.id.clone()));
+    let mut entries = vec![BlockEntry {
+        kind: BlockKind::Item,
+        value: item.id.clone(),
+        note: note.clone(),
+        added_at: 0,
+    }];
+    if let Some(hash) = dedup::thumbnail_hashes(std::slice::from_ref(item))
+        .await
+        .into_iter()
+        .next()
+        .flatten()
+    {
+        entries.push(BlockEntry {
+            kind: BlockKind::Hash,
+            value: format!("{:016x}", hash),
+            note,
+            added_at: 0,
+        });
+    }
+    entries
+}
+
+pub fn export(path: &str) -> Result<usize, String> {
+    let entries = entries();
+    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("failed to write {}: {}", path, e))?;
+    println!("[BLOCKLIST] exported {} entries to {}", entries.len(), path);
+    Ok(entries.len())
+}
+
+/// merge a list written by `export`, or replace the current one with it
+pub fn import(path: &str, replace: bool) -> Result<(Vec<BlockEntry>, usize), String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    let imported: Vec<BlockEntry> = serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not a blocklist: {}", path, e))?;
+
+    println!(
+        "[BLOCKLIST] importing {} entries from {}",
+        imported.len(),
+        path
+    );
+    let base = if replace { Vec::new() } else { entries() };
+    let (entries, added) = merge(base, imported)?;
+    Ok((save(entries)?, added))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: BlockKind, value: &str) -> BlockEntry {
+        BlockEntry {
+            kind,
+            value: value.to_string(),
+            note: None,
+            added_at: 0,
+        }
+    }
+
+    fn item(id: &str, source: &str, title: &str, tags: &[&str]) -> WallpaperItem {
+        WallpaperItem {
+            id: id.to_string(),
+            source: source.to_string(),
+            title: Some(title.to_string()),
+            image_url: format!("https://example.com/{}.jpg", id),
+            thumbnail_url: None,
+            media_type: Some("image".to_string()),
+            width: None,
+            height: None,
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            detail_url: None,
+            original: None,
+        }
+    }
+
+    #[test]
+    fn entries_are_normalized() {
+        let value = |kind, value| normalize(entry(kind, value)).map(|entry| entry.value);
+        assert_eq!(
+            value(BlockKind::Tag, " Blue_Sky "),
+            Ok("blue sky".to_string())
+        );
+        assert_eq!(
+            value(BlockKind::Source, " WallHaven"),
+            Ok("wallhaven".to_string())
+        );
+        assert_eq!(
+            value(BlockKind::Hash, "0x00FF00ff00FF00ff"),
+            Ok("00ff00ff00ff00ff".to_string())
+        );
+        // ids and regexes keep their case
+        assert_eq!(
+            value(BlockKind::Item, " Booru-ABC "),
+            Ok("Booru-ABC".to_string())
+        );
+        assert_eq!(
+            value(BlockKind::Title, "^Sunset"),
+            Ok("^Sunset".to_string())
+        );
+
+        assert!(value(BlockKind::Hash, "abc").is_err());
+        assert!(value(BlockKind::Hash, "zzzzzzzzzzzzzzzz").is_err());
+        assert!(value(BlockKind::Tag, " _ ").is_err());
+        assert!(value(BlockKind::Item, "  ").is_err());
+    }
+
+    #[test]
+    fn bad_title_regex_is_rejected() {
+        let e = normalize(entry(BlockKind::Title, "sunset (")).unwrap_err();
+        assert!(e.starts_with("bad title pattern 'sunset ('"), "{}", e);
+
+        // one bad entry and nothing of the call is kept
+        let current = vec![entry(BlockKind::Tag, "gore")];
+        let new = vec![
+            entry(BlockKind::Tag, "blood"),
+            entry(BlockKind::Title, "[unclosed"),
+        ];
+        assert!(merge(current, new).is_err());
+    }
+
+    #[test]
+    fn merge_skips_listed_entries() {
+        let current = vec![BlockEntry {
+            added_at: 5,
+            ..entry(BlockKind::Tag, "blue sky")
+        }];
+        let imported = vec![
+            entry(BlockKind::Tag, "Blue_Sky"),
+            entry(BlockKind::Source, "Konachan"),
+            entry(BlockKind::Source, "konachan"),
+            BlockEntry {
+                added_at: 7,
+                ..entry(BlockKind::Item, "wallhaven-abc")
+            },
+        ];
+
+        let (entries, added) = merge(current, imported).unwrap();
+        assert_eq!(added, 2);
+        let values: Vec<&str> = entries.iter().map(|entry| entry.value.as_str()).collect();
+        assert_eq!(values, ["blue sky", "konachan", "wallhaven-abc"]);
+        // imported entries keep when they were added, new ones get the time now
+        assert_eq!(entries[0].added_at, 5);
+        assert!(entries[1].added_at > 0);
+        assert_eq!(entries[2].added_at, 7);
+    }
+
+    #[test]
+    fn remove_finds_the_normalized_value() {
+        let entries = vec![
+            entry(BlockKind::Tag, "blue sky"),
+            entry(BlockKind::Source, "konachan"),
+        ];
+        let left = without(entries.clone(), BlockKind::Tag, "Blue_Sky").unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].kind, BlockKind::Source);
+
+        // same value, other kind
+        let e = without(entries, BlockKind::Item, "konachan").unwrap_err();
+        assert_eq!(e, "item konachan is not on the blocklist");
+    }
+
+    #[tokio::test]
+    async fn apply_drops_blocked_items() {
+        let (entries, _) = merge(
+            Vec::new(),
+            vec![
+                entry(BlockKind::Item, "wallhaven-1"),
+                entry(BlockKind::Tag, "Blue_Sky"),
+                entry(BlockKind::Title, "^neon"),
+                entry(BlockKind::Source, "Konachan"),
+            ],
+        )
+        .unwrap();
+        let blocklist = Blocklist::new(entries);
+
+        let mut items = vec![
+            item("wallhaven-1", "wallhaven", "forest", &[]),
+            item("wallhaven-2", "wallhaven", "lake", &["blue_sky"]),
+            item("wallhaven-3", "wallhaven", "Neon city", &[]),
+            item("wallhaven-4", "wallhaven", "city of neon", &[]),
+            item("konachan-5", "konachan", "forest", &[]),
+            item("wallhaven-6", "wallhaven", "sky", &["sky", "blue"]),
+        ];
+        let mut hidden = HiddenItems::default();
+        blocklist.apply(&mut items, &mut hidden).await;
+
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, ["wallhaven-4", "wallhaven-6"]);
+        assert_eq!(hidden.total, 4);
+        assert_eq!(hidden.reasons["blocked tag: blue sky"], 1);
+        assert_eq!(hidden.reasons["blocked source: konachan"], 1);
+        assert!(blocklist.blocks_source("KONACHAN"));
+    }
+
+    #[tokio::test]
+    async fn empty_blocklist_keeps_everything() {
+        let mut items = vec![item("a", "wallhaven", "anything", &["tag"])];
+        let mut hidden = HiddenItems::default();
+        Blocklist::new(Vec::new())
+            .apply(&mut items, &mut hidden)
+            .await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(hidden.total, 0);
+    }
+}
@@ -0,0 +1,88 @@
+/// user blocklist commands, matching lives in blocklist.rs
+use crate::blocklist;
+use crate::models::*;
+
+fn respond(result: Result<(Vec<BlockEntry>, usize), String>) -> BlocklistResponse {
+    match result {
+        Ok((entries, added)) => BlocklistResponse {
+            success: true,
+            entries,
+            added,
+            error: None,
+        },
+        Err(e) => BlocklistResponse {
+            success: false,
+            entries: blocklist::entries(),
+            added: 0,
+            error: Some(e),
+        },
+    }
+}
+
+#[tauri::command]
+pub async fn list_blocklist() -> Result<BlocklistResponse, String> {
+    Ok(BlocklistResponse {
+        success: true,
+        entries: blocklist::entries(),
+        added: 0,
+        error: None,
+    })
+}
+
+/// `value` is an item id, tag, title regex, source id or 16 hex digit thumbnail hash
+#[tauri::command]
+pub async fn add_blocklist_entry(
+    kind: BlockKind,
+    value: String,
+    note: Option<String>,
+) -> Result<BlocklistResponse, String> {
+    Ok(respond(blocklist::add(vec![BlockEntry {
+        kind,
+        value,
+        note,
+        added_at: 0,
+    }])))
+}
+
+#[tauri::command]
+pub async fn remove_blocklist_entry(
+    kind: BlockKind,
+    value: String,
+) -> Result<BlocklistResponse, String> {
+    Ok(respond(
+        blocklist::remove(kind, &value).map(|entries| (entries, 0)),
+    ))
+}
+
+/// "don't show this again": blocks the item and, by its thumbnail hash, the same
+/// picture on every other site
+#[tauri::command]
+pub async fn block_wallpaper(item: WallpaperItem) -> Result<BlocklistResponse, String> {
+    let entries = blocklist::entries_for(&item).await;
+    Ok(respond(blocklist::add(entries)))
+}
+
+#[tauri::command]
+pub async fn export_blocklist(path: String) -> Result<WallpaperResponse, String> {
+    match blocklist::export(&path) {
+        Ok(count) => Ok(WallpaperResponse {
+            success: true,
+            message: Some(format!("Exported {} entries", count)),
+            error: None,
+        }),
+        Err(e) => Ok(WallpaperResponse {
+            success: false,
+            message: None,
+            error: Some(e),
+        }),
+    }
+}
+
+/// merge a shared blocklist into this one, `replace` drops the current entries first
+#[tauri::command]
+pub async fn import_blocklist(
+    path: String,
+    replace: Option<bool>,
+) -> Result<BlocklistResponse, String> {
+    Ok(respond(blocklist::import(&path, replace.unwrap_or(false))))
+}
@@ -0,0 +1,40 @@
+/// source health commands, for the "are my sources broken" panel
+use crate::health;
+use crate::models::*;
+use crate::sources;
+
+/// probe the given sources (all registered ones if none) and return fresh results
+#[tauri::command]
+pub async fn check_sources(sources: Option<Vec<String>>) -> Result<SourceHealthResponse, String> {
+    let ids = sources.unwrap_or_else(sources::default_ids);
+    if let Some(unknown) = ids.iter().find(|id| sources::get(id).is_none()) {
+        return Ok(SourceHealthResponse {
+            success: false,
+            sources: Vec::new(),
+            error: Some(format!("unknown source: {}", unknown)),
+        });
+    }
+
+    println!("[BACKEND:HEALTH] Checking sources: {}", ids.join(","));
+    Ok(SourceHealthResponse {
+        success: true,
+        sources: health::check_sources(&ids).await,
+        error: None,
+    })
+}
+
+/// last stored results without hitting the network
+#[tauri::command]
+pub async fn get_source_health() -> Result<SourceHealthResponse, String> {
+    let last = health::last_health();
+    let sources = sources::default_ids()
+        .iter()
+        .filter_map(|id| last.get(id).cloned())
+        .collect();
+
+    Ok(SourceHealthResponse {
+        success: true,
+        sources,
+        error: None,
+    })
+}
@@ -0,0 +1,37 @@
+/// local folder source commands, indexing itself lives in sources/local.rs
+use crate::models::*;
+use crate::sources::local;
+
+/// walk the configured folders again right now instead of waiting for the index to go stale
+#[tauri::command]
+pub async fn reindex_local_folders() -> Result<ReindexResponse, String> {
+    match local::reindex().await {
+        Ok(file_count) => Ok(ReindexResponse {
+            success: true,
+            file_count,
+            error: None,
+        }),
+        Err(e) => Ok(ReindexResponse {
+            success: false,
+            file_count: 0,
+            error: Some(e),
+        }),
+    }
+}
+
+/// replace the tags of a local file, they're matched by search like folder names
+#[tauri::command]
+pub async fn set_local_tags(path: String, tags: Vec<String>) -> Result<WallpaperResponse, String> {
+    match local::set_tags(&path, tags) {
+        Ok(()) => Ok(WallpaperResponse {
+            success: true,
+            message: Some("Tags saved".to_string()),
+            error: None,
+        }),
+        Err(e) => Ok(WallpaperResponse {
+            success: false,
+            message: None,
+            error: Some(e),
+        }),
+    }
+}
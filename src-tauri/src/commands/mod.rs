@@ -2,8 +2,14 @@
 pub mod search;
 pub mod wallpaper;
 pub mod settings;
+pub mod health;
+pub mod local;
+pub mod blocklist;
 
 pub use search::*;
 pub use wallpaper::*;
 pub use settings::*;
+pub use health::*;
+pub use local::*;
+pub use blocklist::*;
 
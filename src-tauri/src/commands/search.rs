@@ -1,81 +1,323 @@
 /// search and scraping related commands for the triple load
-use crate::models::SearchResponse;
-use crate::scraper::*;
-use rand::seq::SliceRandom;
-use std::collections::HashSet;
+use crate::dedup;
+use crate::error::ScrapeError;
+use crate::details;
+use crate::health;
+use crate::palette;
+use crate::probe;
+use crate::models::*;
+use crate::query;
+use crate::ranking;
+use crate::safety::Policy;
+use crate::sources::{self, ResolvedMedia, SearchParams};
+use crate::storage::load_settings;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
 
+lazy_static::lazy_static! {
+    /// streaming searches still running, keyed by search id so they can be cancelled
+    static ref ACTIVE_SEARCHES: Mutex<HashMap<String, JoinHandle<()>>> = Mutex::new(HashMap::new());
+}
+
+static SEARCH_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/// emit for a streaming search only while it's still registered. the check and the emit
+/// happen under the lock `cancel_search` takes to unregister it, so once that returns
+/// nothing from the search can land anymore. false means it was cancelled
+fn emit_if_active<S: serde::Serialize + Clone>(
+    app: &AppHandle,
+    search_id: &str,
+    event: &str,
+    payload: S,
+) -> bool {
+    let active = ACTIVE_SEARCHES.lock().unwrap();
+    if !active.contains_key(search_id) {
+        return false;
+    }
+    let _ = app.emit(event, payload);
+    true
+}
+
+/// search request with defaults applied, ready to hand to the source registry
+struct PreparedSearch {
+    sources: Vec<String>,
+    params: SearchParams,
+    timeout: Duration,
+    ranking: Ranking,
+    seed: u32,
+    /// per-source page from the request cursors, overrides `params.page`
+    pages: HashMap<String, u32>,
+    /// sources whose cursor said they're out of pages, not searched again
+    exhausted: Vec<String>,
+    /// parts of the query string that didn't parse
+    query_errors: Vec<QueryError>,
+    /// requested sources the content policy left out
+    blocked_sources: Vec<String>,
+}
+
+impl PreparedSearch {
+    /// only the shuffle depends on the seed, other rankings don't hand one out
+    fn response_seed(&self) -> Option<u32> {
+        (self.ranking == Ranking::Shuffle).then_some(self.seed)
+    }
+
+    fn page_of(&self, source: &str) -> u32 {
+        self.pages.get(source).copied().unwrap_or(self.params.page)
+    }
+
+    /// shuffle salt for the merged page, changes whenever any source moves on
+    fn salt(&self) -> String {
+        if self.pages.is_empty() {
+            return self.params.page.to_string();
+        }
+        self.sources
+            .iter()
+            .map(|source| format!("{}:{}", source, self.page_of(source)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// starting cursor map, exhausted sources stay null
+    fn empty_cursors(&self) -> HashMap<String, Option<String>> {
+        self.exhausted
+            .iter()
+            .map(|source| (source.clone(), None))
+            .collect()
+    }
+
+    fn query_errors(&self) -> Option<Vec<QueryError>> {
+        if self.query_errors.is_empty() {
+            None
+        } else {
+            Some(self.query_errors.clone())
+        }
+    }
+
+    /// hidden summary to add per-source counts to, starts with the left out sources
+    fn hidden(&self) -> HiddenItems {
+        HiddenItems {
+            sources: self.blocked_sources.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// cursors are opaque to the frontend, for now they only carry a page number
+fn encode_cursor(next_page: Option<u32>) -> Option<String> {
+    next_page.map(|page| format!("page:{}", page))
+}
+
+fn decode_cursor(cursor: &str) -> Option<u32> {
+    cursor
+        .strip_prefix("page:")
+        .and_then(|page| page.parse().ok())
+        .filter(|page| *page > 0)
+}
+
+/// sources the last health check flagged, so the ui can warn before results come back thin
+fn degraded_sources(ids: &[String]) -> Option<Vec<String>> {
+    let degraded = health::degraded_among(ids);
+    if degraded.is_empty() {
+        None
+    } else {
+        println!("[BACKEND:SEARCH] Degraded sources: {}", degraded.join(","));
+        Some(degraded)
+    }
+}
+
+/// what a source can return at all, so `type:` doesn't search sites that can't match
+fn offers_media_type(id: &str, media_type: &str) -> bool {
+    sources::get(id)
+        .map(|source| {
+            let capabilities = source.capabilities();
+            if media_type == "video" {
+                capabilities.videos
+            } else {
+                capabilities.images
+            }
+        })
+        .unwrap_or(true)
+}
+
+fn prepare_search(request: SearchRequest) -> PreparedSearch {
+    let (parsed, query_errors) = query::parse(&request.query);
+    for error in &query_errors {
+        println!(
+            "[BACKEND:SEARCH] Query error at {}..{}: {}",
+            error.start, error.end, error.message
+        );
+    }
+
+    // filters typed into the query win over the request's own
+    let mut sources = parsed
+        .sources
+        .clone()
+        .or(request.sources)
+        .unwrap_or_else(sources::default_ids);
+    if let Some(media_type) = &parsed.media_type {
+        sources.retain(|source| offers_media_type(source, media_type));
+    }
+    let min_width = request.min_width.max(parsed.min_width);
+    let min_height = request.min_height.max(parsed.min_height);
+    let aspect = parsed
+        .aspect
+        .clone()
+        .or(request.aspect)
+        .filter(|aspect| !aspect.trim().is_empty());
+
+    let mut pages = HashMap::new();
+    let mut exhausted = Vec::new();
+    for (source, cursor) in request.cursors.unwrap_or_default() {
+        match cursor.as_deref().map(decode_cursor) {
+            Some(Some(page)) => {
+                pages.insert(source, page);
+            }
+            Some(None) => println!("[BACKEND:SEARCH] Ignoring bad cursor for {}", source),
+            None => exhausted.push(source),
+        }
+    }
+    sources.retain(|source| !exhausted.contains(source));
+
+    // the content policy caps whatever the request or query asked for
+    let policy = Policy::current();
+    let (allowed, blocked_sources): (Vec<String>, Vec<String>) =
+        sources.into_iter().partition(|id| {
+            sources::get(id)
+                .map(|source| policy.allows_source(source.as_ref()))
+                .unwrap_or(true)
+        });
+    let sources = allowed;
+    if !blocked_sources.is_empty() {
+        println!(
+            "[BACKEND:SEARCH] Left out by content settings: {}",
+            blocked_sources.join(",")
+        );
+    }
+    let purity = policy.cap_purity(
+        parsed
+            .purity
+            .as_deref()
+            .or(request.purity.as_deref())
+            .unwrap_or("100"),
+    );
+
+    let timeout = request
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or_else(|| Duration::from_secs(load_settings().source_timeout_secs));
+
+    // wallhaven can filter server-side, so its pages don't come back half empty
+    let mut wallhaven = request.wallhaven.unwrap_or_default();
+    if wallhaven.atleast.is_none() && (min_width.is_some() || min_height.is_some()) {
+        wallhaven.atleast = Some(format!(
+            "{}x{}",
+            min_width.unwrap_or(1),
+            min_height.unwrap_or(1)
+        ));
+    }
+    if wallhaven.ratios.is_none() {
+        wallhaven.ratios = aspect.as_deref().and_then(probe::wallhaven_ratios);
+    }
+    let color = parsed.color.clone();
+    if wallhaven.colors.is_none() {
+        wallhaven.colors = color
+            .as_deref()
+            .and_then(palette::parse_hex)
+            .map(|color| palette::wallhaven_color(color).to_string());
+    }
+
+    PreparedSearch {
+        sources,
+        params: SearchParams {
+            query: parsed.text(),
+            page: request.page.unwrap_or(1),
+            limit: request.limit_per_source.unwrap_or(10),
+            purity,
+            ai_art: policy.cap_ai_art(request.ai_art.unwrap_or(false)),
+            include_videos: parsed.media_type.as_deref() == Some("video"),
+            wallhaven,
+            min_width,
+            min_height,
+            max_width: parsed.max_width,
+            max_height: parsed.max_height,
+            aspect,
+            exclude: parsed.excluded,
+            media_type: parsed.media_type,
+            color,
+        },
+        timeout,
+        // no ranking given keeps the old behaviour: shuffled unless randomize is false
+        ranking: request.ranking.unwrap_or(if request.randomize == Some(false) {
+            Ranking::Source
+        } else {
+            Ranking::Shuffle
+        }),
+        seed: request.seed.unwrap_or_else(ranking::new_seed),
+        pages,
+        exhausted,
+        query_errors,
+        blocked_sources,
+    }
+}
+
+/// search the requested sources at once, everything comes back in one response.
+/// takes the same request as `start_search`
 #[tauri::command]
-pub async fn search_wallpapers(
-    query: String,
-    sources: Option<Vec<String>>,
-    limit_per_source: Option<usize>,
-    randomize: Option<bool>,
-    page: Option<u32>,
-    purity: Option<String>,
-    ai_art: Option<bool>,
-) -> Result<SearchResponse, String> {
-    let sources = sources.unwrap_or_else(|| {
-        vec![
-            "wallhaven".to_string(),
-            "moewalls".to_string(),
-            "wallpapers".to_string(),
-            "wallpaperflare".to_string(),
-            "motionbgs".to_string(),
-        ]
-    });
-    let limit = limit_per_source.unwrap_or(10);
-    let should_randomize = randomize.unwrap_or(true);
-    let page_num = page.unwrap_or(1);
-    let purity_val = purity.unwrap_or_else(|| "100".to_string());
-    let ai_art_enabled = ai_art.unwrap_or(false);
+pub async fn search_wallpapers(request: SearchRequest) -> Result<SearchResponse, String> {
+    Ok(run_search(prepare_search(request)).await)
+}
 
+/// search every source of `search` at once and merge, dedup and rank the results
+async fn run_search(search: PreparedSearch) -> SearchResponse {
     println!(
-        "[BACKEND:SEARCH] Starting search - query: '{}', page: {}, limit: {}, sources: {}",
-        query,
-        page_num,
-        limit,
-        sources.join(",")
+        "[BACKEND:SEARCH] Starting search - query: '{}', page: {}, limit: {}, timeout: {}ms, sources: {}",
+        search.params.query,
+        search.params.page,
+        search.params.limit,
+        search.timeout.as_millis(),
+        search.sources.join(",")
     );
 
+    // sources and the near-duplicate check share the one timeout
+    let deadline = tokio::time::Instant::now() + search.timeout;
     let mut all_items = Vec::new();
     let mut errors = Vec::new();
+    let mut cache_age: Option<Duration> = None;
+    let mut cursors = search.empty_cursors();
+    let mut hidden = search.hidden();
 
-    for source in sources {
-        println!("[BACKEND:SEARCH] Scraping source: {}", source);
-
-        let result = match source.as_str() {
-            "wallhaven" => {
-                println!("[BACKEND:SCRAPE] wallhaven - page: {}", page_num);
-                scrape_wallhaven(&query, page_num, ai_art_enabled, &purity_val, limit).await
-            }
-            "moewalls" => {
-                println!("[BACKEND:SCRAPE] moewalls - page: {}", page_num);
-                scrape_moewalls(Some(&query), limit, false, page_num).await
-            }
-            "wallpapers" => {
-                println!("[BACKEND:SCRAPE] wallpapers.com - page: {}", page_num);
-                scrape_wallpapers_com(&query, limit, page_num).await
-            }
-            "wallpaperflare" => {
-                println!("[BACKEND:SCRAPE] wallpaperflare - page: {}", page_num);
-                scrape_wallpaperflare(&query, limit, page_num).await
-            }
-            "motionbgs" => {
-                println!("[BACKEND:SCRAPE] motionbgs - page: {}", page_num);
-                scrape_motionbgs(&query, limit, page_num).await
-            }
-            _ => continue,
-        };
-
-        match result {
+    for outcome in
+        sources::search_all(&search.sources, &search.params, &search.pages, search.timeout).await
+    {
+        cursors.insert(outcome.source.clone(), encode_cursor(outcome.next_page));
+        hidden.merge(outcome.hidden);
+        match outcome.result {
             Ok(items) => {
-                let count = items.len();
-                println!("[BACKEND:SCRAPE] {}: Got {} items", source, count);
+                println!(
+                    "[BACKEND:SCRAPE] {}: Got {} items in {}ms",
+                    outcome.source,
+                    items.len(),
+                    outcome.elapsed.as_millis()
+                );
                 all_items.extend(items);
+                // still report the failure, the items are just a fallback
+                if let Some(stale) = outcome.stale {
+                    cache_age = cache_age.max(Some(stale.age));
+                    errors.push(stale.error);
+                }
             }
             Err(e) => {
-                println!("[BACKEND:SCRAPE] {}: ERROR - {}", source, e);
-                errors.push(format!("{}: {}", source, e));
+                println!(
+                    "[BACKEND:SCRAPE] {}: {} - {}",
+                    outcome.source,
+                    e.kind.to_uppercase(),
+                    e.message
+                );
+                errors.push(e);
             }
         }
     }
@@ -87,17 +329,25 @@ pub async fn search_wallpapers(
 
     let mut seen = HashSet::new();
     all_items.retain(|item| seen.insert(item.id.clone()));
+    if load_settings().dedup_similar {
+        all_items = dedup::collapse_similar(all_items, deadline).await;
+    }
 
     println!(
         "[BACKEND:SEARCH] Total items after dedup: {}",
         all_items.len()
     );
 
-    if should_randomize {
-        let mut rng = rand::thread_rng();
-        all_items.shuffle(&mut rng);
-        println!("[BACKEND:SEARCH] Shuffled results");
-    }
+    ranking::rank(
+        &mut all_items,
+        search.ranking,
+        search.seed,
+        &search.salt(),
+    );
+    println!(
+        "[BACKEND:SEARCH] Ranked results by {:?} (seed {})",
+        search.ranking, search.seed
+    );
 
     println!(
         "[BACKEND:SEARCH] Returning {} items with {} errors",
@@ -105,7 +355,7 @@ pub async fn search_wallpapers(
         errors.len()
     );
 
-    Ok(SearchResponse {
+    SearchResponse {
         success: !all_items.is_empty(),
         items: all_items,
         errors: if errors.is_empty() {
@@ -113,80 +363,578 @@ pub async fn search_wallpapers(
         } else {
             Some(errors)
         },
+        degraded: degraded_sources(&search.sources),
+        stale: cache_age.is_some(),
+        cache_age_secs: cache_age.map(|age| age.as_secs()),
+        seed: search.response_seed(),
+        has_more: cursors.values().any(Option::is_some),
+        cursors,
+        query_errors: search.query_errors(),
+        hidden: hidden.into_option(),
+    }
+}
+
+/// how many of an item's tags `find_similar` searches for, sites AND them together
+const SIMILAR_TAGS: usize = 2;
+
+/// more like `item`: wallhaven's `like:` for wallhaven items, the item's tags everywhere
+/// else, narrowed to its dominant color (wallhaven `colors`, palette filtering elsewhere).
+/// `page` / `cursors` page through it like a normal search
+#[tauri::command]
+pub async fn find_similar(
+    item: WallpaperItem,
+    page: Option<u32>,
+    cursors: Option<HashMap<String, Option<String>>>,
+) -> Result<SearchResponse, String> {
+    let color = palette::palettes(std::slice::from_ref(&item))
+        .await
+        .into_iter()
+        .next()
+        .flatten()
+        .and_then(|palette| palette.first().copied());
+    // listing items rarely carry tags, the (cached) detail page usually has them
+    let tags = match item.tags.clone().filter(|tags| !tags.is_empty()) {
+        Some(tags) => tags,
+        None => details::get(&item)
+            .await
+            .map(|details| details.tags)
+            .unwrap_or_default(),
+    };
+    // quoted so tags with spaces or colons stay plain search text
+    let terms: Vec<String> = tags
+        .iter()
+        .take(SIMILAR_TAGS)
+        .map(|tag| format!("\"{}\"", tag.replace('"', "")))
+        .collect();
+
+    let request = |query: String, sources: Vec<String>| SearchRequest {
+        query,
+        sources: Some(sources),
+        limit_per_source: Some(20),
+        page,
+        ranking: Some(Ranking::Interleave),
+        cursors: cursors.clone(),
+        ..Default::default()
+    };
+    let mut tag_sources = sources::default_ids();
+    let like_search = (item.source == "wallhaven").then(|| {
+        tag_sources.retain(|source| source != "wallhaven");
+        let id = item.id.trim_start_matches("wallhaven-");
+        prepare_search(request(format!("\"like:{}\"", id), vec!["wallhaven".to_string()]))
+    });
+    let tag_search = (!terms.is_empty() || color.is_some()).then(|| {
+        let mut query = terms.join(" ");
+        if let Some(color) = color {
+            query = format!("{} color:{}", query, palette::to_hex(color));
+        }
+        prepare_search(request(query.trim().to_string(), tag_sources))
+    });
+    if like_search.is_none() && tag_search.is_none() {
+        return Err(format!(
+            "nothing to compare {} by, it has no tags and no thumbnail",
+            item.id
+        ));
+    }
+
+    println!(
+        "[BACKEND:SIMILAR] {} - tags: {:?}, color: {}",
+        item.id,
+        terms,
+        color.map(palette::to_hex).unwrap_or_default()
+    );
+    let (like, tagged) = tokio::join!(
+        async {
+            match like_search {
+                Some(search) => Some(run_search(search).await),
+                None => None,
+            }
+        },
+        async {
+            match tag_search {
+                Some(search) => Some(run_search(search).await),
+                None => None,
+            }
+        }
+    );
+
+    // wallhaven's own similarity goes first, it knows best
+    let mut merged = SearchResponse::default();
+    let mut seen = HashSet::from([item.id.clone()]);
+    for response in [like, tagged].into_iter().flatten() {
+        merged
+            .items
+            .extend(response.items.into_iter().filter(|other| seen.insert(other.id.clone())));
+        if let Some(errors) = response.errors {
+            merged.errors.get_or_insert_with(Vec::new).extend(errors);
+        }
+        if let Some(degraded) = response.degraded {
+            merged.degraded.get_or_insert_with(Vec::new).extend(degraded);
+        }
+        if let Some(hidden) = response.hidden {
+            merged.hidden.get_or_insert_with(HiddenItems::default).merge(hidden);
+        }
+        merged.stale |= response.stale;
+        merged.cache_age_secs = merged.cache_age_secs.max(response.cache_age_secs);
+        merged.cursors.extend(response.cursors);
+        merged.has_more |= response.has_more;
+    }
+    merged.success = !merged.items.is_empty();
+    println!(
+        "[BACKEND:SIMILAR] {} - {} similar items",
+        item.id,
+        merged.items.len()
+    );
+    Ok(merged)
+}
+
+/// streaming variant of `search_wallpapers`, returns right away and emits
+/// `search:partial` / `search:source-error` per source and `search:done` at the end
+#[tauri::command]
+pub async fn start_search(
+    app: AppHandle,
+    request: SearchRequest,
+) -> Result<StartSearchResponse, String> {
+    let search_id = format!(
+        "search-{}",
+        SEARCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let search = prepare_search(request);
+
+    println!(
+        "[BACKEND:STREAM] {} - query: '{}', page: {}, sources: {}",
+        search_id,
+        search.params.query,
+        search.params.page,
+        search.sources.join(",")
+    );
+
+    let degraded = degraded_sources(&search.sources);
+    let seed = search.response_seed();
+    let query_errors = search.query_errors();
+    let mut rx = sources::spawn_search(
+        &search.sources,
+        &search.params,
+        &search.pages,
+        search.timeout,
+    );
+
+    // lock is held until the handle is stored so the task can't remove itself first
+    let mut active = ACTIVE_SEARCHES.lock().unwrap();
+    let task_id = search_id.clone();
+    let dedup_similar = load_settings().dedup_similar;
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut seen = HashSet::new();
+        let mut seen_hashes = dedup::SeenHashes::default();
+        let mut total_items = 0;
+        let mut error_count = 0;
+        let mut cursors = search.empty_cursors();
+        let mut hidden = search.hidden();
+
+        while let Some(outcome) = rx.recv().await {
+            cursors.insert(outcome.source.clone(), encode_cursor(outcome.next_page));
+            hidden.merge(outcome.hidden.clone());
+            match outcome.result {
+                Ok(mut items) => {
+                    items.retain(|item| seen.insert(item.id.clone()));
+                    if dedup_similar {
+                        items = seen_hashes.filter(items).await;
+                    }
+                    // salted per source, batches arrive in whatever order the sources finish
+                    ranking::rank(
+                        &mut items,
+                        search.ranking,
+                        search.seed,
+                        &format!("{}:{}", search.page_of(&outcome.source), outcome.source),
+                    );
+                    total_items += items.len();
+
+                    let cache_age_secs = outcome.stale.as_ref().map(|stale| stale.age.as_secs());
+                    let emitted = emit_if_active(
+                        &app,
+                        &task_id,
+                        "search:partial",
+                        SearchPartialEvent {
+                            search_id: task_id.clone(),
+                            source: outcome.source,
+                            items,
+                            stale: cache_age_secs.is_some(),
+                            cache_age_secs,
+                            hidden: outcome.hidden.into_option(),
+                        },
+                    );
+                    if !emitted {
+                        return;
+                    }
+                    if let Some(stale) = outcome.stale {
+                        error_count += 1;
+                        let event = SearchSourceErrorEvent {
+                            search_id: task_id.clone(),
+                            error: stale.error,
+                        };
+                        if !emit_if_active(&app, &task_id, "search:source-error", event) {
+                            return;
+                        }
+                    }
+                }
+                Err(error) => {
+                    error_count += 1;
+                    let event = SearchSourceErrorEvent {
+                        search_id: task_id.clone(),
+                        error,
+                    };
+                    if !emit_if_active(&app, &task_id, "search:source-error", event) {
+                        return;
+                    }
+                }
+            }
+        }
+
+        println!(
+            "[BACKEND:STREAM] {} done - {} items, {} errors",
+            task_id, total_items, error_count
+        );
+        // unregistered and emitted under one lock, a cancel either wins or finds nothing
+        let mut active = ACTIVE_SEARCHES.lock().unwrap();
+        if active.remove(&task_id).is_some() {
+            let _ = app.emit(
+                "search:done",
+                SearchDoneEvent {
+                    search_id: task_id,
+                    total_items,
+                    error_count,
+                    cancelled: false,
+                    has_more: cursors.values().any(Option::is_some),
+                    cursors,
+                    hidden: hidden.into_option(),
+                },
+            );
+        }
+    });
+    active.insert(search_id.clone(), handle);
+
+    Ok(StartSearchResponse {
+        success: true,
+        search_id,
+        degraded,
+        seed,
+        query_errors,
     })
 }
 
+/// stop a streaming search. the cancelled `search:done` goes out before this returns
+/// and nothing else from the search after it (see `emit_if_active`)
 #[tauri::command]
-pub async fn fetch_live2d(query: Option<String>) -> Result<SearchResponse, String> {
-    match scrape_moewalls(query.as_deref(), 50, true, 1).await {
-        Ok(items) => Ok(SearchResponse {
-            success: true,
-            items,
-            errors: None,
-        }),
-        Err(e) => Ok(SearchResponse {
+pub async fn cancel_search(app: AppHandle, search_id: String) -> Result<WallpaperResponse, String> {
+    let handle = ACTIVE_SEARCHES.lock().unwrap().remove(&search_id);
+
+    match handle {
+        Some(handle) => {
+            // aborting drops the receiver, which also stops the per-source tasks
+            handle.abort();
+            println!("[BACKEND:STREAM] {} cancelled", search_id);
+            let _ = app.emit(
+                "search:done",
+                SearchDoneEvent {
+                    search_id,
+                    total_items: 0,
+                    error_count: 0,
+                    cancelled: true,
+                    cursors: HashMap::new(),
+                    has_more: false,
+                    hidden: None,
+                },
+            );
+            Ok(WallpaperResponse {
+                success: true,
+                message: Some("search cancelled".to_string()),
+                error: None,
+            })
+        }
+        None => Ok(WallpaperResponse {
             success: false,
-            items: Vec::new(),
-            errors: Some(vec![e]),
+            message: None,
+            error: Some(format!("no running search with id {}", search_id)),
         }),
     }
 }
 
 #[tauri::command]
-pub async fn resolve_wallpaperflare_highres(
-    detail_url: String,
-) -> Result<crate::models::ResolveHighResResponse, String> {
-    println!("info: resolving high-res for: {}", detail_url);
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x84) AppleWebKit/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    match resolve_wallpaperflare_download(&detail_url, &client).await {
-        Ok((high_res_url, _, _)) => {
-            println!("OK resolved to: {}", high_res_url);
-            Ok(crate::models::ResolveHighResResponse {
+pub async fn list_sources() -> Result<SourcesResponse, String> {
+    Ok(SourcesResponse {
+        success: true,
+        sources: sources::all().iter().map(|source| source.info()).collect(),
+        errors: None,
+    })
+}
+
+/// re-read the site definitions in `<app data>/sites` without restarting
+#[tauri::command]
+pub async fn reload_sources() -> Result<SourcesResponse, String> {
+    let errors = sources::reload_sites();
+    Ok(SourcesResponse {
+        success: errors.is_empty(),
+        sources: sources::all().iter().map(|source| source.info()).collect(),
+        errors: if errors.is_empty() { None } else { Some(errors) },
+    })
+}
+
+#[tauri::command]
+pub async fn fetch_live2d(query: Option<String>) -> Result<SearchResponse, String> {
+    let source = sources::get("moewalls").ok_or("moewalls source is not registered")?;
+    let params = SearchParams {
+        query: query.unwrap_or_default(),
+        page: 1,
+        limit: 50,
+        purity: "100".to_string(),
+        include_videos: true,
+        ..Default::default()
+    };
+
+    match source.search(&params).await {
+        Ok(mut page) => {
+            let hidden = Policy::current().apply(&mut page.items).await;
+            Ok(SearchResponse {
                 success: true,
-                url: Some(high_res_url),
-                url4k: None,
-                error: None,
+                items: page.items,
+                hidden: hidden.into_option(),
+                ..Default::default()
             })
         }
-        Err(e) => {
-            println!("error: failed to resolve: {}", e);
-            Ok(crate::models::ResolveHighResResponse {
-                success: false,
-                url: None,
-                url4k: None,
-                error: Some(e),
+        Err(e) => Ok(SearchResponse {
+            errors: Some(vec![SourceError::new("moewalls", &e)]),
+            ..Default::default()
+        }),
+    }
+}
+
+/// toplist periods, the same ones wallhaven takes
+const BROWSE_RANGES: &[&str] = &["1d", "3d", "1w", "1M", "3M", "6M", "1y"];
+
+/// one source's native listing (latest, toplist, random, featured, hot) without a query,
+/// for the home page. `range` is the toplist period where the source has one, `purity`
+/// and `limit` work like a search's `purity` and `limit_per_source`
+#[tauri::command]
+pub async fn browse_wallpapers(
+    source: String,
+    mode: BrowseMode,
+    range: Option<String>,
+    page: Option<u32>,
+    purity: Option<String>,
+    limit: Option<usize>,
+) -> Result<SearchResponse, String> {
+    let browsed = sources::get(&source).ok_or_else(|| format!("unknown source: {}", source))?;
+    if !browsed.browse_modes().contains(&mode) {
+        return Err(format!("{} has no {} listing", source, mode.as_str()));
+    }
+    let policy = Policy::current();
+    if let Some(reason) = policy.source_block_reason(browsed.as_ref()) {
+        return Err(reason);
+    }
+    if let Some(range) = range.as_deref().filter(|range| !BROWSE_RANGES.contains(range)) {
+        return Err(format!(
+            "unknown range {}, expected one of {}",
+            range,
+            BROWSE_RANGES.join(", ")
+        ));
+    }
+
+    let settings = load_settings();
+    let page = page.unwrap_or(1).max(1);
+    let params = SearchParams {
+        page,
+        limit: limit.unwrap_or(30),
+        // same cap as a search, sfw unless asked for more
+        purity: policy.cap_purity(purity.as_deref().unwrap_or("100")),
+        include_videos: true,
+        ..Default::default()
+    };
+    println!(
+        "[BACKEND:BROWSE] {} - {} {}, page {}",
+        source,
+        mode.as_str(),
+        range.as_deref().unwrap_or(""),
+        page
+    );
+
+    let result = match browsed.browse(mode, range.as_deref(), &params).await {
+        // ran past the last page, that's the end of the listing rather than an error
+        Err(ScrapeError::Empty) if page > 1 => Ok(SearchPage {
+            items: Vec::new(),
+            has_more: false,
+        }),
+        result => result,
+    };
+    match result {
+        Ok(mut listing) => {
+            let hidden = policy.apply(&mut listing.items).await;
+            if settings.probe_dimensions {
+                // no size filters on a listing, so no detail pages either
+                probe::fill_dimensions(&browsed, &mut listing.items, false).await;
+            }
+            let next_page = listing.has_more.then_some(page + 1);
+            Ok(SearchResponse {
+                success: !listing.items.is_empty(),
+                items: listing.items,
+                degraded: degraded_sources(std::slice::from_ref(&source)),
+                cursors: HashMap::from([(source, encode_cursor(next_page))]),
+                has_more: next_page.is_some(),
+                hidden: hidden.into_option(),
+                ..Default::default()
             })
         }
+        Err(e) => Ok(SearchResponse {
+            errors: Some(vec![SourceError::new(&source, &e)]),
+            degraded: degraded_sources(std::slice::from_ref(&source)),
+            ..Default::default()
+        }),
     }
 }
 
+/// list one feed on its own, newest entries first as the feed orders them
 #[tauri::command]
-pub async fn resolve_motionbgs_video(detail_url: String) -> Result<crate::models::ResolveHighResResponse, String> {
-    println!("info: RESOLVING motionBg video: {}", detail_url);
+pub async fn fetch_feed(feed_id: String, query: Option<String>) -> Result<SearchResponse, String> {
+    let source = sources::get(&feed_id).ok_or_else(|| format!("unknown feed: {}", feed_id))?;
+    let params = SearchParams {
+        query: query.unwrap_or_default(),
+        page: 1,
+        limit: 100,
+        purity: "100".to_string(),
+        include_videos: true,
+        ..Default::default()
+    };
 
-    match scrape_motionbgs_detail(&detail_url).await {
-        Ok((video_url, video_url_4k)) => {
-            println!("ok: found video url: {}", video_url);
-            Ok(crate::models::ResolveHighResResponse {
+    match source.search(&params).await {
+        Ok(mut page) => {
+            let hidden = Policy::current().apply(&mut page.items).await;
+            Ok(SearchResponse {
                 success: true,
-                url: Some(video_url),
-                url4k: video_url_4k,
-                error: None,
+                items: page.items,
+                hidden: hidden.into_option(),
+                ..Default::default()
             })
         }
+        Err(e) => Ok(SearchResponse {
+            errors: Some(vec![SourceError::new(&feed_id, &e)]),
+            ..Default::default()
+        }),
+    }
+}
+
+/// run the registered resolver for `source` and wrap it for the frontend
+async fn resolve_with(source_id: &str, detail_url: &str) -> ResolveHighResResponse {
+    let Some(source) = sources::get(source_id) else {
+        return ResolveHighResResponse {
+            success: false,
+            url: None,
+            url4k: None,
+            error: Some(format!("unknown source: {}", source_id)),
+            error_kind: None,
+            variants: None,
+            width: None,
+            height: None,
+        };
+    };
+
+    match source.resolve(detail_url).await {
+        Ok(ResolvedMedia {
+            url,
+            url4k,
+            variants,
+            width,
+            height,
+        }) => {
+            println!("ok: {} resolved to: {}", source_id, url);
+            // sites that don't print the size get it from the file's header
+            let (width, height) = match (width, height) {
+                (Some(width), Some(height)) => (Some(width), Some(height)),
+                _ if sources::url_media_type(&url) != Some("video") => probe::probe_url(&url)
+                    .await
+                    .map_or((None, None), |(width, height)| (Some(width), Some(height))),
+                _ => (None, None),
+            };
+            ResolveHighResResponse {
+                success: true,
+                url: Some(url),
+                url4k,
+                error: None,
+                error_kind: None,
+                variants: if variants.is_empty() {
+                    None
+                } else {
+                    Some(variants)
+                },
+                width,
+                height,
+            }
+        }
         Err(e) => {
             println!("error: failed to resolve: {}", e);
-            Ok(crate::models::ResolveHighResResponse {
+            ResolveHighResResponse {
                 success: false,
                 url: None,
                 url4k: None,
-                error: Some(e),
+                error: Some(e.to_string()),
+                error_kind: Some(e.kind().to_string()),
+                variants: None,
+                width: None,
+                height: None,
+            }
+        }
+    }
+}
+
+/// tags, uploader, resolution, file size and license from the item's detail page,
+/// cached per item id so opening the same wallpaper again doesn't refetch
+#[tauri::command]
+pub async fn get_wallpaper_details(item: WallpaperItem) -> Result<WallpaperDetailsResponse, String> {
+    println!("info: fetching details for {} item: {}", item.source, item.id);
+    match details::get(&item).await {
+        Ok(details) => Ok(WallpaperDetailsResponse {
+            success: true,
+            details: Some(details),
+            error: None,
+            error_kind: None,
+        }),
+        Err(e) => {
+            println!("error: failed to get details: {}", e);
+            Ok(WallpaperDetailsResponse {
+                success: false,
+                details: None,
+                error: Some(e.to_string()),
+                error_kind: Some(e.kind().to_string()),
             })
         }
     }
 }
 
+/// generic resolver entry point, works for any source with `capabilities.resolve`
+#[tauri::command]
+pub async fn resolve_wallpaper(
+    source: String,
+    detail_url: String,
+) -> Result<ResolveHighResResponse, String> {
+    println!("info: resolving {} item: {}", source, detail_url);
+    Ok(resolve_with(&source, &detail_url).await)
+}
+
+#[tauri::command]
+pub async fn resolve_wallpaperflare_highres(
+    detail_url: String,
+) -> Result<ResolveHighResResponse, String> {
+    println!("info: resolving high-res for: {}", detail_url);
+    Ok(resolve_with("wallpaperflare", &detail_url).await)
+}
+
+#[tauri::command]
+pub async fn resolve_motionbgs_video(detail_url: String) -> Result<ResolveHighResResponse, String> {
+    println!("info: RESOLVING motionBg video: {}", detail_url);
+    Ok(resolve_with("motionbgs", &detail_url).await)
+}
+
+/// full video behind a moewalls post instead of the preview clip, `variants` lists
+/// every resolution the post offers
+#[tauri::command]
+pub async fn resolve_moewalls_video(detail_url: String) -> Result<ResolveHighResResponse, String> {
+    println!("info: resolving moewalls video: {}", detail_url);
+    Ok(resolve_with("moewalls", &detail_url).await)
+}
@@ -1,21 +1,18 @@
 /// settings management commands (will get worked on later when app is more stable)
+use crate::http;
 use crate::models::*;
+use crate::sources;
 use crate::storage::get_settings_file;
+use tauri::AppHandle;
 
 #[tauri::command]
 pub async fn get_settings() -> Result<SettingsResponse, String> {
     let settings_file = get_settings_file()?;
 
     if !settings_file.exists() {
-        let default_settings = AppSettings {
-            audio_enabled: false,
-            live_wallpaper_enabled: true,
-            video_player: "wmf".to_string(),
-            mpv_path: None,
-        };
         return Ok(SettingsResponse {
             success: true,
-            settings: Some(default_settings),
+            settings: Some(AppSettings::default()),
             error: None,
         });
     }
@@ -42,12 +39,16 @@ pub async fn get_settings() -> Result<SettingsResponse, String> {
 }
 
 #[tauri::command]
-pub async fn save_settings(settings: AppSettings) -> Result<SettingsResponse, String> {
+pub async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<SettingsResponse, String> {
     let settings_file = get_settings_file()?;
     let json = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("failed to serialize settings: {}", e))?;
 
     std::fs::write(&settings_file, json).map_err(|e| format!("failed to write settings: {}", e))?;
+    http::configure(&settings);
+    // booru, feed and local sources live in settings
+    sources::reload_sites();
+    sources::local::allow_asset_access(&app);
 
     Ok(SettingsResponse {
         success: true,
@@ -1,5 +1,7 @@
 /// Wallpaper management commands (static images, video wallpapers, user wallpapers)
+use crate::http;
 use crate::models::*;
+use crate::sources;
 use crate::storage::*;
 use crate::video_wallpaper::*;
 use tauri::AppHandle;
@@ -11,17 +13,20 @@ pub async fn download_wallpaper(
     app: AppHandle,
     url: String,
     suggested_filename: String,
+    item: Option<WallpaperItem>,
 ) -> Result<DownloadResponse, String> {
+    // listing urls are often thumbnails, the resolver knows where the original is
+    let url = match &item {
+        Some(item) => sources::full_resolution_url(item, &url).await,
+        None => url,
+    };
     println!("[download] Starting download from: {}", url);
 
-    // download the file first
-    let client = reqwest::Client::builder()
-        .user_agent("LaxentaInc/1.0")
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
-    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    // download the file first (or just read it, for local source items)
+    let bytes = match local_path_from_url(&url)? {
+        Some(path) => std::fs::read(&path).map_err(|e| e.to_string())?,
+        None => http::get_bytes(http::get(&url)).await?,
+    };
 
     println!("[download] Downloaded {} bytes", bytes.len());
 
@@ -62,13 +67,12 @@ pub async fn download_wallpaper(
 
 /// download image from URL to cache
 async fn download_image(url: &str) -> Result<std::path::PathBuf, String> {
-    let client = reqwest::Client::builder()
-        .user_agent("LaxentaInc/1.0")
-        .build()
-        .map_err(|e| e.to_string())?;
+    // local source items are already on disk
+    if let Some(path) = local_path_from_url(url)? {
+        return Ok(path);
+    }
 
-    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
-    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    let bytes = http::get_bytes(http::get(url)).await?;
 
     let cache_dir = get_cache_dir()?;
     let extension = url
@@ -93,7 +97,14 @@ async fn download_image(url: &str) -> Result<std::path::PathBuf, String> {
 }
 
 #[tauri::command]
-pub async fn set_wallpaper(image_url: String) -> Result<WallpaperResponse, String> {
+pub async fn set_wallpaper(
+    image_url: String,
+    item: Option<WallpaperItem>,
+) -> Result<WallpaperResponse, String> {
+    let image_url = match &item {
+        Some(item) => sources::full_resolution_url(item, &image_url).await,
+        None => image_url,
+    };
     let file_path = match download_image(&image_url).await {
         Ok(path) => path,
         Err(e) => {
@@ -204,7 +215,13 @@ pub async fn clear_cache() -> Result<ClearCacheResponse, String> {
 pub async fn set_video_wallpaper(
     app: AppHandle,
     video_url: String,
+    item: Option<WallpaperItem>,
 ) -> Result<WallpaperResponse, String> {
+    // listings link preview clips, the resolver finds the full quality file
+    let video_url = match &item {
+        Some(item) => sources::full_resolution_url(item, &video_url).await,
+        None => video_url,
+    };
     println!("[main] setting video wallpaper: {}", video_url);
 
     let video_path = match download_video(&video_url).await {
@@ -303,19 +320,13 @@ pub async fn list_user_wallpapers() -> Result<UserWallpapersResponse, String> {
                     let path = entry.path();
                     let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
 
-                    if matches!(extension, "mp4" | "mkv" | "jpg" | "jpeg" | "png" | "gif") {
+                    if let Some(media_type) = media_type_for_extension(extension) {
                         let name = path
                             .file_name()
                             .and_then(|n| n.to_str())
                             .unwrap_or("Unknown")
                             .to_string();
 
-                        let media_type = if matches!(extension, "mp4" | "mkv") {
-                            "video"
-                        } else {
-                            "image"
-                        };
-
                         let added_at = metadata
                             .created()
                             .or_else(|_| metadata.modified())
@@ -0,0 +1,413 @@
+/// near-duplicate detection across sources. the same picture shows up on several sites
+/// under different ids, so thumbnails get a 64 bit difference hash (dhash) and items whose
+/// hashes are only a few bits apart are collapsed into the highest resolution one.
+/// hashes are cached per thumbnail url in AppData, a thumbnail is only downloaded once
+use crate::disk_cache::DiskCache;
+use crate::http;
+use crate::models::WallpaperItem;
+use image::imageops::FilterType;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// max differing bits for two hashes to count as the same picture
+const MAX_DISTANCE: u32 = 6;
+
+/// thumbnails not hashed by then are left out of the comparison, search shouldn't wait on them
+const HASH_BUDGET: Duration = Duration::from_secs(6);
+
+const CONCURRENT_DOWNLOADS: usize = 8;
+
+lazy_static::lazy_static! {
+    /// thumbnail url -> dhash
+    static ref HASHES: DiskCache<u64> =
+        DiskCache::new("thumbnail_hashes.json", "[DEDUP]", "thumbnail hash", 20_000);
+}
+
+/// difference hash: shrink to 9x8 grayscale, one bit per "is the next pixel brighter"
+fn dhash(bytes: &[u8]) -> Option<u64> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let small = image::imageops::resize(&image.to_luma8(), 9, 8, FilterType::Triangle);
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | (right > left) as u64;
+        }
+    }
+    Some(hash)
+}
+
+async fn hash_thumbnail(url: String) -> Option<u64> {
+    let bytes = http::get_bytes(http::get(&url)).await.ok()?;
+    tokio::task::spawn_blocking(move || dhash(&bytes))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// remote thumbnail to hash, local files and videos without a poster have none
+fn thumbnail(item: &WallpaperItem) -> Option<&str> {
+    item.thumbnail_url
+        .as_deref()
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+}
+
+/// dhash per item (same order), from the cache or by downloading the thumbnail.
+/// anything not done within `HASH_BUDGET` or that fails to decode is `None`
+pub async fn thumbnail_hashes(items: &[WallpaperItem]) -> Vec<Option<u64>> {
+    thumbnail_hashes_until(items, tokio::time::Instant::now() + HASH_BUDGET).await
+}
+
+/// `thumbnail_hashes` with downloads cut off at `deadline`, past it only cached hashes count
+async fn thumbnail_hashes_until(
+    items: &[WallpaperItem],
+    deadline: tokio::time::Instant,
+) -> Vec<Option<u64>> {
+    let mut hashes = HASHES.get_many(items.iter().filter_map(thumbnail));
+    let mut missing: Vec<String> = Vec::new();
+    for url in items.iter().filter_map(thumbnail) {
+        if !hashes.contains_key(url) && !missing.iter().any(|other| other == url) {
+            missing.push(url.to_string());
+        }
+    }
+
+    if !missing.is_empty() && deadline > tokio::time::Instant::now() {
+        let started = std::time::Instant::now();
+        let semaphore = Arc::new(Semaphore::new(CONCURRENT_DOWNLOADS));
+        let mut tasks = tokio::task::JoinSet::new();
+        for url in missing.iter().cloned() {
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let hash = hash_thumbnail(url.clone()).await?;
+                Some((url, hash))
+            });
+        }
+
+        let mut new = HashMap::new();
+        while let Ok(Some(result)) = tokio::time::timeout_at(deadline, tasks.join_next()).await {
+            if let Ok(Some((url, hash))) = result {
+                new.insert(url, hash);
+            }
+        }
+        // dropping the set aborts whatever is still downloading
+        drop(tasks);
+
+        println!(
+            "[DEDUP] hashed {} of {} new thumbnails in {}ms",
+            new.len(),
+            missing.len(),
+            started.elapsed().as_millis()
+        );
+        if !new.is_empty() {
+            hashes.extend(new.clone());
+            HASHES.insert(new);
+        }
+    }
+
+    items
+        .iter()
+        .map(|item| thumbnail(item).and_then(|url| hashes.get(url).copied()))
+        .collect()
+}
+
+pub fn is_near(a: u64, b: u64) -> bool {
+    (a ^ b).count_ones() <= MAX_DISTANCE
+}
+
+fn pixels(item: &WallpaperItem) -> u64 {
+    item.width.unwrap_or(0) as u64 * item.height.unwrap_or(0) as u64
+}
+
+/// the mirror entry stored under `original.alternates`
+fn alternate(item: &WallpaperItem) -> Value {
+    json!({
+        "id": item.id,
+        "source": item.source,
+        "imageUrl": item.image_url,
+        "thumbnailUrl": item.thumbnail_url,
+        "detailUrl": item.detail_url,
+        "width": item.width,
+        "height": item.height,
+    })
+}
+
+/// add the collapsed items to `original.alternates`, wrapping non-object originals
+fn attach_alternates(item: &mut WallpaperItem, alternates: Vec<Value>) {
+    let mut original = match item.original.take() {
+        Some(Value::Object(object)) => object,
+        Some(other) => {
+            let mut object = serde_json::Map::new();
+            object.insert("value".to_string(), other);
+            object
+        }
+        None => serde_json::Map::new(),
+    };
+    original.insert("alternates".to_string(), Value::Array(alternates));
+    item.original = Some(Value::Object(original));
+}
+
+/// collapse items from different sources that look the same. each group keeps the
+/// position of its first item, the highest resolution variant wins (first one on ties)
+/// and the rest end up in its `original.alternates`. thumbnails are hashed until
+/// `deadline` at the latest, so a search still answers within its timeout
+pub async fn collapse_similar(
+    items: Vec<WallpaperItem>,
+    deadline: tokio::time::Instant,
+) -> Vec<WallpaperItem> {
+    let deadline = deadline.min(tokio::time::Instant::now() + HASH_BUDGET);
+    let hashes = thumbnail_hashes_until(&items, deadline).await;
+    collapse(items, &hashes)
+}
+
+/// `collapse_similar` with the hashes worked out, `hashes[i]` belongs to `items[i]`
+fn collapse(items: Vec<WallpaperItem>, hashes: &[Option<u64>]) -> Vec<WallpaperItem> {
+    // group index per item, items join the first earlier group they're near
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_hashes: Vec<(u64, String, usize)> = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let media_type = item.media_type.as_deref().unwrap_or("image");
+        let group = hashes[index].and_then(|hash| {
+            group_hashes.iter().find_map(|(other, other_type, group)| {
+                let same_source = groups[*group]
+                    .iter()
+                    .any(|member| items[*member].source == item.source);
+                (is_near(hash, *other) && other_type == media_type && !same_source)
+                    .then_some(*group)
+            })
+        });
+
+        match group {
+            Some(group) => groups[group].push(index),
+            None => {
+                if let Some(hash) = hashes[index] {
+                    group_hashes.push((hash, media_type.to_string(), groups.len()));
+                }
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    let mut slots: Vec<Option<WallpaperItem>> = items.into_iter().map(Some).collect();
+    let mut result = Vec::with_capacity(groups.len());
+    let mut collapsed = 0;
+    for group in groups {
+        let best = *group
+            .iter()
+            .max_by_key(|index| {
+                (
+                    pixels(slots[**index].as_ref().unwrap()),
+                    std::cmp::Reverse(**index),
+                )
+            })
+            .unwrap();
+        let mut winner = slots[best].take().unwrap();
+
+        let alternates: Vec<Value> = group
+            .iter()
+            .filter(|index| **index != best)
+            .filter_map(|index| slots[*index].take())
+            .map(|item| alternate(&item))
+            .collect();
+        if !alternates.is_empty() {
+            collapsed += alternates.len();
+            attach_alternates(&mut winner, alternates);
+        }
+        result.push(winner);
+    }
+
+    if collapsed > 0 {
+        println!("[DEDUP] collapsed {} near-duplicates", collapsed);
+    }
+    result
+}
+
+/// what a streaming search already emitted, batches that arrive later can't replace those
+/// so their near-duplicates are dropped instead
+#[derive(Debug, Default)]
+pub struct SeenHashes {
+    seen: Vec<(u64, String, String)>,
+}
+
+impl SeenHashes {
+    pub async fn filter(&mut self, items: Vec<WallpaperItem>) -> Vec<WallpaperItem> {
+        let hashes = thumbnail_hashes(&items).await;
+        let before = items.len();
+
+        let mut kept = Vec::with_capacity(before);
+        for (item, hash) in items.into_iter().zip(hashes) {
+            let media_type = item
+                .media_type
+                .clone()
+                .unwrap_or_else(|| "image".to_string());
+            if let Some(hash) = hash {
+                let duplicate = self.seen.iter().any(|(other, source, other_type)| {
+                    is_near(hash, *other) && *source != item.source && *other_type == media_type
+                });
+                if duplicate {
+                    continue;
+                }
+                self.seen.push((hash, item.source.clone(), media_type));
+            }
+            kept.push(item);
+        }
+
+        if kept.len() < before {
+            println!(
+                "[DEDUP] dropped {} near-duplicates of earlier results",
+                before - kept.len()
+            );
+        }
+        kept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, ImageFormat, Luma, Rgb};
+    use std::io::Cursor;
+
+    fn encode<P, C>(image: ImageBuffer<P, C>, format: ImageFormat) -> Vec<u8>
+    where
+        P: image::PixelWithColorType,
+        [P::Subpixel]: image::EncodableLayout,
+        C: std::ops::Deref<Target = [P::Subpixel]>,
+    {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    /// brightness rising left to right, or falling when `reversed`
+    fn gradient(width: u32, height: u32, reversed: bool) -> Vec<u8> {
+        let image = ImageBuffer::from_fn(width, height, |x, _| {
+            let x = if reversed { width - 1 - x } else { x };
+            Luma([(x * 255 / (width - 1)) as u8])
+        });
+        encode(image, ImageFormat::Png)
+    }
+
+    /// a few blocks of color, something like a photo's layout
+    fn scene(width: u32, height: u32) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(width, height, |x, y| {
+            let (fx, fy) = (x as f32 / width as f32, y as f32 / height as f32);
+            if fy < 0.4 {
+                Rgb([90, 150, (200.0 + 50.0 * fx) as u8])
+            } else if fx < 0.3 || (fx > 0.6 && fx < 0.8) {
+                Rgb([40, (120.0 - 80.0 * fy) as u8, 30])
+            } else {
+                Rgb([(160.0 * fx) as u8, 110, 60])
+            }
+        })
+    }
+
+    fn item(id: &str, source: &str, size: (u32, u32)) -> WallpaperItem {
+        WallpaperItem {
+            id: id.to_string(),
+            source: source.to_string(),
+            title: None,
+            image_url: format!("https://{}.example.com/{}.jpg", source, id),
+            thumbnail_url: None,
+            media_type: Some("image".to_string()),
+            width: Some(size.0),
+            height: Some(size.1),
+            tags: None,
+            detail_url: None,
+            original: None,
+        }
+    }
+
+    fn ids(items: &[WallpaperItem]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    #[test]
+    fn dhash_of_gradients() {
+        // every pixel brighter than the one on its left
+        assert_eq!(dhash(&gradient(90, 80, false)), Some(u64::MAX));
+        assert_eq!(dhash(&gradient(90, 80, true)), Some(0));
+        assert_eq!(dhash(b"not an image"), None);
+        assert_eq!(dhash(&[]), None);
+    }
+
+    #[test]
+    fn resized_and_recompressed_copies_are_near() {
+        let original = dhash(&encode(scene(640, 360), ImageFormat::Png)).unwrap();
+        let thumbnail = image::imageops::resize(&scene(640, 360), 300, 169, FilterType::Lanczos3);
+        let thumbnail = dhash(&encode(thumbnail, ImageFormat::Jpeg)).unwrap();
+        assert!(
+            is_near(original, thumbnail),
+            "{:016x} {:016x}",
+            original,
+            thumbnail
+        );
+
+        let flipped = image::imageops::flip_horizontal(&scene(640, 360));
+        let flipped = dhash(&encode(flipped, ImageFormat::Png)).unwrap();
+        assert!(!is_near(original, flipped));
+    }
+
+    #[test]
+    fn near_is_a_few_bits() {
+        assert!(is_near(0, 0));
+        assert!(is_near(0, 0b11_1111));
+        assert!(!is_near(0, 0b111_1111));
+        assert!(!is_near(0, u64::MAX));
+    }
+
+    #[test]
+    fn collapse_keeps_the_biggest_copy_in_the_first_slot() {
+        let items = vec![
+            item("a1", "wallhaven", (1920, 1080)),
+            item("b1", "wallpaperflare", (800, 600)),
+            item("c1", "wallpapers", (3840, 2160)),
+            item("a2", "wallhaven", (3840, 2160)),
+            item("d1", "motionbgs", (1920, 1080)),
+        ];
+        let hashes = [
+            Some(0xff00),
+            Some(0x1234_5678_9abc_def0),
+            Some(0xff01),
+            // same source as a1, never merged with it
+            Some(0xff00),
+            None,
+        ];
+        let result = collapse(items, &hashes);
+
+        assert_eq!(ids(&result), ["c1", "b1", "a2", "d1"]);
+        let alternates = result[0].original.as_ref().unwrap()["alternates"]
+            .as_array()
+            .unwrap();
+        assert_eq!(alternates.len(), 1);
+        assert_eq!(alternates[0]["id"], "a1");
+        assert!(result[1].original.is_none());
+    }
+
+    #[test]
+    fn collapse_keeps_videos_and_images_apart() {
+        let mut video = item("v", "motionbgs", (3840, 2160));
+        video.media_type = Some("video".to_string());
+        let items = vec![item("i", "wallhaven", (1920, 1080)), video];
+        let result = collapse(items, &[Some(7), Some(7)]);
+        assert_eq!(ids(&result), ["i", "v"]);
+    }
+
+    #[tokio::test]
+    async fn past_deadline_downloads_nothing() {
+        let mut remote = item("r", "wallhaven", (1, 1));
+        remote.thumbnail_url = Some("https://thumbs.invalid/r.jpg".to_string());
+        let mut local = item("l", "local", (1, 1));
+        local.thumbnail_url = Some("file:///tmp/l.jpg".to_string());
+
+        let started = std::time::Instant::now();
+        let hashes = thumbnail_hashes_until(&[remote, local], tokio::time::Instant::now()).await;
+        assert_eq!(hashes, [None, None]);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,45 @@
+/// lazy detail enrichment. listings only carry a title and a thumbnail, the rest (tags,
+/// uploader, size, license) is fetched from the detail page when an item is opened and
+/// cached per item id in AppData, so opening it again costs nothing
+use crate::disk_cache::DiskCache;
+use crate::error::ScrapeError;
+use crate::models::{WallpaperDetails, WallpaperItem};
+use crate::sources;
+use std::collections::{HashMap, HashSet};
+
+lazy_static::lazy_static! {
+    /// item id -> details
+    static ref DETAILS: DiskCache<WallpaperDetails> =
+        DiskCache::new("wallpaper_details.json", "[DETAILS]", "details", 5_000);
+}
+
+/// listing data fills whatever the detail page didn't have, listing tags go last
+fn merge_item(mut details: WallpaperDetails, item: &WallpaperItem) -> WallpaperDetails {
+    details.id = item.id.clone();
+    details.source = item.source.clone();
+    if details.width.is_none() || details.height.is_none() {
+        details.width = item.width;
+        details.height = item.height;
+    }
+
+    let mut seen: HashSet<String> = details.tags.iter().map(|tag| tag.to_lowercase()).collect();
+    for tag in item.tags.iter().flatten() {
+        if seen.insert(tag.to_lowercase()) {
+            details.tags.push(tag.clone());
+        }
+    }
+    details
+}
+
+/// details for `item`, from the cache or its source's detail page
+pub async fn get(item: &WallpaperItem) -> Result<WallpaperDetails, ScrapeError> {
+    if let Some(details) = DETAILS.get_many([item.id.as_str()]).remove(&item.id) {
+        return Ok(details);
+    }
+
+    let source = sources::get(&item.source)
+        .ok_or_else(|| ScrapeError::ParseChanged(format!("unknown source: {}", item.source)))?;
+    let details = merge_item(source.details(item).await?, item);
+    DETAILS.insert(HashMap::from([(details.id.clone(), details.clone())]));
+    Ok(details)
+}
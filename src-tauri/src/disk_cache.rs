@@ -0,0 +1,103 @@
+/// keyed lookup tables kept in AppData (thumbnail hashes, image sizes, palettes, item details).
+/// lookups only touch the map in memory and only copy the entries asked for, new entries
+/// are written out on a blocking thread so a search never waits on the disk
+use crate::storage::get_app_data_dir;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+pub struct DiskCache<V> {
+    /// file name in AppData
+    file: &'static str,
+    /// log prefix and what the entries are, e.g. ("[DEDUP]", "thumbnail hash")
+    tag: &'static str,
+    what: &'static str,
+    /// the cache starts over past this many entries instead of growing forever
+    max_entries: usize,
+    /// loaded on first use
+    entries: RwLock<Option<HashMap<String, V>>>,
+    /// a write is already queued, it picks up whatever was inserted before it runs
+    flush_queued: AtomicBool,
+}
+
+impl<V> DiskCache<V>
+where
+    V: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    pub fn new(
+        file: &'static str,
+        tag: &'static str,
+        what: &'static str,
+        max_entries: usize,
+    ) -> Self {
+        Self {
+            file,
+            tag,
+            what,
+            max_entries,
+            entries: RwLock::new(None),
+            flush_queued: AtomicBool::new(false),
+        }
+    }
+
+    fn load(&self) -> HashMap<String, V> {
+        get_app_data_dir()
+            .ok()
+            .and_then(|dir| std::fs::read_to_string(dir.join(self.file)).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// cached entries for `keys`, keys without one are left out
+    pub fn get_many<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> HashMap<String, V> {
+        let pick = |entries: &HashMap<String, V>| {
+            keys.into_iter()
+                .filter_map(|key| Some((key.to_string(), entries.get(key)?.clone())))
+                .collect()
+        };
+
+        if let Some(entries) = self.entries.read().unwrap().as_ref() {
+            return pick(entries);
+        }
+        let mut loaded = self.entries.write().unwrap();
+        pick(loaded.get_or_insert_with(|| self.load()))
+    }
+
+    /// add entries and queue a write, a full cache starts over first
+    pub fn insert(&'static self, new: HashMap<String, V>) {
+        if new.is_empty() {
+            return;
+        }
+        {
+            let mut loaded = self.entries.write().unwrap();
+            let entries = loaded.get_or_insert_with(|| self.load());
+            if entries.len() + new.len() > self.max_entries {
+                println!("{} {} cache is full, starting over", self.tag, self.what);
+                entries.clear();
+            }
+            entries.extend(new);
+        }
+
+        if !self.flush_queued.swap(true, Ordering::AcqRel) {
+            tokio::task::spawn_blocking(move || self.flush());
+        }
+    }
+
+    fn flush(&self) {
+        self.flush_queued.store(false, Ordering::Release);
+        let json = {
+            let loaded = self.entries.read().unwrap();
+            serde_json::to_string(loaded.as_ref().unwrap_or(&HashMap::new()))
+        };
+
+        let result = json.map_err(|e| e.to_string()).and_then(|json| {
+            let path = get_app_data_dir()?.join(self.file);
+            std::fs::write(path, json).map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            eprintln!("{} failed to save {} cache: {}", self.tag, self.what, e);
+        }
+    }
+}
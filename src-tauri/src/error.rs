@@ -0,0 +1,141 @@
+/// typed errors for scrapers and the http layer, so the ui can tell
+/// "cloudflare said no" apart from "wifi is down" or "the site changed its markup"
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum ScrapeError {
+    /// dns, tls, connection refused/reset
+    Network(String),
+    /// non-2xx that isn't a block page
+    Http { status: u16, url: String },
+    /// cloudflare challenge, captcha or similar bot wall
+    Blocked(String),
+    /// page loaded but the selectors/json shape we rely on are gone
+    ParseChanged(String),
+    /// page loaded fine and genuinely has nothing for this query
+    Empty,
+    Timeout,
+}
+
+impl ScrapeError {
+    /// stable machine readable kind, sent to the frontend
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScrapeError::Network(_) => "network",
+            ScrapeError::Http { .. } => "http",
+            ScrapeError::Blocked(_) => "blocked",
+            ScrapeError::ParseChanged(_) => "parse_changed",
+            ScrapeError::Empty => "empty",
+            ScrapeError::Timeout => "timeout",
+        }
+    }
+
+    /// whether trying again later has a real chance of working
+    pub fn retryable(&self) -> bool {
+        match self {
+            ScrapeError::Network(_) | ScrapeError::Timeout => true,
+            ScrapeError::Http { status, .. } => *status == 429 || *status >= 500,
+            ScrapeError::Blocked(_) | ScrapeError::ParseChanged(_) | ScrapeError::Empty => false,
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ScrapeError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScrapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScrapeError::Network(e) => write!(f, "network error: {}", e),
+            ScrapeError::Http { status, url } => write!(f, "{} returned {}", url, status),
+            ScrapeError::Blocked(reason) => write!(f, "blocked by the site: {}", reason),
+            ScrapeError::ParseChanged(what) => write!(f, "page layout changed: {}", what),
+            ScrapeError::Empty => write!(f, "no results"),
+            ScrapeError::Timeout => write!(f, "timed out"),
+        }
+    }
+}
+
+impl std::error::Error for ScrapeError {}
+
+impl From<reqwest::Error> for ScrapeError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ScrapeError::Timeout
+        } else {
+            ScrapeError::Network(e.to_string())
+        }
+    }
+}
+
+/// tauri commands still return `Result<_, String>`
+impl From<ScrapeError> for String {
+    fn from(e: ScrapeError) -> Self {
+        e.to_string()
+    }
+}
+
+/// markers of bot-protection interstitials (cloudflare, ddos-guard, generic captchas),
+/// only looked for on the statuses those walls answer with. captcha widgets and
+/// cloudflare's challenge scripts show up on healthy pages as well
+const BLOCK_MARKERS: &[&str] = &[
+    "cf-chl-",
+    "challenge-platform",
+    "Just a moment...",
+    "Attention Required! | Cloudflare",
+    "ddos-guard",
+    "g-recaptcha",
+    "h-captcha",
+    "cf-turnstile",
+];
+
+/// markers only a challenge page itself has, for walls served with a 200
+const CHALLENGE_PAGE_MARKERS: &[&str] = &[
+    "<title>Just a moment...</title>",
+    "<title>Attention Required! | Cloudflare</title>",
+    "<title>DDoS-Guard</title>",
+    "cf-chl-",
+];
+
+/// look for a bot wall in a response, only the head of the page is checked
+pub fn detect_block(status: u16, body: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&body[..body.len().min(64 * 1024)]);
+    let markers = match status {
+        403 | 429 | 503 => BLOCK_MARKERS,
+        _ => CHALLENGE_PAGE_MARKERS,
+    };
+    markers
+        .iter()
+        .find(|marker| head.contains(*marker))
+        .map(|marker| format!("challenge page detected ({})", marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captcha_on_a_healthy_page_is_not_a_block() {
+        let page = br#"<html><head><title>Wallpapers</title>
+            <script src="/cdn-cgi/challenge-platform/scripts/jsd/main.js"></script></head>
+            <body><div class="g-recaptcha"></div></body></html>"#;
+        assert_eq!(detect_block(200, page), None);
+    }
+
+    #[test]
+    fn challenge_page_is_a_block() {
+        let page = b"<html><head><title>Just a moment...</title></head></html>";
+        assert!(detect_block(200, page).is_some());
+        assert!(detect_block(403, b"<div class=\"g-recaptcha\"></div>").is_some());
+        assert!(detect_block(503, b"<script src=\"/cdn-cgi/challenge-platform/h/b\">").is_some());
+    }
+
+    #[test]
+    fn error_page_without_markers_is_not_a_block() {
+        assert_eq!(detect_block(403, b"<h1>Forbidden</h1>"), None);
+    }
+}
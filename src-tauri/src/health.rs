@@ -0,0 +1,261 @@
+/// source health checks - runs a known query per source and verifies every selector
+/// the scraper relies on still matches, so markup changes show up before users notice.
+/// last results are kept in AppData so search can flag degraded sources up front
+use crate::error::ScrapeError;
+use crate::http;
+use crate::models::{HealthStatus, SelectorHealth, SourceError, SourceHealth};
+use crate::scraper::absolute_url;
+use crate::sources;
+use crate::storage::{get_app_data_dir, load_settings};
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Instant;
+
+/// one selector that must match at least once
+#[derive(Debug, Clone)]
+pub struct SelectorCheck {
+    pub name: &'static str,
+    pub selector: String,
+}
+
+impl SelectorCheck {
+    pub fn new(name: &'static str, selector: impl Into<String>) -> Self {
+        Self {
+            name,
+            selector: selector.into(),
+        }
+    }
+}
+
+/// follow the first `link` match of the listing page and check the detail page too
+#[derive(Debug, Clone)]
+pub struct DetailProbe {
+    pub link: String,
+    pub checks: Vec<SelectorCheck>,
+}
+
+/// what a source wants checked: a listing url for a query that always has results
+#[derive(Debug, Clone)]
+pub struct HealthProbe {
+    pub url: String,
+    pub checks: Vec<SelectorCheck>,
+    pub detail: Option<DetailProbe>,
+}
+
+lazy_static::lazy_static! {
+    static ref LAST_HEALTH: RwLock<Option<HashMap<String, SourceHealth>>> = RwLock::new(None);
+}
+
+fn get_health_file() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("source_health.json"))
+}
+
+fn load_from_disk() -> HashMap<String, SourceHealth> {
+    get_health_file()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_to_disk(health: &HashMap<String, SourceHealth>) {
+    let result = get_health_file().and_then(|path| {
+        let json = serde_json::to_string_pretty(health).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("[HEALTH] failed to save health results: {}", e);
+    }
+}
+
+/// last known health per source id, from memory or AppData
+pub fn last_health() -> HashMap<String, SourceHealth> {
+    if let Some(health) = LAST_HEALTH.read().unwrap().as_ref() {
+        return health.clone();
+    }
+
+    let health = load_from_disk();
+    *LAST_HEALTH.write().unwrap() = Some(health.clone());
+    health
+}
+
+/// requested sources whose last check found them degraded or down
+pub fn degraded_among(ids: &[String]) -> Vec<String> {
+    let health = last_health();
+    ids.iter()
+        .filter(|id| {
+            health.get(id.as_str()).is_some_and(|entry| {
+                matches!(entry.status, HealthStatus::Degraded | HealthStatus::Down)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+fn run_checks(document: &Html, checks: &[SelectorCheck]) -> Vec<SelectorHealth> {
+    checks
+        .iter()
+        .map(|check| {
+            let matched = Selector::parse(&check.selector)
+                .map(|selector| document.select(&selector).count())
+                .unwrap_or(0);
+            SelectorHealth {
+                name: check.name.to_string(),
+                selector: check.selector.clone(),
+                matched,
+            }
+        })
+        .collect()
+}
+
+/// fetch the probe page(s) and count selector matches
+async fn run_probe(probe: &HealthProbe) -> Result<Vec<SelectorHealth>, ScrapeError> {
+    let html = http::get_text(http::get(&probe.url)).await?;
+
+    let (mut results, detail_url) = {
+        let document = Html::parse_document(&html);
+        let results = run_checks(&document, &probe.checks);
+        let detail_url = probe.detail.as_ref().and_then(|detail| {
+            let selector = Selector::parse(&detail.link).ok()?;
+            document
+                .select(&selector)
+                .next()
+                .and_then(|link| link.value().attr("href"))
+                .map(|href| absolute_url(href, &probe.url))
+        });
+        (results, detail_url)
+    };
+
+    if let Some(detail) = &probe.detail {
+        match detail_url {
+            Some(url) => {
+                let html = http::get_text(http::get(&url)).await?;
+                let document = Html::parse_document(&html);
+                results.extend(run_checks(&document, &detail.checks));
+            }
+            // no link to follow, every detail check counts as broken
+            None => results.extend(detail.checks.iter().map(|check| SelectorHealth {
+                name: check.name.to_string(),
+                selector: check.selector.clone(),
+                matched: 0,
+            })),
+        }
+    }
+
+    Ok(results)
+}
+
+async fn check_source(source_id: String) -> SourceHealth {
+    let checked_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let started = Instant::now();
+
+    let probe = sources::get(&source_id).and_then(|source| source.health_probe());
+    let Some(probe) = probe else {
+        return SourceHealth {
+            source: source_id,
+            status: HealthStatus::Unknown,
+            checked_at,
+            elapsed_ms: 0,
+            checks: Vec::new(),
+            error: None,
+        };
+    };
+
+    let result = run_probe(&probe).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(checks) => {
+            let broken: Vec<&str> = checks
+                .iter()
+                .filter(|check| check.matched == 0)
+                .map(|check| check.name.as_str())
+                .collect();
+            let status = if broken.is_empty() {
+                HealthStatus::Ok
+            } else {
+                HealthStatus::Degraded
+            };
+            println!(
+                "[HEALTH] {}: {} in {}ms{}",
+                source_id,
+                status.as_str(),
+                elapsed_ms,
+                if broken.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (broken: {})", broken.join(", "))
+                }
+            );
+
+            SourceHealth {
+                source: source_id,
+                status,
+                checked_at,
+                elapsed_ms,
+                checks,
+                error: None,
+            }
+        }
+        Err(e) => {
+            println!("[HEALTH] {}: down - {}", source_id, e);
+            SourceHealth {
+                error: Some(SourceError::new(&source_id, &e)),
+                source: source_id,
+                status: HealthStatus::Down,
+                checked_at,
+                elapsed_ms,
+                checks: Vec::new(),
+            }
+        }
+    }
+}
+
+/// check the given sources concurrently, store and return the results
+pub async fn check_sources(ids: &[String]) -> Vec<SourceHealth> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for id in ids {
+        tasks.spawn(check_source(id.clone()));
+    }
+
+    let mut results = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(health) = result {
+            results.push(health);
+        }
+    }
+    results.sort_by_key(|health| ids.iter().position(|id| *id == health.source));
+
+    let mut all = last_health();
+    for health in &results {
+        all.insert(health.source.clone(), health.clone());
+    }
+    save_to_disk(&all);
+    *LAST_HEALTH.write().unwrap() = Some(all);
+
+    results
+}
+
+/// background loop, first run shortly after startup then every `health_check_interval_hours`.
+/// the interval is re-read each round so a settings change applies without a restart
+pub fn spawn_periodic_checks() {
+    tauri::async_runtime::spawn(async {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+
+        loop {
+            let hours = load_settings().health_check_interval_hours;
+            if hours > 0 {
+                check_sources(&sources::default_ids()).await;
+            }
+
+            // when disabled keep polling hourly in case it gets turned back on
+            let hours = if hours == 0 { 1 } else { hours };
+            tokio::time::sleep(std::time::Duration::from_secs(hours * 60 * 60)).await;
+        }
+    });
+}
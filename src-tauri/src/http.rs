@@ -0,0 +1,377 @@
+/// shared http layer - one pooled client for every scraper and download,
+/// retries 429/5xx and dropped connections with exponential backoff.
+/// also owns per-source base url overrides and the record/replay fixture mode
+use crate::error::{detect_block, ScrapeError};
+use crate::models::AppSettings;
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// one browser-like UA for everything, some sites serve different markup to unknown agents
+pub const USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Safari/537.36";
+
+/// never sleep longer than this between attempts, even if Retry-After asks for more
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// live: normal network. record: network + save every response to the fixture dir.
+/// replay: never touch the network, serve saved responses (missing fixture = error)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpMode {
+    Live,
+    Record,
+    Replay,
+}
+
+impl HttpMode {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "record" => HttpMode::Record,
+            "replay" => HttpMode::Replay,
+            _ => HttpMode::Live,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub connect_timeout: Duration,
+    /// per read, not per request, so long video downloads are fine
+    pub read_timeout: Duration,
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+    pub mode: HttpMode,
+    pub fixtures_dir: PathBuf,
+    /// source id -> origin, e.g. "wallhaven" -> "http://127.0.0.1:8080"
+    pub base_urls: HashMap<String, String>,
+}
+
+impl HttpConfig {
+    /// settings first, `COLORWALL_HTTP_MODE` / `COLORWALL_FIXTURES_DIR` env vars win
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let mode = std::env::var("COLORWALL_HTTP_MODE")
+            .ok()
+            .or_else(|| settings.http_mode.clone())
+            .map(|mode| HttpMode::parse(&mode))
+            .unwrap_or(HttpMode::Live);
+        let fixtures_dir = std::env::var("COLORWALL_FIXTURES_DIR")
+            .ok()
+            .or_else(|| settings.http_fixtures_dir.clone())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join("colorwall_fixtures"));
+
+        Self {
+            connect_timeout: Duration::from_secs(settings.http_connect_timeout_secs),
+            read_timeout: Duration::from_secs(settings.http_read_timeout_secs),
+            max_retries: settings.http_max_retries,
+            base_backoff: Duration::from_millis(500),
+            mode,
+            fixtures_dir,
+            base_urls: settings.source_base_urls.clone(),
+        }
+    }
+}
+
+struct SharedClient {
+    config: HttpConfig,
+    client: reqwest::Client,
+}
+
+lazy_static::lazy_static! {
+    static ref SHARED: RwLock<SharedClient> = {
+        let config = HttpConfig::from_settings(&AppSettings::default());
+        RwLock::new(SharedClient {
+            client: build_client(&config),
+            config,
+        })
+    };
+}
+
+fn build_client(config: &HttpConfig) -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(config.connect_timeout)
+        .read_timeout(config.read_timeout)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(8)
+        .build()
+        .expect("failed to build shared http client")
+}
+
+/// rebuild the shared client with new timeouts (startup + whenever settings are saved)
+pub fn configure(settings: &AppSettings) {
+    let config = HttpConfig::from_settings(settings);
+    let client = build_client(&config);
+    println!(
+        "[HTTP] configured - connect: {}s, read: {}s, retries: {}, mode: {:?}",
+        config.connect_timeout.as_secs(),
+        config.read_timeout.as_secs(),
+        config.max_retries,
+        config.mode
+    );
+    *SHARED.write().unwrap() = SharedClient { config, client };
+}
+
+/// the shared client, cheap to clone (connection pool is shared)
+pub fn client() -> reqwest::Client {
+    SHARED.read().unwrap().client.clone()
+}
+
+fn config() -> HttpConfig {
+    SHARED.read().unwrap().config.clone()
+}
+
+/// origin for a source, `COLORWALL_BASE_URL_<SOURCE>` env var, then settings, then `default`
+pub fn base_url(source: &str, default: &str) -> String {
+    let env_key = format!(
+        "COLORWALL_BASE_URL_{}",
+        source.to_uppercase().replace(['-', '.', ':'], "_")
+    );
+    if let Ok(url) = std::env::var(env_key) {
+        return url.trim_end_matches('/').to_string();
+    }
+
+    SHARED
+        .read()
+        .unwrap()
+        .config
+        .base_urls
+        .get(source)
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| default.to_string())
+}
+
+pub fn get(url: &str) -> RequestBuilder {
+    client().get(url)
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn is_retryable_error(error: &reqwest::Error) -> bool {
+    // only transient failures, a bad url or builder error won't fix itself on retry
+    error.is_connect() || error.is_timeout()
+}
+
+/// Retry-After in seconds, the http-date form is rare enough to fall back to backoff
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+fn backoff(config: &HttpConfig, attempt: u32) -> Duration {
+    let exp = config.base_backoff * 2u32.saturating_pow(attempt);
+    let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..250));
+    (exp + jitter).min(MAX_BACKOFF)
+}
+
+/// send with retries. non-retryable statuses are returned as-is for the caller to judge
+pub async fn send(request: RequestBuilder) -> Result<Response, ScrapeError> {
+    let config = config();
+    let mut attempt = 0;
+
+    loop {
+        // streaming bodies can't be cloned, those just get the one attempt
+        let Some(current) = request.try_clone() else {
+            return request.send().await.map_err(ScrapeError::from);
+        };
+
+        let retries_left = attempt < config.max_retries;
+        match current.send().await {
+            Ok(response) if retries_left && is_retryable_status(response.status()) => {
+                let delay = retry_after(&response)
+                    .unwrap_or_else(|| backoff(&config, attempt))
+                    .min(MAX_BACKOFF);
+                println!(
+                    "[HTTP] {} from {}, retrying in {}ms",
+                    response.status(),
+                    response.url(),
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Ok(response) => return Ok(response),
+            Err(e) if retries_left && is_retryable_error(&e) => {
+                let delay = backoff(&config, attempt);
+                println!("[HTTP] {}, retrying in {}ms", e, delay.as_millis());
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        attempt += 1;
+    }
+}
+
+/// fully read response, the unit that gets recorded and replayed
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub url: String,
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+impl Fetched {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FixtureMeta {
+    method: String,
+    url: String,
+    status: u16,
+}
+
+/// fixtures are keyed by method + final request url (query included)
+fn fixture_paths(dir: &std::path::Path, method: &str, url: &str) -> (PathBuf, PathBuf) {
+    let key = format!("{:x}", md5::compute(format!("{} {}", method, url)));
+    (
+        dir.join(format!("{}.json", key)),
+        dir.join(format!("{}.body", key)),
+    )
+}
+
+fn load_fixture(config: &HttpConfig, method: &str, url: &str) -> Result<Fetched, ScrapeError> {
+    let (meta_path, body_path) = fixture_paths(&config.fixtures_dir, method, url);
+    let meta: FixtureMeta = std::fs::read_to_string(&meta_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .ok_or_else(|| ScrapeError::Network(format!("no recorded fixture for {} {}", method, url)))?;
+    let body = std::fs::read(&body_path).map_err(|e| {
+        ScrapeError::Network(format!("fixture body missing for {}: {}", url, e))
+    })?;
+
+    Ok(Fetched {
+        url: meta.url,
+        status: StatusCode::from_u16(meta.status).unwrap_or(StatusCode::OK),
+        body,
+    })
+}
+
+fn save_fixture(config: &HttpConfig, method: &str, url: &str, fetched: &Fetched) {
+    if let Err(e) = std::fs::create_dir_all(&config.fixtures_dir) {
+        eprintln!("[HTTP] failed to create fixture dir: {}", e);
+        return;
+    }
+
+    let (meta_path, body_path) = fixture_paths(&config.fixtures_dir, method, url);
+    let meta = FixtureMeta {
+        method: method.to_string(),
+        url: url.to_string(),
+        status: fetched.status.as_u16(),
+    };
+    let written = serde_json::to_string_pretty(&meta)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&meta_path, json).map_err(|e| e.to_string()))
+        .and_then(|_| std::fs::write(&body_path, &fetched.body).map_err(|e| e.to_string()));
+
+    if let Err(e) = written {
+        eprintln!("[HTTP] failed to record fixture for {}: {}", url, e);
+    }
+}
+
+/// send + read the whole body, honoring record/replay. use this for anything scraped
+pub async fn fetch(request: RequestBuilder) -> Result<Fetched, ScrapeError> {
+    let config = config();
+    let (method, url) = request
+        .try_clone()
+        .and_then(|r| r.build().ok())
+        .map(|r| (r.method().to_string(), r.url().to_string()))
+        .ok_or_else(|| ScrapeError::Network("request can't be inspected".to_string()))?;
+
+    if config.mode == HttpMode::Replay {
+        return load_fixture(&config, &method, &url);
+    }
+
+    let response = send(request).await?;
+    let fetched = Fetched {
+        url: response.url().to_string(),
+        status: response.status(),
+        body: response.bytes().await?.to_vec(),
+    };
+
+    if config.mode == HttpMode::Record {
+        save_fixture(&config, &method, &url, &fetched);
+    }
+
+    Ok(fetched)
+}
+
+/// fetch and fail on non-2xx or a bot wall (cloudflare happily serves those with 200 too)
+pub async fn fetch_ok(request: RequestBuilder) -> Result<Fetched, ScrapeError> {
+    let fetched = fetch(request).await?;
+
+    if let Some(reason) = detect_block(fetched.status.as_u16(), &fetched.body) {
+        return Err(ScrapeError::Blocked(reason));
+    }
+    if !fetched.status.is_success() {
+        return Err(ScrapeError::Http {
+            status: fetched.status.as_u16(),
+            url: fetched.url,
+        });
+    }
+    Ok(fetched)
+}
+
+/// first `max_bytes` of a file through a range request, enough to read image headers.
+/// servers that ignore `Range` get cut off after `max_bytes` all the same.
+/// recorded and replayed like `fetch`, as the truncated body
+pub async fn fetch_prefix(url: &str, max_bytes: usize) -> Result<Vec<u8>, ScrapeError> {
+    let config = config();
+    if config.mode == HttpMode::Replay {
+        let mut body = load_fixture(&config, "GET", url)?.body;
+        body.truncate(max_bytes);
+        return Ok(body);
+    }
+
+    let request = get(url).header("Range", format!("bytes=0-{}", max_bytes.saturating_sub(1)));
+    let mut response = send(request).await?;
+    let status = response.status();
+    let final_url = response.url().to_string();
+    // 206 for servers that honor the range, 200 for the rest
+    if !status.is_success() {
+        return Err(ScrapeError::Http {
+            status: status.as_u16(),
+            url: final_url,
+        });
+    }
+
+    let mut body = Vec::new();
+    while body.len() < max_bytes {
+        match response.chunk().await? {
+            Some(chunk) => body.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    body.truncate(max_bytes);
+
+    if config.mode == HttpMode::Record {
+        let fetched = Fetched {
+            url: final_url,
+            status,
+            body,
+        };
+        save_fixture(&config, "GET", url, &fetched);
+        return Ok(fetched.body);
+    }
+    Ok(body)
+}
+
+pub async fn get_text(request: RequestBuilder) -> Result<String, ScrapeError> {
+    Ok(fetch_ok(request).await?.text())
+}
+
+pub async fn get_bytes(request: RequestBuilder) -> Result<Vec<u8>, ScrapeError> {
+    Ok(fetch_ok(request).await?.body)
+}
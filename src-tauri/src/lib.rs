@@ -1,3 +1,5 @@
+pub mod error;
+pub mod http;
 pub mod models;
 pub mod scraper;
 pub use models::*;
@@ -2,8 +2,22 @@
 // main tauri entry point for loading everything together
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod blocklist;
+mod dedup;
+mod details;
+mod disk_cache;
+mod error;
+mod health;
+mod http;
 mod models;
+mod palette;
+mod probe;
+mod query;
+mod ranking;
+mod safety;
 mod scraper;
+mod search_cache;
+mod sources;
 mod video_wallpaper;
 mod storage;
 mod commands;
@@ -50,9 +64,32 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             // Search commands
             search_wallpapers,
+            start_search,
+            cancel_search,
+            list_sources,
+            reload_sources,
             fetch_live2d,
+            fetch_feed,
+            browse_wallpapers,
+            find_similar,
+            resolve_wallpaper,
             resolve_wallpaperflare_highres,
             resolve_motionbgs_video,
+            resolve_moewalls_video,
+            get_wallpaper_details,
+            // Source health commands
+            check_sources,
+            get_source_health,
+            // Local folder commands
+            reindex_local_folders,
+            set_local_tags,
+            // Blocklist commands
+            list_blocklist,
+            add_blocklist_entry,
+            remove_blocklist_entry,
+            block_wallpaper,
+            import_blocklist,
+            export_blocklist,
             // Wallpaper commands
             set_wallpaper,
             get_current_wallpaper,
@@ -72,6 +109,12 @@ fn main() {
             save_settings,
         ])
         .setup(|app| {
+            http::configure(&storage::load_settings());
+            sources::reload_sites();
+            search_cache::prune();
+            sources::local::allow_asset_access(app.handle());
+            health::spawn_periodic_checks();
+
             let window = app.get_webview_window("main").unwrap();
 
             // Window close event handler - wallpaper continues in background
@@ -1,4 +1,6 @@
+use crate::error::ScrapeError;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -17,14 +19,333 @@ pub struct WallpaperItem {
     pub original: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize)]
+/// one page of a source's results, `has_more` comes from the site's own next page link
+/// or api metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPage {
+    pub items: Vec<WallpaperItem>,
+    pub has_more: bool,
+}
+
+/// search input shared by `search_wallpapers` and the streaming `start_search`
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchRequest {
+    /// free text plus filters like `-word type:video res:>=2560x1440`, see query.rs
+    pub query: String,
+    pub sources: Option<Vec<String>>,
+    pub limit_per_source: Option<usize>,
+    /// legacy switch, `false` means source order when no `ranking` is given
+    pub randomize: Option<bool>,
+    pub page: Option<u32>,
+    pub purity: Option<String>,
+    pub ai_art: Option<bool>,
+    pub timeout_ms: Option<u64>,
+    pub wallhaven: Option<WallhavenOptions>,
+    pub ranking: Option<Ranking>,
+    /// shuffle seed from an earlier response, keeps the order stable across pages
+    pub seed: Option<u32>,
+    /// smallest accepted size, items from every source are probed for it
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+    /// "16:9", "16x9", "1.78", "landscape", "portrait", "square", comma separated
+    pub aspect: Option<String>,
+    /// `cursors` from the previous response, per source. sources missing here start
+    /// at `page`, ones mapped to null are exhausted and skipped
+    pub cursors: Option<HashMap<String, Option<String>>>,
+}
+
+/// how merged results are ordered, see ranking.rs
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Ranking {
+    /// as the sources returned them, sources in request order
+    Source,
+    /// round-robin, one item per source at a time
+    Interleave,
+    /// largest width x height first
+    Resolution,
+    /// videos first
+    Video,
+    /// seeded shuffle, the seed is returned with the results
+    Shuffle,
+}
+
+/// wallhaven api search filters, passed through as-is (see wallhaven.cc/help/api#search)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WallhavenOptions {
+    /// date_added, relevance, random, views, favorites, toplist
+    pub sorting: Option<String>,
+    /// desc or asc
+    pub order: Option<String>,
+    /// toplist range: 1d, 3d, 1w, 1M, 3M, 6M, 1y
+    pub top_range: Option<String>,
+    /// general/anime/people bits, e.g. "110"
+    pub categories: Option<String>,
+    /// minimum resolution, e.g. "1920x1080"
+    pub atleast: Option<String>,
+    /// comma separated, e.g. "16x9,21x9"
+    pub ratios: Option<String>,
+    /// keeps `random` sorting stable across pages
+    pub seed: Option<String>,
+    /// one hex color from wallhaven's palette, e.g. "ff6600"
+    pub colors: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResponse {
     pub success: bool,
     pub items: Vec<WallpaperItem>,
+    pub errors: Option<Vec<SourceError>>,
+    /// requested sources whose last health check wasn't ok, results may be thin
+    pub degraded: Option<Vec<String>>,
+    /// some sources failed and were answered from an older cached page instead
+    pub stale: bool,
+    /// age of the oldest cached page served that way
+    pub cache_age_secs: Option<u64>,
+    /// seed used by the `shuffle` ranking, pass it back for the next page
+    pub seed: Option<u32>,
+    /// where each searched source continues, null once a source has no more pages
+    pub cursors: HashMap<String, Option<String>>,
+    /// any source still has a next page
+    pub has_more: bool,
+    /// parts of the query that didn't parse, they were left out of the search
+    pub query_errors: Option<Vec<QueryError>>,
+    /// what the content policy took out, null when nothing was
+    pub hidden: Option<HiddenItems>,
+}
+
+/// items the content policy kept from the ui
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HiddenItems {
+    pub total: usize,
+    /// reason ("keyword: gore", "ai art") -> how many items it hid
+    pub reasons: HashMap<String, usize>,
+    /// sources left out entirely, blocklisted or nsfw without a purity filter
+    pub sources: Vec<String>,
+}
+
+impl HiddenItems {
+    pub fn add(&mut self, reason: String) {
+        self.total += 1;
+        *self.reasons.entry(reason).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: HiddenItems) {
+        self.total += other.total;
+        for (reason, count) in other.reasons {
+            *self.reasons.entry(reason).or_default() += count;
+        }
+        for source in other.sources {
+            if !self.sources.contains(&source) {
+                self.sources.push(source);
+            }
+        }
+    }
+
+    /// `None` when nothing was hidden, for the response fields
+    pub fn into_option(self) -> Option<HiddenItems> {
+        (self.total > 0 || !self.sources.is_empty()).then_some(self)
+    }
+}
+
+/// broken part of a search query, `start`..`end` are utf-16 offsets into it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// per-source failure inside a search, `kind` is one of `ScrapeError::kind()`
+/// (network, http, blocked, parse_changed, empty, timeout)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceError {
+    pub source: String,
+    pub kind: String,
+    pub message: String,
+    pub status: Option<u16>,
+    /// worth offering a "try again" button
+    pub retryable: bool,
+}
+
+impl SourceError {
+    pub fn new(source: &str, error: &ScrapeError) -> Self {
+        Self {
+            source: source.to_string(),
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+            status: error.status(),
+            retryable: error.retryable(),
+        }
+    }
+}
+
+/// what a source can do, reported to the frontend through `list_sources`
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceCapabilities {
+    pub images: bool,
+    pub videos: bool,
+    pub nsfw: bool,
+    pub pagination: bool,
+    /// has a resolver for full-res images / full videos behind `detail_url`
+    pub resolve: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceInfo {
+    pub id: String,
+    pub name: String,
+    pub capabilities: SourceCapabilities,
+    /// native listings `browse_wallpapers` can show for this source
+    pub browse_modes: Vec<BrowseMode>,
+}
+
+/// query-less listing for `browse_wallpapers`, each source maps it onto its own pages
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum BrowseMode {
+    /// newest uploads first
+    Latest,
+    /// most favorited over `range` (1d, 3d, 1w, 1M, 3M, 6M, 1y)
+    Toplist,
+    Random,
+    /// the site's own picks
+    Featured,
+    /// popular right now
+    Hot,
+}
+
+impl BrowseMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BrowseMode::Latest => "latest",
+            BrowseMode::Toplist => "toplist",
+            BrowseMode::Random => "random",
+            BrowseMode::Featured => "featured",
+            BrowseMode::Hot => "hot",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcesResponse {
+    pub success: bool,
+    pub sources: Vec<SourceInfo>,
+    /// site definition files that failed to load, only set by `reload_sources`
     pub errors: Option<Vec<String>>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartSearchResponse {
+    pub success: bool,
+    pub search_id: String,
+    pub degraded: Option<Vec<String>>,
+    pub seed: Option<u32>,
+    pub query_errors: Option<Vec<QueryError>>,
+}
+
+/// one selector from a health probe and how many elements it matched
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectorHealth {
+    pub name: String,
+    pub selector: String,
+    pub matched: usize,
+}
+
+/// outcome of a source's health probe
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    /// some selector matched nothing
+    Degraded,
+    /// the page didn't load, see `SourceHealth.error`
+    Down,
+    /// the source has no probe
+    Unknown,
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Ok => "ok",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Down => "down",
+            HealthStatus::Unknown => "unknown",
+        }
+    }
+}
+
+/// result of probing one source
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceHealth {
+    pub source: String,
+    pub status: HealthStatus,
+    /// unix seconds
+    pub checked_at: i64,
+    pub elapsed_ms: u64,
+    pub checks: Vec<SelectorHealth>,
+    pub error: Option<SourceError>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceHealthResponse {
+    pub success: bool,
+    pub sources: Vec<SourceHealth>,
+    pub error: Option<String>,
+}
+
+/// `search:partial` - one source finished, items are already deduped against earlier batches
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPartialEvent {
+    pub search_id: String,
+    pub source: String,
+    pub items: Vec<WallpaperItem>,
+    /// items come from the search cache because the source failed, see `search:source-error`
+    pub stale: bool,
+    pub cache_age_secs: Option<u64>,
+    /// items of this batch the content policy took out
+    pub hidden: Option<HiddenItems>,
+}
+
+/// `search:source-error` - one source failed or timed out
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSourceErrorEvent {
+    pub search_id: String,
+    pub error: SourceError,
+}
+
+/// `search:done` - last event of a streaming search, also sent when it gets cancelled
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDoneEvent {
+    pub search_id: String,
+    pub total_items: usize,
+    pub error_count: usize,
+    pub cancelled: bool,
+    /// same as `SearchResponse.cursors`, empty when cancelled
+    pub cursors: HashMap<String, Option<String>>,
+    pub has_more: bool,
+    /// everything the content policy took out over the whole search
+    pub hidden: Option<HiddenItems>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WallpaperResponse {
@@ -55,6 +376,51 @@ pub struct ResolveHighResResponse {
     pub url: Option<String>,
     pub url4k: Option<String>,
     pub error: Option<String>,
+    /// `ScrapeError::kind()` when resolving failed
+    pub error_kind: Option<String>,
+    /// every file the source offers, for sources that list several resolutions
+    pub variants: Option<Vec<MediaVariant>>,
+    /// size of `url`, from the resolver or else read from the image's header
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// one downloadable file of a wallpaper, e.g. the 1080p and 4k versions of a video
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaVariant {
+    pub url: String,
+    /// what the site calls it, e.g. "Download 4K"
+    pub label: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// bytes
+    pub file_size: Option<u64>,
+}
+
+/// what a wallpaper's detail page says about it, listings only carry a fraction of this
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WallpaperDetails {
+    pub id: String,
+    pub source: String,
+    /// tags and categories, listing tags included
+    pub tags: Vec<String>,
+    pub uploader: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// bytes
+    pub file_size: Option<u64>,
+    pub license: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WallpaperDetailsResponse {
+    pub success: bool,
+    pub details: Option<WallpaperDetails>,
+    pub error: Option<String>,
+    pub error_kind: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -98,12 +464,258 @@ pub struct AppSettings {
     /// Path to mpv.exe (optional, user can provide their own)
     #[serde(default)]
     pub mpv_path: Option<String>,
+    /// per-source search timeout, slow sources get reported instead of blocking the rest
+    #[serde(default = "default_source_timeout")]
+    pub source_timeout_secs: u64,
+    /// wallhaven api key, needed for nsfw purity
+    #[serde(default)]
+    pub wallhaven_api_key: Option<String>,
+    #[serde(default = "default_connect_timeout")]
+    pub http_connect_timeout_secs: u64,
+    /// max time between two reads, not a cap on the whole download
+    #[serde(default = "default_read_timeout")]
+    pub http_read_timeout_secs: u64,
+    /// retries on 429/5xx and dropped connections
+    #[serde(default = "default_max_retries")]
+    pub http_max_retries: u32,
+    /// "live" (default), "record" or "replay", see http.rs
+    #[serde(default)]
+    pub http_mode: Option<String>,
+    #[serde(default)]
+    pub http_fixtures_dir: Option<String>,
+    /// source id -> origin override, for pointing a scraper at a local stand-in server
+    #[serde(default)]
+    pub source_base_urls: HashMap<String, String>,
+    /// background source health check interval, 0 turns it off
+    #[serde(default = "default_health_check_interval")]
+    pub health_check_interval_hours: u64,
+    /// booru-style api sources (danbooru, gelbooru, konachan, yande.re...)
+    #[serde(default = "default_booru_sources")]
+    pub booru_sources: Vec<BooruConfig>,
+    /// rss/atom/json feeds, each one shows up as its own source
+    #[serde(default)]
+    pub feeds: Vec<FeedConfig>,
+    /// directories indexed by the "local" source, searched recursively
+    #[serde(default)]
+    pub local_folders: Vec<String>,
+    /// how long cached search pages are served without asking the source again, 0 = always ask
+    #[serde(default = "default_search_cache_ttl")]
+    pub search_cache_ttl_minutes: u64,
+    /// source id -> ttl override in minutes
+    #[serde(default)]
+    pub source_cache_ttl_minutes: HashMap<String, u64>,
+    /// collapse the same picture found on several sources (thumbnail hash comparison)
+    #[serde(default = "default_true")]
+    pub dedup_similar: bool,
+    /// read image headers to fill in missing width/height (always on while size filters are set)
+    #[serde(default = "default_true")]
+    pub probe_dimensions: bool,
+    /// highest content level any source may return: "sfw", "sketchy" or "nsfw"
+    #[serde(default = "default_content_purity")]
+    pub content_purity: String,
+    /// off turns ai art off on every search, sites without a native filter are checked by keyword
+    #[serde(default = "default_true")]
+    pub allow_ai_art: bool,
+    /// items with one of these in their title or tags are hidden (whole words, any case)
+    #[serde(default)]
+    pub blocked_keywords: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedConfig {
+    /// source id, must not clash with another source
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// which post api flavour a booru speaks
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BooruKind {
+    /// /posts.json, ratings g/s/q/e
+    Danbooru,
+    /// /index.php?page=dapi, ratings general/sensitive/questionable/explicit
+    Gelbooru,
+    /// konachan, yande.re - /post.json, ratings s/q/e
+    Moebooru,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BooruConfig {
+    /// source id, must not clash with another source
+    pub id: String,
+    pub name: String,
+    pub kind: BooruKind,
+    pub base_url: String,
+    /// danbooru login / gelbooru user id, goes with `api_key`
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// posts smaller than this are dropped, 0 = no limit
+    #[serde(default)]
+    pub min_width: u32,
+    #[serde(default)]
+    pub min_height: u32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            audio_enabled: false,
+            live_wallpaper_enabled: true,
+            video_player: default_player(),
+            mpv_path: None,
+            source_timeout_secs: default_source_timeout(),
+            wallhaven_api_key: None,
+            http_connect_timeout_secs: default_connect_timeout(),
+            http_read_timeout_secs: default_read_timeout(),
+            http_max_retries: default_max_retries(),
+            http_mode: None,
+            http_fixtures_dir: None,
+            source_base_urls: HashMap::new(),
+            health_check_interval_hours: default_health_check_interval(),
+            booru_sources: default_booru_sources(),
+            feeds: Vec::new(),
+            local_folders: Vec::new(),
+            search_cache_ttl_minutes: default_search_cache_ttl(),
+            source_cache_ttl_minutes: HashMap::new(),
+            dedup_similar: true,
+            probe_dimensions: true,
+            content_purity: default_content_purity(),
+            allow_ai_art: true,
+            blocked_keywords: Vec::new(),
+        }
+    }
 }
 
 fn default_player() -> String {
     "wmf".to_string()
 }
 
+fn default_source_timeout() -> u64 {
+    15
+}
+
+fn default_connect_timeout() -> u64 {
+    10
+}
+
+fn default_read_timeout() -> u64 {
+    30
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_health_check_interval() -> u64 {
+    6
+}
+
+fn default_search_cache_ttl() -> u64 {
+    30
+}
+
+fn default_content_purity() -> String {
+    "sfw".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_booru_sources() -> Vec<BooruConfig> {
+    let booru = |id: &str, name: &str, kind: BooruKind, base_url: &str| BooruConfig {
+        id: id.to_string(),
+        name: name.to_string(),
+        kind,
+        base_url: base_url.to_string(),
+        user: None,
+        api_key: None,
+        // anything smaller looks bad as a wallpaper
+        min_width: 1280,
+        min_height: 720,
+        // listed so they're easy to turn on in settings.json, but searched only once
+        // the user opts in
+        enabled: false,
+    };
+
+    vec![
+        booru("danbooru", "Danbooru", BooruKind::Danbooru, "https://danbooru.donmai.us"),
+        booru("gelbooru", "Gelbooru", BooruKind::Gelbooru, "https://gelbooru.com"),
+        booru("konachan", "Konachan", BooruKind::Moebooru, "https://konachan.com"),
+        booru("yandere", "yande.re", BooruKind::Moebooru, "https://yande.re"),
+    ]
+}
+
+/// what a blocklist entry matches on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    /// an item id, `wallhaven-abc123`
+    Item,
+    /// a tag, any case, `_` and spaces are the same
+    Tag,
+    /// a regex run against titles, case insensitive
+    Title,
+    /// a source id, the whole source is left out
+    Source,
+    /// a thumbnail dhash as 16 hex digits, catches the same picture on every site
+    Hash,
+}
+
+impl BlockKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockKind::Item => "item",
+            BlockKind::Tag => "tag",
+            BlockKind::Title => "title",
+            BlockKind::Source => "source",
+            BlockKind::Hash => "hash",
+        }
+    }
+}
+
+/// one entry of the user blocklist, kind + value is unique
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockEntry {
+    pub kind: BlockKind,
+    pub value: String,
+    /// free text, e.g. the title of the blocked item
+    #[serde(default)]
+    pub note: Option<String>,
+    /// unix seconds
+    #[serde(default)]
+    pub added_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlocklistResponse {
+    pub success: bool,
+    pub entries: Vec<BlockEntry>,
+    /// entries added by an import or `block_wallpaper`, already-listed ones don't count
+    pub added: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReindexResponse {
+    pub success: bool,
+    pub file_count: usize,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SettingsResponse {
@@ -0,0 +1,240 @@
+/// dominant colors of thumbnails, for `color:` searches and `find_similar`. a thumbnail is
+/// shrunk, its pixels bucketed by color and the biggest buckets averaged into a small
+/// palette. palettes are cached per thumbnail url in AppData like the dedup hashes
+use crate::disk_cache::DiskCache;
+use crate::http;
+use crate::models::WallpaperItem;
+use image::imageops::FilterType;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+pub type Rgb = [u8; 3];
+
+const PALETTE_SIZE: usize = 5;
+
+/// an item matches a color when one of this many of its main colors is close to it
+const MATCHED_COLORS: usize = 3;
+
+/// redmean distance under which two colors count as the same, the scale goes up to ~765
+const MAX_DISTANCE: f64 = 110.0;
+
+/// thumbnails not analyzed by then are kept unfiltered, search shouldn't wait on them
+const PALETTE_BUDGET: Duration = Duration::from_secs(6);
+
+const CONCURRENT_DOWNLOADS: usize = 8;
+
+/// the only values wallhaven's `colors` filter accepts
+const WALLHAVEN_COLORS: &[&str] = &[
+    "660000", "990000", "cc0000", "cc3333", "ea4c88", "993399", "663399", "333399", "0066cc",
+    "0099cc", "66cccc", "77cc33", "669900", "336600", "666600", "999900", "cccc33", "ffff00",
+    "ffcc33", "ff9900", "ff6600", "cc6633", "996633", "663300", "000000", "999999", "cccccc",
+    "ffffff", "424153",
+];
+
+lazy_static::lazy_static! {
+    /// thumbnail url -> palette as hex strings
+    static ref PALETTES: DiskCache<Vec<String>> =
+        DiskCache::new("thumbnail_palettes.json", "[PALETTE]", "thumbnail palette", 20_000);
+}
+
+pub fn to_hex(color: Rgb) -> String {
+    format!("{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// "ff6600" or "#ff6600"
+pub fn parse_hex(value: &str) -> Option<Rgb> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |range: std::ops::Range<usize>| u8::from_str_radix(&hex[range], 16).ok();
+    Some([channel(0..2)?, channel(2..4)?, channel(4..6)?])
+}
+
+/// plain color names for the query language, picked from wallhaven's palette
+pub fn named_color(name: &str) -> Option<Rgb> {
+    let hex = match name {
+        "red" => "cc0000",
+        "orange" => "ff6600",
+        "yellow" => "ffff00",
+        "green" => "669900",
+        "teal" | "cyan" => "66cccc",
+        "blue" => "0066cc",
+        "purple" => "663399",
+        "pink" => "ea4c88",
+        "brown" => "663300",
+        "black" => "000000",
+        "gray" | "grey" => "999999",
+        "white" => "ffffff",
+        _ => return None,
+    };
+    parse_hex(hex)
+}
+
+/// "redmean" weighted distance, close enough to how different colors look for this
+fn distance(a: Rgb, b: Rgb) -> f64 {
+    let mean_red = (a[0] as f64 + b[0] as f64) / 2.0;
+    let red = a[0] as f64 - b[0] as f64;
+    let green = a[1] as f64 - b[1] as f64;
+    let blue = a[2] as f64 - b[2] as f64;
+    ((2.0 + mean_red / 256.0) * red * red
+        + 4.0 * green * green
+        + (2.0 + (255.0 - mean_red) / 256.0) * blue * blue)
+        .sqrt()
+}
+
+/// closest value wallhaven's `colors` filter takes
+pub fn wallhaven_color(color: Rgb) -> &'static str {
+    WALLHAVEN_COLORS
+        .iter()
+        .min_by(|a, b| {
+            let a = distance(color, parse_hex(a).unwrap_or_default());
+            let b = distance(color, parse_hex(b).unwrap_or_default());
+            a.total_cmp(&b)
+        })
+        .copied()
+        .unwrap_or("000000")
+}
+
+/// shrink to 48x48, bucket pixels by their top 3 bits per channel and average the
+/// biggest buckets, most common color first
+fn compute_palette(bytes: &[u8]) -> Option<Vec<Rgb>> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let small = image::imageops::resize(&image.to_rgb8(), 48, 48, FilterType::Triangle);
+
+    let mut buckets: HashMap<(u8, u8, u8), ([u64; 3], u64)> = HashMap::new();
+    for pixel in small.pixels() {
+        let [r, g, b] = pixel.0;
+        let (sums, count) = buckets.entry((r >> 5, g >> 5, b >> 5)).or_default();
+        sums[0] += r as u64;
+        sums[1] += g as u64;
+        sums[2] += b as u64;
+        *count += 1;
+    }
+
+    let mut buckets: Vec<([u64; 3], u64)> = buckets.into_values().collect();
+    buckets.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let palette: Vec<Rgb> = buckets
+        .into_iter()
+        .take(PALETTE_SIZE)
+        .map(|(sums, count)| {
+            [
+                (sums[0] / count) as u8,
+                (sums[1] / count) as u8,
+                (sums[2] / count) as u8,
+            ]
+        })
+        .collect();
+    (!palette.is_empty()).then_some(palette)
+}
+
+async fn analyze_thumbnail(url: String) -> Option<Vec<Rgb>> {
+    let bytes = http::get_bytes(http::get(&url)).await.ok()?;
+    tokio::task::spawn_blocking(move || compute_palette(&bytes))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// remote thumbnail to analyze, local files and videos without a poster have none
+fn thumbnail(item: &WallpaperItem) -> Option<&str> {
+    item.thumbnail_url
+        .as_deref()
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+}
+
+fn decode(palette: &[String]) -> Vec<Rgb> {
+    palette.iter().filter_map(|hex| parse_hex(hex)).collect()
+}
+
+/// palette per item (same order), from the cache or by downloading the thumbnail.
+/// anything not done within `PALETTE_BUDGET` or that fails to decode is `None`
+pub async fn palettes(items: &[WallpaperItem]) -> Vec<Option<Vec<Rgb>>> {
+    let mut found: HashMap<String, Vec<Rgb>> = PALETTES
+        .get_many(items.iter().filter_map(thumbnail))
+        .into_iter()
+        .map(|(url, palette)| (url, decode(&palette)))
+        .collect();
+    let mut missing: Vec<String> = Vec::new();
+    for url in items.iter().filter_map(thumbnail) {
+        if !found.contains_key(url) && !missing.iter().any(|other| other == url) {
+            missing.push(url.to_string());
+        }
+    }
+
+    if !missing.is_empty() {
+        let started = std::time::Instant::now();
+        let semaphore = Arc::new(Semaphore::new(CONCURRENT_DOWNLOADS));
+        let mut tasks = tokio::task::JoinSet::new();
+        for url in missing.iter().cloned() {
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let palette = analyze_thumbnail(url.clone()).await?;
+                Some((url, palette))
+            });
+        }
+
+        let deadline = tokio::time::Instant::now() + PALETTE_BUDGET;
+        let mut new = HashMap::new();
+        while let Ok(Some(result)) = tokio::time::timeout_at(deadline, tasks.join_next()).await {
+            if let Ok(Some((url, palette))) = result {
+                new.insert(url, palette);
+            }
+        }
+        // dropping the set aborts whatever is still downloading
+        drop(tasks);
+
+        println!(
+            "[PALETTE] analyzed {} of {} new thumbnails in {}ms",
+            new.len(),
+            missing.len(),
+            started.elapsed().as_millis()
+        );
+        if !new.is_empty() {
+            PALETTES.insert(
+                new.iter()
+                    .map(|(url, palette)| {
+                        (url.clone(), palette.iter().copied().map(to_hex).collect())
+                    })
+                    .collect(),
+            );
+            found.extend(new);
+        }
+    }
+
+    items
+        .iter()
+        .map(|item| thumbnail(item).and_then(|url| found.get(url).cloned()))
+        .collect()
+}
+
+pub fn matches(palette: &[Rgb], color: Rgb) -> bool {
+    palette
+        .iter()
+        .take(MATCHED_COLORS)
+        .any(|main| distance(*main, color) <= MAX_DISTANCE)
+}
+
+/// drop items whose main colors are nowhere near `color`.
+/// items whose palette couldn't be worked out are kept, there's nothing to judge them by
+pub async fn filter_by_color(items: &mut Vec<WallpaperItem>, color: Rgb) {
+    let palettes = palettes(items).await;
+    let before = items.len();
+    let mut palettes = palettes.into_iter();
+    items.retain(|_| match palettes.next().flatten() {
+        Some(palette) => matches(&palette, color),
+        None => true,
+    });
+
+    if items.len() < before {
+        println!(
+            "[PALETTE] color #{} dropped {} of {} items",
+            to_hex(color),
+            before - items.len(),
+            before
+        );
+    }
+}
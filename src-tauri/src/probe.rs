@@ -0,0 +1,493 @@
+/// image dimension probing - reads just the header bytes of an image (png, jpeg, gif,
+/// webp, avif) through a range request, so listings without sizes still get width/height
+/// and the min size / aspect filters work the same for every source.
+/// results are cached per url in AppData
+use crate::disk_cache::DiskCache;
+use crate::http;
+use crate::models::WallpaperItem;
+use crate::sources::{SearchParams, WallpaperSource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// covers png/gif/webp/avif headers and most jpegs
+const PROBE_BYTES: usize = 64 * 1024;
+
+/// jpegs with a big exif block or embedded thumbnail put the frame header further in
+const JPEG_RETRY_BYTES: usize = 512 * 1024;
+
+/// items not probed by then keep unknown dimensions, the search doesn't wait longer
+const PROBE_BUDGET: Duration = Duration::from_secs(5);
+
+const CONCURRENT_PROBES: usize = 8;
+
+/// relative difference still counted as the same aspect ratio, loose enough for 3440x1440 as 21:9
+const ASPECT_TOLERANCE: f64 = 0.03;
+
+/// what a probe found, `url` is set when the item had to be resolved to its full image first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Probed {
+    url: Option<String>,
+    width: u32,
+    height: u32,
+}
+
+lazy_static::lazy_static! {
+    /// image url (or detail url for resolved items) -> probe result
+    static ref PROBED: DiskCache<Probed> =
+        DiskCache::new("image_dimensions.json", "[PROBE]", "image dimension", 20_000);
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn be32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le24(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    // signature, then IHDR is always the first chunk
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") || bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((be32(bytes, 16)?, be32(bytes, 20)?))
+}
+
+fn gif_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if !bytes.starts_with(b"GIF8") {
+        return None;
+    }
+    Some((le16(bytes, 6)?, le16(bytes, 8)?))
+}
+
+/// walk the marker segments until a start-of-frame
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut at = 2;
+    loop {
+        // markers can be padded with any number of 0xFF
+        while *bytes.get(at)? == 0xFF && *bytes.get(at + 1)? == 0xFF {
+            at += 1;
+        }
+        if *bytes.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(at + 1)?;
+        match marker {
+            // standalone markers, no length
+            0x01 | 0xD0..=0xD7 => at += 2,
+            // start of scan before any frame header, nothing more to find
+            0xD9 | 0xDA => return None,
+            // SOF0..SOF15, minus DHT / JPG / DAC which share the range
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = be16(bytes, at + 5)?;
+                let width = be16(bytes, at + 7)?;
+                return Some((width, height));
+            }
+            _ => at += 2 + be16(bytes, at + 2)? as usize,
+        }
+    }
+}
+
+fn webp_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    match bytes.get(12..16)? {
+        // lossy: frame tag + start code, then 14 bit sizes
+        b"VP8 " => Some((le16(bytes, 26)? & 0x3FFF, le16(bytes, 28)? & 0x3FFF)),
+        // lossless: signature byte, then 14 bit width-1 / height-1 packed together
+        b"VP8L" => {
+            let b = bytes.get(21..25)?;
+            let width = 1 + (((b[1] as u32 & 0x3F) << 8) | b[0] as u32);
+            let height =
+                1 + (((b[3] as u32 & 0x0F) << 10) | (b[2] as u32) << 2 | (b[1] as u32 & 0xC0) >> 6);
+            Some((width, height))
+        }
+        // extended: 24 bit canvas width-1 / height-1
+        b"VP8X" => Some((1 + le24(bytes, 24)?, 1 + le24(bytes, 27)?)),
+        _ => None,
+    }
+}
+
+/// avif/heif keep the size in `ispe` properties, grid images have one per tile
+/// plus one for the whole picture, so the largest wins
+fn avif_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(4..8)? != b"ftyp" {
+        return None;
+    }
+    let brands = bytes.get(8..(be32(bytes, 0)? as usize).min(bytes.len()))?;
+    if !brands
+        .chunks(4)
+        .any(|brand| matches!(brand, b"avif" | b"avis" | b"mif1" | b"heic"))
+    {
+        return None;
+    }
+
+    bytes
+        .windows(4)
+        .enumerate()
+        .filter(|(_, window)| *window == b"ispe")
+        // box type, then version/flags, then width and height
+        .filter_map(|(at, _)| Some((be32(bytes, at + 8)?, be32(bytes, at + 12)?)))
+        .max_by_key(|(width, height)| *width as u64 * *height as u64)
+}
+
+/// width/height from the start of an image file, `None` for unknown formats or cut-off headers
+pub fn image_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    png_size(bytes)
+        .or_else(|| jpeg_size(bytes))
+        .or_else(|| webp_size(bytes))
+        .or_else(|| avif_size(bytes))
+        .or_else(|| gif_size(bytes))
+        .filter(|(width, height)| *width > 0 && *height > 0)
+}
+
+/// dimensions of a remote image without downloading all of it
+pub async fn probe_url(url: &str) -> Option<(u32, u32)> {
+    let bytes = http::fetch_prefix(url, PROBE_BYTES).await.ok()?;
+    if let Some(size) = image_size(&bytes) {
+        return Some(size);
+    }
+
+    // a jpeg whose frame header is past the first chunk, one bigger try
+    if bytes.starts_with(&[0xFF, 0xD8]) && bytes.len() == PROBE_BYTES {
+        let bytes = http::fetch_prefix(url, JPEG_RETRY_BYTES).await.ok()?;
+        return image_size(&bytes);
+    }
+    None
+}
+
+/// what to probe for an item, `true` when the listing only has a thumbnail and the
+/// full image has to come from the source's resolver first
+fn probe_target(item: &WallpaperItem, can_resolve: bool) -> Option<(String, bool)> {
+    if item.media_type.as_deref() == Some("video")
+        || (item.width.is_some() && item.height.is_some())
+    {
+        return None;
+    }
+
+    let is_thumbnail = item.thumbnail_url.as_deref() == Some(item.image_url.as_str());
+    if !is_thumbnail {
+        let remote =
+            item.image_url.starts_with("http://") || item.image_url.starts_with("https://");
+        return remote.then(|| (item.image_url.clone(), false));
+    }
+    // a thumbnail's size says nothing about the wallpaper
+    match &item.detail_url {
+        Some(detail_url) if can_resolve => Some((detail_url.clone(), true)),
+        _ => None,
+    }
+}
+
+async fn probe_item(
+    source: Arc<dyn WallpaperSource>,
+    key: String,
+    resolve: bool,
+) -> Option<Probed> {
+    if !resolve {
+        let (width, height) = probe_url(&key).await?;
+        return Some(Probed {
+            url: None,
+            width,
+            height,
+        });
+    }
+
+    let resolved = source.resolve(&key).await.ok()?;
+    let (width, height) = match (resolved.width, resolved.height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => probe_url(&resolved.url).await?,
+    };
+    Some(Probed {
+        url: Some(resolved.url),
+        width,
+        height,
+    })
+}
+
+/// fill in missing width/height from the cache or by probing image headers, whatever isn't
+/// done within `PROBE_BUDGET` stays unknown. thumbnail-only listings need a detail page
+/// per item to find the real image, that's only done with `resolve` (size or ratio filters
+/// set), otherwise their size stays unknown until the item is opened
+pub async fn fill_dimensions(
+    source: &Arc<dyn WallpaperSource>,
+    items: &mut [WallpaperItem],
+    resolve: bool,
+) {
+    let can_resolve = resolve && source.capabilities().resolve;
+    let targets: Vec<Option<(String, bool)>> = items
+        .iter()
+        .map(|item| probe_target(item, can_resolve))
+        .collect();
+    if targets.iter().all(Option::is_none) {
+        return;
+    }
+
+    let mut probed = PROBED.get_many(targets.iter().flatten().map(|(key, _)| key.as_str()));
+    let missing: Vec<(String, bool)> = targets
+        .iter()
+        .flatten()
+        .filter(|(key, _)| !probed.contains_key(key))
+        .cloned()
+        .collect();
+
+    if !missing.is_empty() {
+        let started = std::time::Instant::now();
+        let semaphore = Arc::new(Semaphore::new(CONCURRENT_PROBES));
+        let mut tasks = tokio::task::JoinSet::new();
+        for (key, resolve) in missing.iter().cloned() {
+            let semaphore = semaphore.clone();
+            let source = source.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let result = probe_item(source, key.clone(), resolve).await?;
+                Some((key, result))
+            });
+        }
+
+        let deadline = tokio::time::Instant::now() + PROBE_BUDGET;
+        let mut new = HashMap::new();
+        while let Ok(Some(result)) = tokio::time::timeout_at(deadline, tasks.join_next()).await {
+            if let Ok(Some((key, result))) = result {
+                new.insert(key, result);
+            }
+        }
+        // dropping the set aborts whatever is still running
+        drop(tasks);
+
+        println!(
+            "[PROBE] {}: probed {} of {} images in {}ms",
+            source.id(),
+            new.len(),
+            missing.len(),
+            started.elapsed().as_millis()
+        );
+        if !new.is_empty() {
+            probed.extend(new.clone());
+            PROBED.insert(new);
+        }
+    }
+
+    for (item, target) in items.iter_mut().zip(targets) {
+        let Some(result) = target.and_then(|(key, _)| probed.get(&key)) else {
+            continue;
+        };
+        item.width = Some(result.width);
+        item.height = Some(result.height);
+        if let Some(url) = &result.url {
+            item.image_url = url.clone();
+        }
+    }
+}
+
+/// one entry of the `aspect` filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aspect {
+    Ratio(f64),
+    Landscape,
+    Portrait,
+}
+
+/// "16:9", "16x9", "1.78", "landscape", "portrait" or "square", comma separated.
+/// entries that don't parse are ignored
+fn parse_aspects(value: &str) -> Vec<Aspect> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let entry = entry.trim().to_lowercase();
+            match entry.as_str() {
+                "" => None,
+                "landscape" => Some(Aspect::Landscape),
+                "portrait" => Some(Aspect::Portrait),
+                "square" => Some(Aspect::Ratio(1.0)),
+                _ => {
+                    let ratio = match entry.split_once([':', 'x']) {
+                        Some((w, h)) => {
+                            w.trim().parse::<f64>().ok()? / h.trim().parse::<f64>().ok()?
+                        }
+                        None => entry.parse::<f64>().ok()?,
+                    };
+                    (ratio.is_finite() && ratio > 0.0).then_some(Aspect::Ratio(ratio))
+                }
+            }
+        })
+        .collect()
+}
+
+/// every comma separated entry of `value` parses as an aspect
+pub fn is_valid_aspect(value: &str) -> bool {
+    let entries = value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .count();
+    entries > 0 && parse_aspects(value).len() == entries
+}
+
+/// wallhaven's `ratios` param for the same aspect filter, `None` when it can't express it
+pub fn wallhaven_ratios(value: &str) -> Option<String> {
+    value
+        .split(',')
+        .map(|entry| {
+            let entry = entry.trim().to_lowercase();
+            match entry.as_str() {
+                "landscape" | "portrait" => Some(entry),
+                _ => {
+                    let (w, h) = entry.split_once([':', 'x'])?;
+                    let (w, h) = (w.trim().parse::<u32>().ok()?, h.trim().parse::<u32>().ok()?);
+                    Some(format!("{}x{}", w, h))
+                }
+            }
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|ratios| ratios.join(","))
+}
+
+fn matches_aspect(aspects: &[Aspect], width: u32, height: u32) -> bool {
+    let ratio = width as f64 / height as f64;
+    aspects.iter().any(|aspect| match aspect {
+        Aspect::Landscape => width > height,
+        Aspect::Portrait => height > width,
+        Aspect::Ratio(wanted) => (ratio - wanted).abs() / wanted <= ASPECT_TOLERANCE,
+    })
+}
+
+/// drop items outside `min_width` / `min_height`, `max_width` / `max_height` or `aspect`.
+/// items whose size is still unknown can't be shown to be big enough, so they're dropped
+/// when there's a minimum and kept otherwise
+pub fn apply_filters(items: &mut Vec<WallpaperItem>, params: &SearchParams) {
+    let aspects = params
+        .aspect
+        .as_deref()
+        .map(parse_aspects)
+        .unwrap_or_default();
+    if !params.has_size_filters() {
+        return;
+    }
+
+    let before = items.len();
+    items.retain(|item| {
+        let (Some(width), Some(height)) = (item.width, item.height) else {
+            return params.min_width.is_none() && params.min_height.is_none();
+        };
+        width >= params.min_width.unwrap_or(0)
+            && height >= params.min_height.unwrap_or(0)
+            && width <= params.max_width.unwrap_or(u32::MAX)
+            && height <= params.max_height.unwrap_or(u32::MAX)
+            && (aspects.is_empty() || matches_aspect(&aspects, width, height))
+    });
+
+    if items.len() < before {
+        println!(
+            "[PROBE] size/aspect filters dropped {} of {} items",
+            before - items.len(),
+            before
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/fixtures/probe/",
+                $name
+            ))
+        };
+    }
+
+    /// (file, size, bytes the size needs), every shorter prefix has to give up quietly
+    const FIXTURES: &[(&[u8], (u32, u32), usize)] = &[
+        (fixture!("tiny.png"), (3, 2), 24),
+        (fixture!("progressive.jpg"), (640, 480), 53),
+        (fixture!("screen.gif"), (40, 30), 10),
+        (fixture!("lossy.webp"), (1920, 1080), 30),
+        (fixture!("lossless.webp"), (800, 600), 25),
+        (fixture!("extended.webp"), (3440, 1440), 30),
+        (fixture!("grid.avif"), (2048, 1024), 109),
+    ];
+
+    #[test]
+    fn reads_header_sizes() {
+        for (bytes, size, _) in FIXTURES {
+            assert_eq!(image_size(bytes), Some(*size));
+        }
+    }
+
+    #[test]
+    fn truncated_headers_are_none() {
+        for (bytes, size, needed) in FIXTURES {
+            for len in 0..*needed {
+                assert_eq!(image_size(&bytes[..len]), None, "{:?} cut at {}", size, len);
+            }
+            assert!(image_size(&bytes[..*needed]).is_some());
+        }
+    }
+
+    #[test]
+    fn every_prefix_is_safe() {
+        for (bytes, _, _) in FIXTURES {
+            for len in 0..=bytes.len() {
+                image_size(&bytes[..len]);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_formats_are_none() {
+        assert_eq!(image_size(b"<!DOCTYPE html><html></html>"), None);
+        assert_eq!(image_size(&[0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02]), None);
+    }
+
+    #[test]
+    fn thumbnails_are_only_resolved_when_asked() {
+        let mut item = WallpaperItem {
+            id: "site-1".to_string(),
+            source: "site".to_string(),
+            title: None,
+            image_url: "https://example.com/thumb/1.jpg".to_string(),
+            thumbnail_url: Some("https://example.com/thumb/1.jpg".to_string()),
+            media_type: Some("image".to_string()),
+            width: None,
+            height: None,
+            tags: None,
+            detail_url: Some("https://example.com/w/1".to_string()),
+            original: None,
+        };
+        assert_eq!(probe_target(&item, false), None);
+        assert_eq!(
+            probe_target(&item, true),
+            Some(("https://example.com/w/1".to_string(), true))
+        );
+
+        // a real image url is a plain header probe either way
+        item.image_url = "https://example.com/full/1.jpg".to_string();
+        assert_eq!(
+            probe_target(&item, false),
+            Some(("https://example.com/full/1.jpg".to_string(), false))
+        );
+        item.width = Some(1920);
+        item.height = Some(1080);
+        assert_eq!(probe_target(&item, true), None);
+    }
+}
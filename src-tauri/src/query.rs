@@ -0,0 +1,545 @@
+/// search query language. free words go to the sites, the rest are filters:
+///
+///   cyberpunk city -anime type:video res:>=2560x1440 ratio:21x9 source:wallhaven,motionbgs purity:sfw
+///
+/// filters become native params where a source has them (wallhaven atleast/ratios/purity/colors,
+/// booru/wallhaven `-tag`, motionbgs builds its tag slug from the free words only) and
+/// post-filters on the results everywhere else. error positions are utf-16 offsets so
+/// the search bar can index its js string with them directly
+use crate::models::{QueryError, WallpaperItem};
+use crate::palette;
+use crate::probe;
+use crate::sources::{self, SearchParams};
+
+/// what a query string asks for, with the filter syntax taken out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedQuery {
+    /// free words and "quoted phrases"
+    pub terms: Vec<String>,
+    /// `-word`, results mentioning it in their title or tags are dropped
+    pub excluded: Vec<String>,
+    /// `type:image` / `type:video`
+    pub media_type: Option<String>,
+    /// `res:>=WxH`, `res:<=WxH`, `res:=WxH`, a bare `res:WxH` is a minimum
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// `ratio:21x9`, same values as the `aspect` search param
+    pub aspect: Option<String>,
+    /// `source:wallhaven,motionbgs`
+    pub sources: Option<Vec<String>>,
+    /// `purity:sfw,sketchy` as wallhaven purity bits
+    pub purity: Option<String>,
+    /// `color:ff6600`, `color:#ff6600` or `color:blue`, as 6 hex digits
+    pub color: Option<String>,
+}
+
+impl ParsedQuery {
+    /// the part sent to the sites as their search text
+    pub fn text(&self) -> String {
+        self.terms.join(" ")
+    }
+}
+
+/// whitespace separated chunk of the input, quotes still in
+struct Token<'a> {
+    raw: &'a str,
+    start: usize,
+    end: usize,
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn error(start: usize, end: usize, message: impl Into<String>) -> QueryError {
+    QueryError {
+        message: message.into(),
+        start,
+        end,
+    }
+}
+
+/// split on whitespace outside of double quotes
+fn tokenize<'a>(input: &'a str, errors: &mut Vec<QueryError>) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    // (byte offset, utf-16 offset) of the token being read
+    let mut current: Option<(usize, usize)> = None;
+    let mut quote_at: Option<usize> = None;
+    let mut position = 0;
+
+    for (index, ch) in input.char_indices() {
+        if ch.is_whitespace() && quote_at.is_none() {
+            if let Some((byte_start, start)) = current.take() {
+                tokens.push(Token {
+                    raw: &input[byte_start..index],
+                    start,
+                    end: position,
+                });
+            }
+        } else {
+            if current.is_none() {
+                current = Some((index, position));
+            }
+            if ch == '"' {
+                quote_at = if quote_at.is_some() {
+                    None
+                } else {
+                    Some(position)
+                };
+            }
+        }
+        position += ch.len_utf16();
+    }
+
+    if let Some(start) = quote_at {
+        errors.push(error(start, position, "missing closing quote"));
+    }
+    if let Some((byte_start, start)) = current {
+        tokens.push(Token {
+            raw: &input[byte_start..],
+            start,
+            end: position,
+        });
+    }
+    tokens
+}
+
+fn unquote(text: &str) -> String {
+    text.replace('"', "").trim().to_string()
+}
+
+/// "2560x1440" or a name like "1440p" / "4k"
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    match value.to_lowercase().as_str() {
+        "720p" | "hd" => Some((1280, 720)),
+        "1080p" | "fhd" => Some((1920, 1080)),
+        "1440p" | "2k" | "qhd" => Some((2560, 1440)),
+        "2160p" | "4k" | "uhd" => Some((3840, 2160)),
+        "5k" => Some((5120, 2880)),
+        "8k" => Some((7680, 4320)),
+        value => {
+            let (width, height) = value.split_once(['x', '*'])?;
+            let width = width.trim().parse().ok()?;
+            let height = height.trim().parse().ok()?;
+            (width > 0 && height > 0).then_some((width, height))
+        }
+    }
+}
+
+fn apply_res(
+    parsed: &mut ParsedQuery,
+    value: &str,
+    start: usize,
+    end: usize,
+) -> Result<(), QueryError> {
+    let (op, size) = [">=", "<=", ">", "<", "="]
+        .iter()
+        .find_map(|op| value.strip_prefix(op).map(|rest| (*op, rest)))
+        .unwrap_or(("", value));
+    let (width, height) = parse_size(size).ok_or_else(|| {
+        error(
+            start,
+            end,
+            "expected a size like 2560x1440 or 4k, optionally after >=, <= or =",
+        )
+    })?;
+
+    match op {
+        "" | ">=" => {
+            parsed.min_width = Some(width);
+            parsed.min_height = Some(height);
+        }
+        ">" => {
+            parsed.min_width = Some(width.saturating_add(1));
+            parsed.min_height = Some(height.saturating_add(1));
+        }
+        "<=" => {
+            parsed.max_width = Some(width);
+            parsed.max_height = Some(height);
+        }
+        "<" => {
+            parsed.max_width = Some(width.saturating_sub(1));
+            parsed.max_height = Some(height.saturating_sub(1));
+        }
+        _ => {
+            parsed.min_width = Some(width);
+            parsed.min_height = Some(height);
+            parsed.max_width = Some(width);
+            parsed.max_height = Some(height);
+        }
+    }
+    Ok(())
+}
+
+/// comma separated entries of `value` with their own positions
+fn list_entries(value: &str, start: usize) -> Vec<(String, usize, usize)> {
+    let mut entries = Vec::new();
+    let mut position = start;
+    for entry in value.split(',') {
+        let len = utf16_len(entry);
+        let trimmed = unquote(entry).to_lowercase();
+        if !trimmed.is_empty() {
+            entries.push((trimmed, position, position + len));
+        }
+        position += len + 1;
+    }
+    entries
+}
+
+fn apply_filter(
+    parsed: &mut ParsedQuery,
+    key: &str,
+    value: &str,
+    start: usize,
+    end: usize,
+    errors: &mut Vec<QueryError>,
+) {
+    let unquoted = unquote(value).to_lowercase();
+    match key {
+        "type" => match unquoted.as_str() {
+            "image" | "images" | "static" => parsed.media_type = Some("image".to_string()),
+            "video" | "videos" | "live" => parsed.media_type = Some("video".to_string()),
+            _ => errors.push(error(start, end, "type is image or video")),
+        },
+        "res" | "resolution" => {
+            if let Err(e) = apply_res(parsed, &unquoted, start, end) {
+                errors.push(e);
+            }
+        }
+        "ratio" | "aspect" => {
+            if probe::is_valid_aspect(&unquoted) {
+                parsed.aspect = Some(unquoted);
+            } else {
+                errors.push(error(
+                    start,
+                    end,
+                    "expected a ratio like 16x9, 21:9, landscape or portrait",
+                ));
+            }
+        }
+        "color" | "colour" => {
+            match palette::parse_hex(&unquoted).or_else(|| palette::named_color(&unquoted)) {
+                Some(color) => parsed.color = Some(palette::to_hex(color)),
+                None => errors.push(error(
+                    start,
+                    end,
+                    "expected a hex color like #ff6600 or a name like blue",
+                )),
+            }
+        }
+        "source" | "sources" => {
+            for (id, entry_start, entry_end) in list_entries(value, start) {
+                if sources::get(&id).is_none() {
+                    errors.push(error(
+                        entry_start,
+                        entry_end,
+                        format!("unknown source '{}'", id),
+                    ));
+                    continue;
+                }
+                let selected = parsed.sources.get_or_insert_with(Vec::new);
+                if !selected.contains(&id) {
+                    selected.push(id);
+                }
+            }
+        }
+        "purity" => {
+            let mut bits = parsed
+                .purity
+                .as_deref()
+                .unwrap_or("000")
+                .as_bytes()
+                .to_vec();
+            for (level, entry_start, entry_end) in list_entries(value, start) {
+                let bit = match level.as_str() {
+                    "sfw" => 0,
+                    "sketchy" => 1,
+                    "nsfw" => 2,
+                    _ => {
+                        errors.push(error(
+                            entry_start,
+                            entry_end,
+                            "purity is sfw, sketchy or nsfw",
+                        ));
+                        continue;
+                    }
+                };
+                bits[bit] = b'1';
+            }
+            if bits.contains(&b'1') {
+                parsed.purity = Some(String::from_utf8_lossy(&bits).to_string());
+            }
+        }
+        _ => unreachable!("filter keys are checked before"),
+    }
+}
+
+const FILTER_KEYS: &[&str] = &[
+    "type",
+    "res",
+    "resolution",
+    "ratio",
+    "aspect",
+    "source",
+    "sources",
+    "purity",
+    "color",
+    "colour",
+];
+
+/// parse `input`, broken parts are left out of the result and reported instead
+pub fn parse(input: &str) -> (ParsedQuery, Vec<QueryError>) {
+    let mut parsed = ParsedQuery::default();
+    let mut errors = Vec::new();
+
+    for token in tokenize(input, &mut errors) {
+        if let Some(rest) = token.raw.strip_prefix('-') {
+            let word = unquote(rest);
+            if word.is_empty() {
+                errors.push(error(
+                    token.start,
+                    token.end,
+                    "nothing to exclude after '-'",
+                ));
+            } else {
+                parsed.excluded.push(word.to_lowercase());
+            }
+            continue;
+        }
+
+        // `key:value`, unless the colon is inside quotes
+        let filter = token
+            .raw
+            .split_once(':')
+            .filter(|(key, _)| !key.contains('"'));
+        let Some((key, value)) = filter else {
+            let term = unquote(token.raw);
+            if !term.is_empty() {
+                parsed.terms.push(term);
+            }
+            continue;
+        };
+
+        let key_lower = key.to_lowercase();
+        let value_start = token.start + utf16_len(key) + 1;
+        if !FILTER_KEYS.contains(&key_lower.as_str()) {
+            errors.push(error(
+                token.start,
+                value_start - 1,
+                format!(
+                    "unknown filter '{}', put it in quotes to search for it",
+                    key
+                ),
+            ));
+        } else if unquote(value).is_empty() {
+            errors.push(error(
+                token.start,
+                token.end,
+                format!("'{}:' needs a value", key),
+            ));
+        } else {
+            apply_filter(
+                &mut parsed,
+                &key_lower,
+                value,
+                value_start,
+                token.end,
+                &mut errors,
+            );
+        }
+    }
+
+    // the unclosed quote error is found first but sits at the end
+    errors.sort_by_key(|error| error.start);
+    (parsed, errors)
+}
+
+/// the post-filter half: excluded words and media type, for sources that can't do them
+pub fn apply_filters(items: &mut Vec<WallpaperItem>, params: &SearchParams) {
+    if params.exclude.is_empty() && params.media_type.is_none() {
+        return;
+    }
+
+    let before = items.len();
+    items.retain(|item| {
+        let media_type = item.media_type.as_deref().unwrap_or("image");
+        if params
+            .media_type
+            .as_deref()
+            .is_some_and(|wanted| wanted != media_type)
+        {
+            return false;
+        }
+
+        let haystack = format!(
+            "{} {}",
+            item.title.as_deref().unwrap_or(""),
+            item.tags.as_deref().unwrap_or(&[]).join(" ")
+        )
+        .to_lowercase();
+        !params
+            .exclude
+            .iter()
+            .any(|word| haystack.contains(word.as_str()))
+    });
+
+    if items.len() < before {
+        println!(
+            "[QUERY] query filters dropped {} of {} items",
+            before - items.len(),
+            before
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(errors: &[QueryError]) -> Vec<(usize, usize)> {
+        errors.iter().map(|e| (e.start, e.end)).collect()
+    }
+
+    #[test]
+    fn filters_and_terms() {
+        let (parsed, errors) = parse(
+            "cyberpunk \"neon city\" type:video res:>=2560x1440 ratio:21x9 purity:sfw,sketchy",
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(parsed.terms, ["cyberpunk", "neon city"]);
+        assert_eq!(parsed.text(), "cyberpunk neon city");
+        assert_eq!(parsed.media_type.as_deref(), Some("video"));
+        assert_eq!(
+            (parsed.min_width, parsed.min_height),
+            (Some(2560), Some(1440))
+        );
+        assert_eq!((parsed.max_width, parsed.max_height), (None, None));
+        assert_eq!(parsed.aspect.as_deref(), Some("21x9"));
+        assert_eq!(parsed.purity.as_deref(), Some("110"));
+    }
+
+    #[test]
+    fn unclosed_quote() {
+        let (parsed, errors) = parse("city \"neon lights");
+        assert_eq!(spans(&errors), [(5, 17)]);
+        assert_eq!(errors[0].message, "missing closing quote");
+        // what was typed is still searched for
+        assert_eq!(parsed.terms, ["city", "neon lights"]);
+
+        // the quote keeps the rest together, filters inside it are plain text
+        let (parsed, errors) = parse("\"a type:video");
+        assert_eq!(spans(&errors), [(0, 13)]);
+        assert_eq!(parsed.terms, ["a type:video"]);
+        assert_eq!(parsed.media_type, None);
+    }
+
+    #[test]
+    fn offsets_are_utf16() {
+        // two chars before the filter, each one utf-16 unit
+        let (_, errors) = parse("日本 res:huge");
+        assert_eq!(spans(&errors), [(7, 11)]);
+
+        // an emoji is a surrogate pair, two units
+        let (_, errors) = parse("🌆 type:gif");
+        assert_eq!(spans(&errors), [(8, 11)]);
+
+        let (_, errors) = parse("🌆🌆 \"é");
+        assert_eq!(spans(&errors), [(5, 7)]);
+
+        // list entries get their own span
+        let (parsed, errors) = parse("über purity:sfw,lewd");
+        assert_eq!(spans(&errors), [(16, 20)]);
+        assert_eq!(parsed.purity.as_deref(), Some("100"));
+    }
+
+    #[test]
+    fn malformed_res() {
+        for input in [
+            "res:huge",
+            "res:2560x",
+            "res:x1440",
+            "res:>=0x1440",
+            "res:>>4k",
+        ] {
+            let (parsed, errors) = parse(input);
+            assert_eq!(spans(&errors), [(4, input.len())], "{}", input);
+            assert_eq!(parsed.min_width, None, "{}", input);
+            assert_eq!(parsed.max_width, None, "{}", input);
+        }
+
+        let (_, errors) = parse("res:");
+        assert_eq!(spans(&errors), [(0, 4)]);
+        assert_eq!(errors[0].message, "'res:' needs a value");
+    }
+
+    #[test]
+    fn res_operators() {
+        let (parsed, _) = parse("res:4k");
+        assert_eq!(
+            (parsed.min_width, parsed.min_height),
+            (Some(3840), Some(2160))
+        );
+
+        let (parsed, _) = parse("res:<1440p");
+        assert_eq!(
+            (parsed.max_width, parsed.max_height),
+            (Some(2559), Some(1439))
+        );
+        assert_eq!(parsed.min_width, None);
+
+        let (parsed, errors) = parse("res:>4294967295x1");
+        assert!(errors.is_empty());
+        assert_eq!(
+            (parsed.min_width, parsed.min_height),
+            (Some(u32::MAX), Some(2))
+        );
+
+        let (parsed, _) = parse("res:=1920*1080");
+        assert_eq!(
+            (parsed.min_width, parsed.max_width),
+            (Some(1920), Some(1920))
+        );
+        assert_eq!(
+            (parsed.min_height, parsed.max_height),
+            (Some(1080), Some(1080))
+        );
+    }
+
+    #[test]
+    fn exclude() {
+        let (parsed, errors) = parse("city -Anime -\"Blue Sky\" -");
+        assert_eq!(parsed.terms, ["city"]);
+        assert_eq!(parsed.excluded, ["anime", "blue sky"]);
+        assert_eq!(spans(&errors), [(24, 25)]);
+        assert_eq!(errors[0].message, "nothing to exclude after '-'");
+    }
+
+    #[test]
+    fn unknown_filter() {
+        let (parsed, errors) = parse("mode:dark \"mode:dark\"");
+        assert_eq!(spans(&errors), [(0, 4)]);
+        assert_eq!(parsed.terms, ["mode:dark"]);
+    }
+
+    /// the queries `find_similar` builds come back as plain terms
+    #[test]
+    fn find_similar_round_trip() {
+        let (parsed, errors) = parse("\"like:8oev1j\"");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(parsed.terms, ["like:8oev1j"]);
+
+        let (parsed, errors) = parse("\"blue sky\" \"source:manga\" color:ff6600");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(parsed.terms, ["blue sky", "source:manga"]);
+        assert_eq!(parsed.sources, None);
+        assert_eq!(parsed.color.as_deref(), Some("ff6600"));
+
+        // tags starting with '-' aren't exclusions once quoted
+        let (parsed, errors) = parse("\"-core\"");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(parsed.terms, ["-core"]);
+        assert!(parsed.excluded.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+/// result ordering for merged searches. every strategy is deterministic, the shuffle
+/// included - it's seeded, and the seed goes back to the frontend so later pages
+/// (and later sessions) can ask for the same order
+use crate::models::{Ranking, WallpaperItem};
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// fnv-1a, unlike `DefaultHasher` it's guaranteed not to change between rust versions
+fn stable_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// new seed for a search that didn't bring one, kept below 2^32 so it survives a js number
+pub fn new_seed() -> u32 {
+    rand::random()
+}
+
+/// fisher-yates over ChaCha8, whose output rand_chacha keeps the same across releases.
+/// `SliceRandom::shuffle` and `StdRng` don't promise that, a seed has to give the same
+/// order in a later build too. the modulo bias is far below anything visible
+fn shuffle(items: &mut [WallpaperItem], seed: u64) {
+    let mut key = [0u8; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    let mut rng = ChaCha8Rng::from_seed(key);
+    for i in (1..items.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// round-robin across sources, each source keeps its own order
+fn interleave(items: Vec<WallpaperItem>) -> Vec<WallpaperItem> {
+    let total = items.len();
+    let mut groups: Vec<(String, Vec<WallpaperItem>)> = Vec::new();
+    for item in items {
+        match groups.iter_mut().find(|(source, _)| *source == item.source) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((item.source.clone(), vec![item])),
+        }
+    }
+
+    let mut groups: Vec<_> = groups
+        .into_iter()
+        .map(|(_, group)| group.into_iter())
+        .collect();
+    let mut result = Vec::with_capacity(total);
+    while result.len() < total {
+        for group in groups.iter_mut() {
+            result.extend(group.next());
+        }
+    }
+    result
+}
+
+/// order `items` in place. `salt` separates shuffles that share a seed,
+/// e.g. the page number so page 2 isn't page 1's order again
+pub fn rank(items: &mut Vec<WallpaperItem>, ranking: Ranking, seed: u32, salt: &str) {
+    match ranking {
+        Ranking::Source => {}
+        Ranking::Interleave => *items = interleave(std::mem::take(items)),
+        // biggest first, items without known dimensions go last
+        Ranking::Resolution => items.sort_by_key(|item| {
+            std::cmp::Reverse(item.width.unwrap_or(0) as u64 * item.height.unwrap_or(0) as u64)
+        }),
+        // videos first, interleaved so one source doesn't fill the top
+        Ranking::Video => {
+            *items = interleave(std::mem::take(items));
+            items.sort_by_key(|item| item.media_type.as_deref() != Some("video"));
+        }
+        Ranking::Shuffle => shuffle(items, ((seed as u64) << 32) ^ stable_hash(salt)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(count: usize) -> Vec<WallpaperItem> {
+        (0..count)
+            .map(|n| WallpaperItem {
+                id: n.to_string(),
+                source: "test".to_string(),
+                title: None,
+                image_url: String::new(),
+                thumbnail_url: None,
+                media_type: None,
+                width: None,
+                height: None,
+                tags: None,
+                detail_url: None,
+                original: None,
+            })
+            .collect()
+    }
+
+    fn ids(items: &[WallpaperItem]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    /// pinned, a change here means saved seeds no longer give the order they used to
+    #[test]
+    fn shuffle_order_is_stable() {
+        let mut shuffled = items(10);
+        rank(&mut shuffled, Ranking::Shuffle, 42, "1");
+        assert_eq!(
+            ids(&shuffled),
+            ["7", "0", "8", "1", "5", "6", "9", "4", "3", "2"]
+        );
+    }
+
+    #[test]
+    fn salt_changes_the_order() {
+        let mut page1 = items(10);
+        let mut page2 = items(10);
+        rank(&mut page1, Ranking::Shuffle, 42, "1");
+        rank(&mut page2, Ranking::Shuffle, 42, "2");
+        assert_ne!(ids(&page1), ids(&page2));
+
+        let mut sorted = ids(&page2);
+        sorted.sort_by_key(|id| id.parse::<u32>().unwrap());
+        assert_eq!(sorted, ids(&items(10)));
+    }
+}
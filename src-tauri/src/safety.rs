@@ -0,0 +1,340 @@
+/// content-safety policy from AppSettings, applied to every source. the purity level and
+/// ai art switch cap what a search asks for (wallhaven purity/ai_art_filter, booru rating
+/// tags), sources that may return nsfw but can't filter it are left out below nsfw, and
+/// every item is checked against the keyword blocklist and the user blocklist
+/// (blocklist.rs) before it reaches the ui
+use crate::blocklist::{self, Blocklist};
+use crate::models::{AppSettings, HiddenItems, WallpaperItem};
+use crate::sources::WallpaperSource;
+use crate::storage::load_settings;
+use std::sync::Arc;
+
+/// what marks an item as ai generated on sites without a native filter
+const AI_ART_KEYWORDS: &[&str] = &[
+    "ai generated",
+    "ai-generated",
+    "ai art",
+    "ai artwork",
+    "generative ai",
+    "stable diffusion",
+    "midjourney",
+    "dall-e",
+    "novelai",
+];
+
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// wallhaven style purity bits, "100" sfw, "110" sketchy, "111" nsfw
+    purity: String,
+    allow_ai_art: bool,
+    /// lowercased, empty entries dropped
+    keywords: Vec<String>,
+    blocklist: Arc<Blocklist>,
+}
+
+/// "sfw" / "sketchy" / "nsfw" as purity bits, anything else is sfw
+fn purity_bits(level: &str) -> &'static str {
+    match level.trim().to_lowercase().as_str() {
+        "nsfw" => "111",
+        "sketchy" => "110",
+        _ => "100",
+    }
+}
+
+/// `needle` appears in `haystack` on its own, not as part of a longer word
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    haystack.match_indices(needle).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+impl Policy {
+    pub fn current() -> Self {
+        Self::new(&load_settings(), blocklist::current())
+    }
+
+    fn new(settings: &AppSettings, blocklist: Arc<Blocklist>) -> Self {
+        Self {
+            purity: purity_bits(&settings.content_purity).to_string(),
+            allow_ai_art: settings.allow_ai_art,
+            keywords: settings
+                .blocked_keywords
+                .iter()
+                .map(|keyword| keyword.trim().to_lowercase())
+                .filter(|keyword| !keyword.is_empty())
+                .collect(),
+            blocklist,
+        }
+    }
+
+    fn allows(&self, class: usize) -> bool {
+        self.purity.as_bytes().get(class) == Some(&b'1')
+    }
+
+    /// requested purity bits with everything the policy forbids turned off,
+    /// sfw when nothing is left
+    pub fn cap_purity(&self, requested: &str) -> String {
+        let capped: String = (0..3)
+            .map(|class| {
+                let wanted = requested.as_bytes().get(class) == Some(&b'1');
+                if wanted && self.allows(class) {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect();
+        if capped == "000" {
+            "100".to_string()
+        } else {
+            capped
+        }
+    }
+
+    pub fn cap_ai_art(&self, requested: bool) -> bool {
+        requested && self.allow_ai_art
+    }
+
+    /// why `source` is left out entirely, `None` when it can be searched. sources that may
+    /// return nsfw and have no purity filter of their own only show up when the policy
+    /// allows nsfw
+    pub fn source_block_reason(&self, source: &dyn WallpaperSource) -> Option<String> {
+        if self.blocklist.blocks_source(source.id()) {
+            return Some(format!("{} is on the blocklist", source.id()));
+        }
+        if source.capabilities().nsfw && !source.filters_purity() && !self.allows(2) {
+            return Some(format!(
+                "{} can return nsfw and can't filter it, the content settings only allow it at nsfw",
+                source.id()
+            ));
+        }
+        None
+    }
+
+    pub fn allows_source(&self, source: &dyn WallpaperSource) -> bool {
+        self.source_block_reason(source).is_none()
+    }
+
+    /// why `item` can't be shown, `None` when it can
+    fn block_reason(&self, item: &WallpaperItem) -> Option<String> {
+        if self.keywords.is_empty() && self.allow_ai_art {
+            return None;
+        }
+
+        let haystack = format!(
+            "{} , {}",
+            item.title.as_deref().unwrap_or(""),
+            item.tags.as_deref().unwrap_or(&[]).join(" , ")
+        )
+        .to_lowercase();
+        if let Some(keyword) = self
+            .keywords
+            .iter()
+            .find(|keyword| contains_word(&haystack, keyword))
+        {
+            return Some(format!("keyword: {}", keyword));
+        }
+        if !self.allow_ai_art
+            && AI_ART_KEYWORDS
+                .iter()
+                .any(|keyword| contains_word(&haystack, keyword))
+        {
+            return Some("ai art".to_string());
+        }
+        None
+    }
+
+    /// drop blocked items, returns what was dropped and why
+    pub async fn apply(&self, items: &mut Vec<WallpaperItem>) -> HiddenItems {
+        let mut hidden = HiddenItems::default();
+        items.retain(|item| match self.block_reason(item) {
+            Some(reason) => {
+                hidden.add(reason);
+                false
+            }
+            None => true,
+        });
+        self.blocklist.apply(items, &mut hidden).await;
+        if hidden.total > 0 {
+            println!("[SAFETY] hid {} items: {:?}", hidden.total, hidden.reasons);
+        }
+        hidden
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ScrapeError;
+    use crate::models::{SearchPage, SourceCapabilities};
+    use crate::sources::SearchParams;
+    use async_trait::async_trait;
+
+    fn policy(level: &str, allow_ai_art: bool, keywords: &[&str]) -> Policy {
+        let settings = AppSettings {
+            content_purity: level.to_string(),
+            allow_ai_art,
+            blocked_keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+            ..AppSettings::default()
+        };
+        Policy::new(&settings, Arc::new(Blocklist::default()))
+    }
+
+    fn item(title: &str, tags: &[&str]) -> WallpaperItem {
+        WallpaperItem {
+            id: title.to_string(),
+            source: "test".to_string(),
+            title: Some(title.to_string()),
+            image_url: String::new(),
+            thumbnail_url: None,
+            media_type: None,
+            width: None,
+            height: None,
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            detail_url: None,
+            original: None,
+        }
+    }
+
+    struct Source {
+        nsfw: bool,
+        filters_purity: bool,
+    }
+
+    #[async_trait]
+    impl WallpaperSource for Source {
+        fn id(&self) -> &str {
+            "test"
+        }
+
+        fn display_name(&self) -> &str {
+            "Test"
+        }
+
+        fn capabilities(&self) -> SourceCapabilities {
+            SourceCapabilities {
+                images: true,
+                videos: false,
+                nsfw: self.nsfw,
+                pagination: false,
+                resolve: false,
+            }
+        }
+
+        async fn search(&self, _params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+            Err(ScrapeError::Empty)
+        }
+
+        fn filters_purity(&self) -> bool {
+            self.filters_purity
+        }
+    }
+
+    #[test]
+    fn purity_levels() {
+        assert_eq!(purity_bits(" NSFW "), "111");
+        assert_eq!(purity_bits("sketchy"), "110");
+        assert_eq!(purity_bits("sfw"), "100");
+        assert_eq!(purity_bits("everything"), "100");
+    }
+
+    #[test]
+    fn purity_is_capped_downward() {
+        let sfw = policy("sfw", false, &[]);
+        assert_eq!(sfw.cap_purity("111"), "100");
+        assert_eq!(sfw.cap_purity("100"), "100");
+        // nothing allowed is left, sfw rather than no purity at all
+        assert_eq!(sfw.cap_purity("011"), "100");
+
+        let sketchy = policy("sketchy", false, &[]);
+        assert_eq!(sketchy.cap_purity("111"), "110");
+        assert_eq!(sketchy.cap_purity("011"), "010");
+        assert_eq!(sketchy.cap_purity("001"), "100");
+
+        let nsfw = policy("nsfw", false, &[]);
+        assert_eq!(nsfw.cap_purity("011"), "011");
+        assert_eq!(nsfw.cap_purity("001"), "001");
+
+        // never raised above what was asked for
+        for policy in [sfw, sketchy, nsfw] {
+            assert_eq!(policy.cap_purity("100"), "100");
+            assert_eq!(policy.cap_purity(""), "100");
+            assert_eq!(policy.cap_purity("garbage"), "100");
+        }
+    }
+
+    #[test]
+    fn ai_art_is_capped() {
+        assert!(!policy("sfw", false, &[]).cap_ai_art(true));
+        assert!(policy("sfw", true, &[]).cap_ai_art(true));
+        assert!(!policy("sfw", true, &[]).cap_ai_art(false));
+    }
+
+    #[test]
+    fn unfiltered_nsfw_sources_need_nsfw() {
+        let unfiltered = Source {
+            nsfw: true,
+            filters_purity: false,
+        };
+        let filtered = Source {
+            nsfw: true,
+            filters_purity: true,
+        };
+        let safe = Source {
+            nsfw: false,
+            filters_purity: false,
+        };
+
+        for level in ["sfw", "sketchy"] {
+            let policy = policy(level, false, &[]);
+            assert!(
+                policy.source_block_reason(&unfiltered).is_some(),
+                "{}",
+                level
+            );
+            assert!(policy.allows_source(&filtered));
+            assert!(policy.allows_source(&safe));
+        }
+        assert!(policy("nsfw", false, &[]).allows_source(&unfiltered));
+    }
+
+    #[test]
+    fn words_match_whole() {
+        assert!(contains_word("gore , blood", "gore"));
+        assert!(contains_word("some-gore!", "gore"));
+        assert!(!contains_word("gorey", "gore"));
+        assert!(!contains_word("ingore", "gore"));
+        assert!(contains_word("ai art , city", "ai art"));
+        assert!(!contains_word("ai artist", "ai art"));
+    }
+
+    #[tokio::test]
+    async fn keywords_and_ai_art_hide_items() {
+        let strict = policy("sfw", false, &[" Gore ", ""]);
+        let mut items = vec![
+            item("GORE fest", &[]),
+            item("Gorez", &[]),
+            item("city", &["Gore"]),
+            item("portrait", &["AI Generated"]),
+            item("Made in Midjourney", &[]),
+            item("forest", &["nature"]),
+        ];
+        let hidden = strict.apply(&mut items).await;
+
+        let titles: Vec<&str> = items
+            .iter()
+            .map(|item| item.title.as_deref().unwrap())
+            .collect();
+        assert_eq!(titles, ["Gorez", "forest"]);
+        assert_eq!(hidden.reasons["keyword: gore"], 2);
+        assert_eq!(hidden.reasons["ai art"], 2);
+
+        // allowed ai art and no keywords keep everything
+        let mut items = vec![item("portrait", &["ai generated"])];
+        let hidden = policy("sfw", true, &[]).apply(&mut items).await;
+        assert_eq!(hidden.total, 0);
+        assert_eq!(items.len(), 1);
+    }
+}
@@ -1,9 +1,30 @@
 // ACTUAL scrapers made from reading site html lol, used by the search.rs command, provides for three tier loading for video and thumbnails.
 // will add more video sites soon! i have found a few that i like a lot
+use crate::error::ScrapeError;
+use crate::http;
 use crate::models::*;
 use regex::Regex;
 use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+
+// default site origins, each can be pointed elsewhere through `http::base_url`
+pub const WALLHAVEN_BASE: &str = "https://wallhaven.cc";
+pub const WALLPAPERFLARE_BASE: &str = "https://www.wallpaperflare.com";
+pub const MOEWALLS_BASE: &str = "https://moewalls.com";
+pub const MOTIONBGS_BASE: &str = "https://motionbgs.com";
+
+// listing/detail selectors, shared with the health check so both always agree
+pub const WALLHAVEN_ITEM: &str = ".thumb-listing-page ul li .thumb";
+pub const MOEWALLS_ITEM: &str = "#primary ul li";
+pub const MOTIONBGS_ITEM: &str = "div.tmb a[href]";
+pub const MOTIONBGS_TITLE: &str = "span.ttl";
+pub const MOTIONBGS_VIDEO: &str = "video source[src]";
+
+// "next page" links most listings have, checked before the site specific href pattern
+const NEXT_PAGE_LINKS: &str =
+    "link[rel=\"next\"], a[rel=\"next\"], a.next, a.nextpostslink, .pagination .next a, li.next a";
+
 // url normalization
 pub fn absolute_url(href: &str, base: &str) -> String {
     if href.starts_with("http://") || href.starts_with("https://") {
@@ -17,6 +38,48 @@ pub fn absolute_url(href: &str, base: &str) -> String {
     }
 }
 
+// zero items means either the site really has nothing or our selectors went stale.
+// if the results container is still there, or the page says so, it's the former
+pub fn empty_or_changed(document: &Html, container: &str) -> ScrapeError {
+    let container_selector = Selector::parse(container).unwrap();
+    if document.select(&container_selector).next().is_some() {
+        return ScrapeError::Empty;
+    }
+
+    let body_selector = Selector::parse("body").unwrap();
+    let text = document
+        .select(&body_selector)
+        .next()
+        .map(|body| body.text().collect::<String>().to_lowercase())
+        .unwrap_or_default();
+    let no_results_markers = ["no results", "nothing found", "no wallpapers found", "0 results"];
+    if no_results_markers.iter().any(|marker| text.contains(marker)) {
+        return ScrapeError::Empty;
+    }
+
+    ScrapeError::ParseChanged(format!("`{}` no longer matches", container))
+}
+
+// whether a listing links to a page after this one. `next_href` is what this site's link
+// to the following page contains (e.g. "/page/3/"), used when there's no rel=next / .next link
+pub fn has_next_page(document: &Html, next_href: &str) -> bool {
+    let next_selector = Selector::parse(NEXT_PAGE_LINKS).unwrap();
+    if document.select(&next_selector).next().is_some() {
+        return true;
+    }
+
+    // "page=3" must not match "page=30"
+    let link_selector = Selector::parse("a[href]").unwrap();
+    document
+        .select(&link_selector)
+        .filter_map(|link| link.value().attr("href"))
+        .any(|href| {
+            href.match_indices(next_href).any(|(at, _)| {
+                !href[at + next_href.len()..].starts_with(|ch: char| ch.is_ascii_digit())
+            })
+        })
+}
+
 // srcset/cdn parsing
 pub fn pick_image_source(value: &str) -> String {
     if value.is_empty() {
@@ -54,27 +117,22 @@ pub async fn scrape_wallhaven(
     ai_art: bool,
     purity: &str,
     limit: usize,
-) -> Result<Vec<WallpaperItem>, String> {
+) -> Result<SearchPage, ScrapeError> {
     println!("[SCRAPER:WALLHAVEN] Starting scrape - query: '{}', page: {}, limit: {}, purity: {}, ai_art: {}", query, page, limit, purity, ai_art);
-    let client = reqwest::Client::builder()
-        .user_agent("LaxentaInc/1.0")
-        .build()
-        .map_err(|e| e.to_string())?;
-
     let ai_filter = if ai_art { "0" } else { "1" };
     let url = format!(
-        "https://wallhaven.cc/search?q={}&page={}&purity={}&ai_art_filter={}",
+        "{}/search?q={}&page={}&purity={}&ai_art_filter={}",
+        http::base_url("wallhaven", WALLHAVEN_BASE),
         urlencoding::encode(query),
         page,
         purity,
         ai_filter
     );
 
-    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
-    let html = response.text().await.map_err(|e| e.to_string())?;
+    let html = http::get_text(http::get(&url)).await?;
 
     let document = Html::parse_document(&html);
-    let thumb_selector = Selector::parse(".thumb-listing-page ul li .thumb").unwrap();
+    let thumb_selector = Selector::parse(WALLHAVEN_ITEM).unwrap();
     let preview_selector = Selector::parse(".preview").unwrap();
     let thumb_info_selector = Selector::parse(".thumb-info .png span").unwrap();
     let img_selector = Selector::parse("img").unwrap();
@@ -113,7 +171,7 @@ pub async fn scrape_wallhaven(
                         width: None,
                         height: None,
                         tags: None,
-                        detail_url: None,
+                        detail_url: Some(preview_url.to_string()),
                         original: None,
                     });
                 }
@@ -123,161 +181,245 @@ pub async fn scrape_wallhaven(
 
     if items.is_empty() {
         println!("[SCRAPER:WALLHAVEN] No items found");
-        return Err("wallhaven returned no results".to_string());
+        return Err(empty_or_changed(&document, "#thumbs"));
     }
 
-    println!("[SCRAPER:WALLHAVEN] Found {} items", items.len());
-    Ok(items)
+    let has_more = has_next_page(&document, &format!("page={}", page.max(1) + 1));
+    println!("[SCRAPER:WALLHAVEN] Found {} items, more: {}", items.len(), has_more);
+    Ok(SearchPage { items, has_more })
 }
 
-// picre and zerochan scrapers removed
+// wallhaven official api - exact paths, dimensions and colors instead of guessing from html
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WallhavenApiWallpaper {
+    pub id: String,
+    pub url: String,
+    pub purity: String,
+    pub category: String,
+    pub dimension_x: u32,
+    pub dimension_y: u32,
+    pub file_size: u64,
+    pub file_type: String,
+    #[serde(default)]
+    pub colors: Vec<String>,
+    pub path: String,
+    pub thumbs: WallhavenApiThumbs,
+    /// only present on the single wallpaper endpoint
+    #[serde(default)]
+    pub tags: Option<Vec<WallhavenApiTag>>,
+    /// single wallpaper endpoint only, like `tags`
+    #[serde(default)]
+    pub uploader: Option<WallhavenApiUploader>,
+}
 
-// wallpapers.com main scraper
-pub async fn scrape_wallpapers_com(
-    query: &str,
-    limit: usize,
-    page: u32,
-) -> Result<Vec<WallpaperItem>, String> {
-    println!(
-        "[SCRAPER:WALLPAPERS] Starting scrape - query: '{}', page: {}, limit: {}",
-        query, page, limit
-    );
-    let client = reqwest::Client::builder()
-    // again doesnt rlly matter x64 or x84 lol
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WallhavenApiThumbs {
+    pub large: String,
+    pub original: String,
+    pub small: String,
+}
 
-    // wallpapers.com uses ?p=2 ooops
-    let url = if page > 1 {
-        format!(
-            "https://wallpapers.com/search/{}?p={}",
-            urlencoding::encode(query),
-            page
-        )
-    } else {
-        format!(
-            "https://wallpapers.com/search/{}",
-            urlencoding::encode(query)
-        )
-    };
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WallhavenApiTag {
+    pub name: String,
+}
 
-    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
-    let html = response.text().await.map_err(|e| e.to_string())?;
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WallhavenApiUploader {
+    pub username: String,
+}
 
-    let document = Html::parse_document(&html);
-    let item_selector = Selector::parse(".tab-content ul.kw-contents li").unwrap();
-    let figure_selector = Selector::parse("figure").unwrap();
-    let img_selector = Selector::parse("img").unwrap();
+#[derive(Debug, Deserialize)]
+struct WallhavenApiSearch {
+    data: Vec<WallhavenApiWallpaper>,
+    #[serde(default)]
+    meta: Option<WallhavenApiMeta>,
+}
 
-    let mut items = Vec::new();
+#[derive(Debug, Deserialize)]
+struct WallhavenApiMeta {
+    current_page: u32,
+    last_page: u32,
+}
 
-    for element in document.select(&item_selector).take(limit) {
-        if let Some(figure) = element.select(&figure_selector).next() {
-            let title = figure
-                .value()
-                .attr("data-title")
-                .unwrap_or("Wallpapers.com");
-            let key = figure.value().attr("data-key").unwrap_or("");
+#[derive(Debug, Deserialize)]
+struct WallhavenApiSingle {
+    data: WallhavenApiWallpaper,
+}
 
-            if key.is_empty() {
-                continue;
-            }
+impl WallhavenApiWallpaper {
+    pub fn into_item(self) -> WallpaperItem {
+        let tags = self
+            .tags
+            .as_ref()
+            .map(|tags| tags.iter().map(|tag| tag.name.clone()).collect());
+        let original = serde_json::to_value(&self).ok();
+
+        WallpaperItem {
+            id: format!("wallhaven-{}", self.id),
+            source: "wallhaven".to_string(),
+            title: Some(self.id),
+            image_url: self.path,
+            thumbnail_url: Some(self.thumbs.large),
+            media_type: Some("image".to_string()),
+            width: Some(self.dimension_x),
+            height: Some(self.dimension_y),
+            tags,
+            detail_url: Some(self.url),
+            original,
+        }
+    }
+}
 
-            let thumb_src = element
-                .select(&img_selector)
-                .next()
-                .and_then(|img| {
-                    img.value()
-                        .attr("data-src")
-                        .or_else(|| img.value().attr("src"))
-                })
-                .unwrap_or("");
-
-            let thumbnail_url = if !thumb_src.is_empty() {
-                absolute_url(thumb_src, "https://wallpapers.com")
-            } else {
-                String::new()
-            };
-
-            items.push(WallpaperItem {
-                id: format!("wallpapers-{}", key),
-                source: "wallpapers".to_string(),
-                title: Some(title.to_string()),
-                image_url: thumbnail_url.clone(),
-                thumbnail_url: Some(thumbnail_url),
-                media_type: Some("image".to_string()),
-                width: None,
-                height: None,
-                tags: None,
-                detail_url: None,
-                original: None,
-            });
+// 401 here means a bad api key
+async fn wallhaven_api_get(url: &str, api_key: Option<&str>) -> Result<String, ScrapeError> {
+    let mut request = http::get(url);
+    if let Some(key) = api_key.filter(|key| !key.is_empty()) {
+        request = request.header("X-API-Key", key);
+    }
+
+    http::get_text(request).await
+}
+
+// wallhaven api search, `/api/v1/search`
+pub async fn search_wallhaven_api(
+    query: &str,
+    page: u32,
+    ai_art: bool,
+    purity: &str,
+    limit: usize,
+    options: &WallhavenOptions,
+    api_key: Option<&str>,
+) -> Result<SearchPage, ScrapeError> {
+    println!(
+        "[SCRAPER:WALLHAVEN-API] Starting search - query: '{}', page: {}, limit: {}, purity: {}, key: {}",
+        query,
+        page,
+        limit,
+        purity,
+        api_key.is_some()
+    );
+    let api_base = http::base_url("wallhaven", WALLHAVEN_BASE);
+    let mut url = url::Url::parse(&format!("{}/api/v1/search", api_base))
+        .map_err(|e| ScrapeError::Network(format!("bad wallhaven base url: {}", e)))?;
+    {
+        let mut pairs = url.query_pairs_mut();
+        pairs
+            .append_pair("q", query)
+            .append_pair("page", &page.max(1).to_string())
+            .append_pair("purity", purity)
+            .append_pair("ai_art_filter", if ai_art { "0" } else { "1" });
+
+        let optional = [
+            ("sorting", &options.sorting),
+            ("order", &options.order),
+            ("topRange", &options.top_range),
+            ("categories", &options.categories),
+            ("atleast", &options.atleast),
+            ("ratios", &options.ratios),
+            ("seed", &options.seed),
+            ("colors", &options.colors),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+                pairs.append_pair(key, value);
+            }
         }
     }
 
+    let body = wallhaven_api_get(url.as_str(), api_key).await?;
+    let parsed: WallhavenApiSearch = serde_json::from_str(&body)
+        .map_err(|e| ScrapeError::ParseChanged(format!("unexpected wallhaven api response: {}", e)))?;
+
+    // without meta, a full page (24) is the best hint there's another one
+    let has_more = match &parsed.meta {
+        Some(meta) => meta.current_page < meta.last_page,
+        None => parsed.data.len() >= 24,
+    };
+    let items: Vec<WallpaperItem> = parsed
+        .data
+        .into_iter()
+        .take(limit)
+        .map(WallhavenApiWallpaper::into_item)
+        .collect();
+
     if items.is_empty() {
-        println!("[SCRAPER:WALLPAPERS] No items found");
-        return Err("wallpapers.com returned no results".to_string());
+        println!("[SCRAPER:WALLHAVEN-API] No items found");
+        return Err(ScrapeError::Empty);
     }
 
-    println!("[SCRAPER:WALLPAPERS] Found {} items", items.len());
-    Ok(items)
+    println!("[SCRAPER:WALLHAVEN-API] Found {} items, more: {}", items.len(), has_more);
+    Ok(SearchPage { items, has_more })
 }
 
+// wallhaven api single wallpaper, `/api/v1/w/{id}` - the only endpoint that returns tags
+pub async fn fetch_wallhaven_wallpaper(
+    id: &str,
+    api_key: Option<&str>,
+) -> Result<WallhavenApiWallpaper, ScrapeError> {
+    let url = format!(
+        "{}/api/v1/w/{}",
+        http::base_url("wallhaven", WALLHAVEN_BASE),
+        urlencoding::encode(id)
+    );
+
+    let body = wallhaven_api_get(&url, api_key).await?;
+    let parsed: WallhavenApiSingle = serde_json::from_str(&body)
+        .map_err(|e| ScrapeError::ParseChanged(format!("unexpected wallhaven api response: {}", e)))?;
+    Ok(parsed.data)
+}
+
+// picre and zerochan scrapers removed
+
 // wallpaperflare download resolver
 pub async fn resolve_wallpaperflare_download(
     detail_url: &str,
-    client: &reqwest::Client,
-) -> Result<(String, Option<u32>, Option<u32>), String> {
-    let absolute = absolute_url(detail_url, "https://www.wallpaperflare.com");
+) -> Result<(String, Option<u32>, Option<u32>), ScrapeError> {
+    let base = http::base_url("wallpaperflare", WALLPAPERFLARE_BASE);
+    let absolute = absolute_url(detail_url, &base);
     let download_page_url = format!("{}/download", absolute.trim_end_matches('/'));
 
     println!("debug: resolving high-res from: {}", download_page_url);
 
-    if let Ok(response) = client
-        .get(&download_page_url)
+    let download_page = http::get(&download_page_url)
         .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8")
         .header("Referer", &absolute)
-        .header("Upgrade-Insecure-Requests", "1")
-        .send()
-        .await
-    {
-        if let Ok(html) = response.text().await {
-            let document = Html::parse_document(&html);
+        .header("Upgrade-Insecure-Requests", "1");
+
+    if let Ok(html) = http::get_text(download_page).await {
+        let document = Html::parse_document(&html);
+        
+        let show_img_selector = Selector::parse("#show_img").unwrap();
+        let content_url_selector = Selector::parse("img[itemprop=\"contentUrl\"]").unwrap();
+        
+        let high_res_image = document
+            .select(&show_img_selector)
+            .next()
+            .and_then(|el| el.value().attr("src"))
+            .or_else(|| {
+                document
+                    .select(&content_url_selector)
+                    .next()
+                    .and_then(|el| el.value().attr("src"))
+            });
+        
+        if let Some(img_url) = high_res_image {
+            let width_selector = Selector::parse("span[itemprop=\"width\"] span[itemprop=\"value\"]").unwrap();
+            let height_selector = Selector::parse("span[itemprop=\"height\"] span[itemprop=\"value\"]").unwrap();
             
-            let show_img_selector = Selector::parse("#show_img").unwrap();
-            let content_url_selector = Selector::parse("img[itemprop=\"contentUrl\"]").unwrap();
+            let width = document
+                .select(&width_selector)
+                .next()
+                .and_then(|el| el.text().collect::<String>().parse::<u32>().ok());
             
-            let high_res_image = document
-                .select(&show_img_selector)
+            let height = document
+                .select(&height_selector)
                 .next()
-                .and_then(|el| el.value().attr("src"))
-                .or_else(|| {
-                    document
-                        .select(&content_url_selector)
-                        .next()
-                        .and_then(|el| el.value().attr("src"))
-                });
+                .and_then(|el| el.text().collect::<String>().parse::<u32>().ok());
             
-            if let Some(img_url) = high_res_image {
-                let width_selector = Selector::parse("span[itemprop=\"width\"] span[itemprop=\"value\"]").unwrap();
-                let height_selector = Selector::parse("span[itemprop=\"height\"] span[itemprop=\"value\"]").unwrap();
-                
-                let width = document
-                    .select(&width_selector)
-                    .next()
-                    .and_then(|el| el.text().collect::<String>().parse::<u32>().ok());
-                
-                let height = document
-                    .select(&height_selector)
-                    .next()
-                    .and_then(|el| el.text().collect::<String>().parse::<u32>().ok());
-                
-                let final_url = absolute_url(img_url, "https://www.wallpaperflare.com");
-                println!("ok: found high-res image: {}", final_url);
-                return Ok((final_url, width, height));
-            }
+            let final_url = absolute_url(img_url, &base);
+            println!("ok: found high-res image: {}", final_url);
+            return Ok((final_url, width, height));
         }
     }
 
@@ -286,15 +428,13 @@ pub async fn resolve_wallpaperflare_download(
         absolute
     );
 
-    match client.get(&absolute)
+    let detail_page = http::get(&absolute)
         .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8")
-        .header("Referer", "https://www.wallpaperflare.com/")
-        .header("Upgrade-Insecure-Requests", "1")
-        .send()
-        .await
-    {
-        Ok(response) => {
-            let html = response.text().await.map_err(|e| e.to_string())?;
+        .header("Referer", format!("{}/", base))
+        .header("Upgrade-Insecure-Requests", "1");
+
+    match http::get_text(detail_page).await {
+        Ok(html) => {
             let document = Html::parse_document(&html);
             
             let content_url_selector = Selector::parse("img[itemprop=\"contentUrl\"]").unwrap();
@@ -331,14 +471,16 @@ pub async fn resolve_wallpaperflare_download(
                 
                 let (width, height) = parse_resolution(meta_description);
                 
-                let final_url = absolute_url(&img_url, "https://www.wallpaperflare.com");
+                let final_url = absolute_url(&img_url, &base);
                 println!("ok: found image from detail page: {}", final_url);
                 return Ok((final_url, width, height));
             }
             
-            Err("no image found on detail page".to_string())
+            Err(ScrapeError::ParseChanged(
+                "no image found on detail page".to_string(),
+            ))
         }
-        Err(e) => Err(format!("failed to fetch detail page: {}", e)),
+        Err(e) => Err(e),
     }
 }
 
@@ -347,40 +489,31 @@ pub async fn scrape_wallpaperflare(
     query: &str,
     limit: usize,
     page: u32,
-) -> Result<Vec<WallpaperItem>, String> {
+) -> Result<SearchPage, ScrapeError> {
     println!(
         "[SCRAPER:WALLPAPERFLARE] Starting scrape - query: '{}', page: {}, limit: {}",
         query, page, limit
     );
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
+    let base = http::base_url("wallpaperflare", WALLPAPERFLARE_BASE);
 
     // wf uh uses &page=2 for pagination
     let url = if page > 1 {
         format!(
-            "https://www.wallpaperflare.com/search?wallpaper={}&page={}",
+            "{}/search?wallpaper={}&page={}",
+            base,
             urlencoding::encode(query),
             page
         )
     } else {
-        format!(
-            "https://www.wallpaperflare.com/search?wallpaper={}",
-            urlencoding::encode(query)
-        )
+        format!("{}/search?wallpaper={}", base, urlencoding::encode(query))
     };
 
-    let response = client
-        .get(&url)
+    let request = http::get(&url)
         .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8")
-        .header("Referer", "https://www.wallpaperflare.com/")
-        .header("Upgrade-Insecure-Requests", "1")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        .header("Referer", format!("{}/", base))
+        .header("Upgrade-Insecure-Requests", "1");
 
-    let html = response.text().await.map_err(|e| e.to_string())?;
+    let html = http::get_text(request).await?;
 
     #[derive(Clone)]
     struct TempItem {
@@ -392,6 +525,7 @@ pub async fn scrape_wallpaperflare(
 
     let mut temp_items = Vec::new();
     let mut seen_ids = HashSet::new();
+    let has_more;
 
     {
         let document = Html::parse_document(&html);
@@ -415,7 +549,7 @@ pub async fn scrape_wallpaperflare(
                 continue;
             }
 
-            let normalized_href = absolute_url(href, "https://www.wallpaperflare.com");
+            let normalized_href = absolute_url(href, &base);
             if !normalized_href.to_lowercase().contains("wallpaper") {
                 continue;
             }
@@ -452,7 +586,7 @@ pub async fn scrape_wallpaperflare(
             }
             seen_ids.insert(id.clone());
 
-            let thumbnail_url = absolute_url(&thumb, "https://www.wallpaperflare.com");
+            let thumbnail_url = absolute_url(&thumb, &base);
             let title = media_elem
                 .value()
                 .attr("alt")
@@ -467,11 +601,12 @@ pub async fn scrape_wallpaperflare(
                 detail_url: normalized_href,
             });
         }
-    }
 
-    if temp_items.is_empty() {
-        println!("[SCRAPER:WALLPAPERFLARE] No items found");
-        return Err("wallpaperflare returned no results".to_string());
+        if temp_items.is_empty() {
+            println!("[SCRAPER:WALLPAPERFLARE] No items found");
+            return Err(empty_or_changed(&document, "#gallery"));
+        }
+        has_more = has_next_page(&document, &format!("page={}", page.max(1) + 1));
     }
 
     println!(
@@ -495,8 +630,8 @@ pub async fn scrape_wallpaperflare(
         })
         .collect();
 
-    println!("[SCRAPER:WALLPAPERFLARE] Returning {} items", items.len());
-    Ok(items)
+    println!("[SCRAPER:WALLPAPERFLARE] Returning {} items, more: {}", items.len(), has_more);
+    Ok(SearchPage { items, has_more })
 }
 
 // moewalls main scraper
@@ -505,40 +640,32 @@ pub async fn scrape_moewalls(
     limit: usize,
     include_videos: bool,
     page: u32,
-) -> Result<Vec<WallpaperItem>, String> {
+) -> Result<SearchPage, ScrapeError> {
     println!(
         "[SCRAPER:MOEWALLS] Starting scrape - query: {:?}, page: {}, limit: {}",
         query, page, limit
     );
-    let client = reqwest::Client::builder()
-        .user_agent("WallpaperApp/1.0")
-        .build()
-        .map_err(|e| e.to_string())?;
+    let base = http::base_url("moewalls", MOEWALLS_BASE);
 
     // meowwalls uses /page/2/ for pagination
     let url = if let Some(q) = query {
         if page > 1 {
-            format!(
-                "https://moewalls.com/page/{}/?s={}",
-                page,
-                urlencoding::encode(q)
-            )
+            format!("{}/page/{}/?s={}", base, page, urlencoding::encode(q))
         } else {
-            format!("https://moewalls.com/?s={}", urlencoding::encode(q))
+            format!("{}/?s={}", base, urlencoding::encode(q))
         }
     } else {
         if page > 1 {
-            format!("https://moewalls.com/page/{}/", page)
+            format!("{}/page/{}/", base, page)
         } else {
-            "https://moewalls.com/".to_string()
+            format!("{}/", base)
         }
     };
 
-    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
-    let html = response.text().await.map_err(|e| e.to_string())?;
+    let html = http::get_text(http::get(&url)).await?;
 
     let document = Html::parse_document(&html);
-    let item_selector = Selector::parse("#primary ul li").unwrap();
+    let item_selector = Selector::parse(MOEWALLS_ITEM).unwrap();
     let anchor_selector = Selector::parse("a").unwrap();
     let img_selector = Selector::parse("img").unwrap();
 
@@ -592,7 +719,7 @@ pub async fn scrape_moewalls(
                         width: None,
                         height: None,
                         tags: None,
-                        detail_url: None,
+                        detail_url: anchor.value().attr("href").map(|href| absolute_url(href, &base)),
                         original: None,
                     });
                 }
@@ -602,11 +729,88 @@ pub async fn scrape_moewalls(
 
     if items.is_empty() {
         println!("[SCRAPER:MOEWALLS] No items found");
-        return Err("moewalls returned no results".to_string());
+        return Err(empty_or_changed(&document, "#primary"));
+    }
+
+    let has_more = has_next_page(&document, &format!("/page/{}/", page.max(1) + 1));
+    println!("[SCRAPER:MOEWALLS] Found {} items, more: {}", items.len(), has_more);
+    Ok(SearchPage { items, has_more })
+}
+
+// moewalls post page download links. the button points at a download script (data-url or href),
+// some posts list one link per resolution
+pub const MOEWALLS_DOWNLOAD: &str = "#moe-download, a[href*=\".mp4\"], [data-url]";
+
+// moewalls full video resolver, every download link on the post page as a variant.
+// largest first, the preview clip is never one of them
+pub async fn resolve_moewalls_download(detail_url: &str) -> Result<Vec<MediaVariant>, ScrapeError> {
+    let base = http::base_url("moewalls", MOEWALLS_BASE);
+    let absolute = absolute_url(detail_url, &base);
+    println!("info: fetching moewalls post: {}", absolute);
+
+    let html = http::get_text(http::get(&absolute).header("Referer", format!("{}/", base))).await?;
+    let document = Html::parse_document(&html);
+
+    // what the post says about the video, for links that don't mention it themselves
+    let body_selector = Selector::parse("body").unwrap();
+    let body = document
+        .select(&body_selector)
+        .next()
+        .map(|body| body.text().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+    let resolution_regex = Regex::new(r"(?i)resolution\s*:?\s*(\d{3,5})\s*[x×]\s*(\d{3,5})").unwrap();
+    let (page_width, page_height) = resolution_regex
+        .captures(&body)
+        .map(|caps| (caps[1].parse().ok(), caps[2].parse().ok()))
+        .unwrap_or((None, None));
+    let size_regex = Regex::new(r"(?i)size\s*:?\s*(\d+(?:[.,]\d+)?\s*(?:gb|mb|kb))").unwrap();
+    let page_size = size_regex.captures(&body).and_then(|caps| parse_file_size(&caps[1]));
+
+    let download_selector = Selector::parse(MOEWALLS_DOWNLOAD).unwrap();
+    let mut seen = HashSet::new();
+    let mut variants: Vec<MediaVariant> = Vec::new();
+    for element in document.select(&download_selector) {
+        let Some(href) = element
+            .value()
+            .attr("data-url")
+            .or_else(|| element.value().attr("href"))
+        else {
+            continue;
+        };
+        let is_download = href.contains(".mp4") || href.contains("download");
+        if !is_download || href.contains("-preview") || !seen.insert(href.to_string()) {
+            continue;
+        }
+
+        let label = element
+            .text()
+            .flat_map(str::split_whitespace)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (width, height) = match parse_resolution(&format!("{} {}", label, href)) {
+            (Some(width), Some(height)) => (Some(width), Some(height)),
+            _ => (page_width, page_height),
+        };
+        variants.push(MediaVariant {
+            url: absolute_url(href, &base),
+            file_size: parse_file_size(&label).or(page_size),
+            label: if label.is_empty() { None } else { Some(label) },
+            width,
+            height,
+        });
+    }
+
+    if variants.is_empty() {
+        return Err(ScrapeError::ParseChanged(
+            "no download link on moewalls post".to_string(),
+        ));
     }
 
-    println!("[SCRAPER:MOEWALLS] Found {} items", items.len());
-    Ok(items)
+    variants.sort_by_key(|variant| {
+        std::cmp::Reverse(variant.width.unwrap_or(0) as u64 * variant.height.unwrap_or(0) as u64)
+    });
+    println!("[success] found {} moewalls download(s), best: {}", variants.len(), variants[0].url);
+    Ok(variants)
 }
 
 // slugify for motionbgs
@@ -630,16 +834,11 @@ pub async fn scrape_motionbgs(
     query: &str,
     limit: usize,
     page: u32,
-) -> Result<Vec<WallpaperItem>, String> {
+) -> Result<SearchPage, ScrapeError> {
     println!(
         "[SCRAPER:MOTIONBGS] Starting scrape - query: '{}', page: {}, limit: {}",
         query, page, limit
     );
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
-
     let slug = {
         let slugged = motionbgs_tag_slug(query);
         if slugged.is_empty() {
@@ -649,22 +848,22 @@ pub async fn scrape_motionbgs(
         }
     };
 
+    let base = http::base_url("motionbgs", MOTIONBGS_BASE);
     let page_index = page.max(1);
     let url = if page_index <= 1 {
-        format!("https://motionbgs.com/tag:{}/", slug)
+        format!("{}/tag:{}/", base, slug)
     } else {
-        format!("https://motionbgs.com/tag:{}/{}/", slug, page_index)
+        format!("{}/tag:{}/{}/", base, slug, page_index)
     };
 
     println!("info: fetching motionbgs: {}", url);
 
-    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
-    let html = response.text().await.map_err(|e| e.to_string())?;
+    let html = http::get_text(http::get(&url)).await?;
 
     let document = Html::parse_document(&html);
-    let tmb_selector = Selector::parse("div.tmb a[href]").unwrap();
+    let tmb_selector = Selector::parse(MOTIONBGS_ITEM).unwrap();
     let img_selector = Selector::parse("img").unwrap();
-    let title_selector = Selector::parse("span.ttl").unwrap();
+    let title_selector = Selector::parse(MOTIONBGS_TITLE).unwrap();
     let format_selector = Selector::parse("span.frm").unwrap();
 
     let mut items = Vec::new();
@@ -705,8 +904,8 @@ pub async fn scrape_motionbgs(
             .trim_end_matches('/')
             .to_string();
 
-        let thumbnail_url = absolute_url(thumbnail, "https://motionbgs.com");
-        let full_detail_url = absolute_url(detail_url, "https://motionbgs.com");
+        let thumbnail_url = absolute_url(thumbnail, &base);
+        let full_detail_url = absolute_url(detail_url, &base);
 
         let (width, height) = if format.contains("4K") {
             (Some(3840), Some(2160))
@@ -737,39 +936,35 @@ pub async fn scrape_motionbgs(
 
     if items.is_empty() {
         println!("[SCRAPER:MOTIONBGS] No items found");
-        return Err("motionbgs returned no results".to_string());
+        return Err(empty_or_changed(&document, "div.tmb"));
     }
 
-    println!("[SCRAPER:MOTIONBGS] Found {} items", items.len());
-    Ok(items)
+    let has_more = has_next_page(&document, &format!("/tag:{}/{}/", slug, page_index + 1));
+    println!("[SCRAPER:MOTIONBGS] Found {} items, more: {}", items.len(), has_more);
+    Ok(SearchPage { items, has_more })
 }
 
 // motionbgs detail extractor - fixed lol
-pub async fn scrape_motionbgs_detail(detail_url: &str) -> Result<(String, Option<String>), String> {
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
-
+pub async fn scrape_motionbgs_detail(
+    detail_url: &str,
+) -> Result<(String, Option<String>), ScrapeError> {
     println!("info: fetching motionbgs detail: {}", detail_url);
+    let base = http::base_url("motionbgs", MOTIONBGS_BASE);
 
-    let response = client
-        .get(detail_url)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let html = response.text().await.map_err(|e| e.to_string())?;
+    let html = http::get_text(http::get(detail_url)).await?;
 
     let document = Html::parse_document(&html);
 
     // Extract preview video from <video><source src="..."> tag
-    let video_selector = Selector::parse("video source[src]").unwrap();
+    let video_selector = Selector::parse(MOTIONBGS_VIDEO).unwrap();
     let preview_url = document
         .select(&video_selector)
         .next()
         .and_then(|source| source.value().attr("src"))
-        .map(|src| absolute_url(src, "https://motionbgs.com"))
-        .ok_or_else(|| "preview video url not found in video tag".to_string())?;
+        .map(|src| absolute_url(src, &base))
+        .ok_or_else(|| {
+            ScrapeError::ParseChanged("preview video url not found in video tag".to_string())
+        })?;
 
     println!("[info] found preview video url: {}", preview_url);
     let download_selector = Selector::parse("div.download a[href*='/dl/4k/']").unwrap(); // done OK got the link
@@ -777,7 +972,7 @@ pub async fn scrape_motionbgs_detail(detail_url: &str) -> Result<(String, Option
         .select(&download_selector)
         .next()
         .and_then(|link| link.value().attr("href"))
-        .map(|href| absolute_url(href, "https://motionbgs.com"));
+        .map(|href| absolute_url(href, &base));
 
     if let Some(ref url) = download_4k_url {
         println!("[success] found 4k download url: {}", url);
@@ -786,4 +981,179 @@ pub async fn scrape_motionbgs_detail(detail_url: &str) -> Result<(String, Option
     }
 
     Ok((preview_url, download_4k_url))
-}
\ No newline at end of file
+}
+// detail page metadata (tags, uploader, size, license). schema.org itemprops and rel=tag links
+// are tried on every page, `DetailSelectors` adds what each site does its own way
+pub struct DetailSelectors {
+    /// tag/category links
+    pub tags: &'static str,
+    /// elements whose text holds a "1920x1080" and/or "12.3 MB", e.g. download buttons
+    pub info: Option<&'static str>,
+}
+
+pub const WALLPAPERFLARE_DETAILS: DetailSelectors = DetailSelectors {
+    tags: "#tags a, ul.tags a",
+    info: None,
+};
+pub const MOEWALLS_DETAILS: DetailSelectors = DetailSelectors {
+    tags: ".entry-tags a, .tags-links a, .cat-links a, .post-tags a",
+    info: Some("a[href*=\"download\"], .download, .wallpaper-info"),
+};
+pub const MOTIONBGS_DETAILS: DetailSelectors = DetailSelectors {
+    tags: "a[href^=\"/tag:\"]",
+    info: Some("div.download a"),
+};
+/// for sites without anything of their own, only the generic selectors apply
+pub const GENERIC_DETAILS: DetailSelectors = DetailSelectors {
+    tags: DETAIL_GENERIC_TAGS,
+    info: None,
+};
+
+const DETAIL_GENERIC_TAGS: &str = "a[rel~=\"tag\"], [itemprop=\"keywords\"]";
+const DETAIL_UPLOADER: &str =
+    "[itemprop=\"author\"] [itemprop=\"name\"], [itemprop=\"author\"], a[rel=\"author\"], .author a, meta[name=\"author\"]";
+
+// "12.3 MB" / "850KB" / "1,2 mb" to bytes
+pub fn parse_file_size(text: &str) -> Option<u64> {
+    let re = Regex::new(r"(?i)(\d+(?:[.,]\d+)?)\s*(gb|mb|kb|gib|mib|kib)\b").unwrap();
+    let caps = re.captures(text)?;
+    let value: f64 = caps[1].replace(',', ".").parse().ok()?;
+    let unit = match caps[2].to_lowercase().as_str() {
+        "gb" | "gib" => 1024.0 * 1024.0 * 1024.0,
+        "mb" | "mib" => 1024.0 * 1024.0,
+        _ => 1024.0,
+    };
+    Some((value * unit) as u64)
+}
+
+// text or content="" of an element, whitespace collapsed
+fn element_text(element: scraper::ElementRef) -> String {
+    let text = match element.value().attr("content") {
+        Some(content) => content.to_string(),
+        None => element.text().collect::<String>(),
+    };
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn first_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).unwrap();
+    document
+        .select(&selector)
+        .map(element_text)
+        .find(|text| !text.is_empty())
+}
+
+fn first_number(document: &Html, selector: &str) -> Option<u32> {
+    first_text(document, selector).and_then(|text| {
+        text.chars()
+            .filter(|ch| ch.is_ascii_digit())
+            .collect::<String>()
+            .parse()
+            .ok()
+    })
+}
+
+pub fn parse_detail_page(html: &str, selectors: &DetailSelectors) -> WallpaperDetails {
+    let document = Html::parse_document(html);
+    let mut details = WallpaperDetails::default();
+
+    // keywords come as one comma separated string, links one tag each
+    let mut seen = HashSet::new();
+    for selector in [selectors.tags, DETAIL_GENERIC_TAGS] {
+        let selector = Selector::parse(selector).unwrap();
+        for element in document.select(&selector) {
+            for tag in element_text(element).split(',') {
+                let tag = tag.trim();
+                if !tag.is_empty() && tag.len() <= 40 && seen.insert(tag.to_lowercase()) {
+                    details.tags.push(tag.to_string());
+                }
+            }
+        }
+    }
+
+    // a whole author block is not a name
+    details.uploader = first_text(&document, DETAIL_UPLOADER).filter(|name| name.len() <= 60);
+    details.width = first_number(&document, "[itemprop=\"width\"] [itemprop=\"value\"], [itemprop=\"width\"]");
+    details.height = first_number(&document, "[itemprop=\"height\"] [itemprop=\"value\"], [itemprop=\"height\"]");
+    details.file_size = first_text(&document, "[itemprop=\"contentSize\"]").and_then(|text| parse_file_size(&text));
+
+    let license_selector = Selector::parse("[itemprop=\"license\"], a[rel~=\"license\"]").unwrap();
+    details.license = document.select(&license_selector).next().and_then(|element| {
+        let text = element_text(element);
+        if text.is_empty() {
+            element.value().attr("href").map(String::from)
+        } else {
+            Some(text)
+        }
+    });
+
+    // biggest resolution the info elements mention, with the size next to it
+    if let Some(info) = selectors.info {
+        let info_selector = Selector::parse(info).unwrap();
+        let best = document
+            .select(&info_selector)
+            .map(element_text)
+            .filter_map(|text| match parse_resolution(&text) {
+                (Some(width), Some(height)) => Some((width, height, parse_file_size(&text))),
+                _ => None,
+            })
+            .max_by_key(|(width, height, _)| *width as u64 * *height as u64);
+        if let Some((width, height, size)) = best {
+            details.width = details.width.or(Some(width));
+            details.height = details.height.or(Some(height));
+            details.file_size = details.file_size.or(size);
+        }
+        if details.file_size.is_none() {
+            details.file_size = document
+                .select(&info_selector)
+                .find_map(|element| parse_file_size(&element_text(element)));
+        }
+    }
+
+    // last resort, labelled values in the page text
+    if details.width.is_none() || details.file_size.is_none() {
+        let body_selector = Selector::parse("body").unwrap();
+        let body = document
+            .select(&body_selector)
+            .next()
+            .map(element_text)
+            .unwrap_or_default();
+        if details.width.is_none() {
+            let re = Regex::new(r"(?i)resolution\s*:?\s*(\d{3,5})\s*[x×]\s*(\d{3,5})").unwrap();
+            if let Some(caps) = re.captures(&body) {
+                details.width = caps[1].parse().ok();
+                details.height = caps[2].parse().ok();
+            }
+        }
+        if details.file_size.is_none() {
+            let re = Regex::new(r"(?i)(?:file\s*)?size\s*:?\s*(\d+(?:[.,]\d+)?\s*(?:gb|mb|kb))").unwrap();
+            details.file_size = re.captures(&body).and_then(|caps| parse_file_size(&caps[1]));
+        }
+    }
+
+    details
+}
+
+// fetch + parse a detail page, `detail_url` may be relative to the site's base
+pub async fn scrape_detail_page(
+    site: &str,
+    default_base: &str,
+    detail_url: &str,
+    selectors: &DetailSelectors,
+) -> Result<WallpaperDetails, ScrapeError> {
+    let base = http::base_url(site, default_base);
+    let url = absolute_url(detail_url, &base);
+    println!("[SCRAPER:DETAILS] fetching {}", url);
+
+    let html = http::get_text(http::get(&url)).await?;
+    let details = parse_detail_page(&html, selectors);
+    println!(
+        "[SCRAPER:DETAILS] {}: {} tags, {:?}x{:?}, {:?} bytes",
+        site,
+        details.tags.len(),
+        details.width,
+        details.height,
+        details.file_size
+    );
+    Ok(details)
+}
@@ -0,0 +1,163 @@
+/// on-disk cache of per-source search results, one json file per (source, query, page, filters).
+/// fresh entries are served without touching the network, older ones are kept around
+/// as the fallback when a source fails (offline, blocked, timed out)
+use crate::models::{SearchPage, WallpaperItem};
+use crate::sources::SearchParams;
+use crate::storage::{get_app_data_dir, load_settings};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// entries older than this aren't even used as a fallback, `prune` deletes them
+const MAX_STALE_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    source: String,
+    /// full key, kept so a hash collision can't serve the wrong page
+    key: String,
+    stored_at: i64,
+    items: Vec<WallpaperItem>,
+    has_more: bool,
+}
+
+/// cached page and how old it is
+#[derive(Debug)]
+pub struct CachedPage {
+    pub items: Vec<WallpaperItem>,
+    pub has_more: bool,
+    pub age: Duration,
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn get_search_cache_dir() -> Result<PathBuf, String> {
+    let dir = get_app_data_dir()?.join("search_cache");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("failed to create search cache directory: {}", e))?;
+    Ok(dir)
+}
+
+/// "  Blue   Sky " and "blue sky" are the same search
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn cache_key(source: &str, params: &SearchParams) -> String {
+    format!(
+        "{}|{}|{}|{}|{}|{}|{}|{}|{}",
+        source,
+        normalize_query(&params.query),
+        params.exclude.join(","),
+        params.page,
+        params.limit,
+        params.purity,
+        params.ai_art,
+        params.include_videos,
+        serde_json::to_string(&params.wallhaven).unwrap_or_default()
+    )
+}
+
+fn entry_path(key: &str) -> Result<PathBuf, String> {
+    Ok(get_search_cache_dir()?.join(format!("{:x}.json", md5::compute(key.as_bytes()))))
+}
+
+/// how long a source's results count as fresh, `source_cache_ttl_minutes` overrides the default
+pub fn ttl(source: &str) -> Duration {
+    let settings = load_settings();
+    let minutes = settings
+        .source_cache_ttl_minutes
+        .get(source)
+        .copied()
+        .unwrap_or(settings.search_cache_ttl_minutes);
+    Duration::from_secs(minutes * 60)
+}
+
+fn read(source: &str, params: &SearchParams) -> Option<CachedPage> {
+    let key = cache_key(source, params);
+    let content = std::fs::read_to_string(entry_path(&key).ok()?).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&content).ok()?;
+    if entry.key != key {
+        return None;
+    }
+
+    let age = Duration::from_secs(now().saturating_sub(entry.stored_at).max(0) as u64);
+    if age > MAX_STALE_AGE {
+        return None;
+    }
+    Some(CachedPage {
+        items: entry.items,
+        has_more: entry.has_more,
+        age,
+    })
+}
+
+/// cached page that's still within the source's ttl, a ttl of 0 never hits
+pub fn fresh(source: &str, params: &SearchParams) -> Option<CachedPage> {
+    let ttl = ttl(source);
+    if ttl.is_zero() {
+        return None;
+    }
+    read(source, params).filter(|page| page.age < ttl)
+}
+
+/// cached page of any age (up to a week), for when the live search failed
+pub fn stale(source: &str, params: &SearchParams) -> Option<CachedPage> {
+    read(source, params)
+}
+
+pub fn store(source: &str, params: &SearchParams, page: &SearchPage) {
+    let key = cache_key(source, params);
+    let entry = CacheEntry {
+        source: source.to_string(),
+        key,
+        stored_at: now(),
+        items: page.items.clone(),
+        has_more: page.has_more,
+    };
+
+    let result = entry_path(&entry.key).and_then(|path| {
+        let json = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("[CACHE] failed to store {} results: {}", source, e);
+    }
+}
+
+/// drop entries too old to ever be served, run once at startup
+pub fn prune() {
+    let Ok(entries) =
+        get_search_cache_dir().and_then(|dir| std::fs::read_dir(dir).map_err(|e| e.to_string()))
+    else {
+        return;
+    };
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let expired = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheEntry>(&content).ok())
+            .map(|entry| now().saturating_sub(entry.stored_at) > MAX_STALE_AGE.as_secs() as i64)
+            // unreadable or from an older format
+            .unwrap_or(true);
+        if expired && std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+
+    if removed > 0 {
+        println!("[CACHE] pruned {} expired search cache entries", removed);
+    }
+}
@@ -0,0 +1,532 @@
+/// booru-style post apis (danbooru, gelbooru, konachan/yande.re), one configurable adapter.
+/// instances come from `AppSettings.booru_sources`, ratings are mapped onto the
+/// wallhaven purity bits so the same `purity` param works everywhere
+use super::{unsupported_browse, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::http;
+use crate::models::{
+    BooruConfig, BooruKind, BrowseMode, SearchPage, SourceCapabilities, WallpaperItem,
+};
+use crate::scraper::absolute_url;
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// wallhaven purity class of a booru rating: 0 sfw, 1 sketchy, 2 nsfw.
+/// questionable is sketchy on every kind, explicit is nsfw
+fn purity_class(kind: BooruKind, rating: &str) -> usize {
+    match (kind, rating) {
+        (BooruKind::Danbooru, "g") => 0,
+        (BooruKind::Danbooru, "s" | "q") => 1,
+        (BooruKind::Danbooru, "e") => 2,
+        // older gelbooru posts still say "safe"
+        (BooruKind::Gelbooru, "general" | "safe") => 0,
+        (BooruKind::Gelbooru, "sensitive" | "questionable") => 1,
+        (BooruKind::Gelbooru, "explicit") => 2,
+        // moebooru's "safe" covers what the others call general + sensitive
+        (BooruKind::Moebooru, "s") => 0,
+        (BooruKind::Moebooru, "q") => 1,
+        (BooruKind::Moebooru, "e") => 2,
+        // unknown ratings are treated as explicit
+        _ => 2,
+    }
+}
+
+/// "100" / "110" / "111" style bits, anything malformed means sfw only
+fn purity_allows(purity: &str, class: usize) -> bool {
+    purity.as_bytes().get(class) == Some(&b'1')
+}
+
+/// ordering metatag for a browse listing, `None` when the default (newest) order is wanted
+fn order_tag(kind: BooruKind, mode: BrowseMode) -> Option<&'static str> {
+    match (kind, mode) {
+        (_, BrowseMode::Latest) => None,
+        (BooruKind::Gelbooru, BrowseMode::Toplist) => Some("sort:score"),
+        (BooruKind::Gelbooru, BrowseMode::Random) => Some("sort:random"),
+        (_, BrowseMode::Toplist) => Some("order:score"),
+        (_, BrowseMode::Random) => Some("order:random"),
+        (_, BrowseMode::Featured | BrowseMode::Hot) => None,
+    }
+}
+
+/// server-side rating tag for the common sfw-only case, so pages aren't mostly filtered out
+fn sfw_tag(kind: BooruKind) -> &'static str {
+    match kind {
+        BooruKind::Danbooru => "rating:g",
+        BooruKind::Gelbooru => "rating:general",
+        BooruKind::Moebooru => "rating:s",
+    }
+}
+
+fn as_u32(value: Option<&Value>) -> Option<u32> {
+    match value? {
+        Value::Number(n) => n.as_u64().map(|n| n as u32),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn as_str<'a>(post: &'a Value, key: &str) -> Option<&'a str> {
+    post.get(key)
+        .and_then(|value| value.as_str())
+        .filter(|value| !value.is_empty())
+}
+
+fn as_id(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        _ => None,
+    }
+}
+
+pub struct Booru {
+    config: BooruConfig,
+}
+
+impl Booru {
+    pub fn new(config: BooruConfig) -> Self {
+        Self { config }
+    }
+
+    fn base(&self) -> String {
+        http::base_url(&self.config.id, &self.config.base_url)
+    }
+
+    fn search_url(&self, tags: &str, page: u32, limit: usize) -> String {
+        let base = self.base();
+        let tags = urlencoding::encode(tags);
+        let page = page.max(1);
+        let user = self.config.user.as_deref().filter(|user| !user.is_empty());
+        let api_key = self.config.api_key.as_deref().filter(|key| !key.is_empty());
+
+        let mut url = match self.config.kind {
+            BooruKind::Danbooru => {
+                format!(
+                    "{}/posts.json?tags={}&page={}&limit={}",
+                    base, tags, page, limit
+                )
+            }
+            // gelbooru pages are 0-based
+            BooruKind::Gelbooru => format!(
+                "{}/index.php?page=dapi&s=post&q=index&json=1&tags={}&pid={}&limit={}",
+                base,
+                tags,
+                page - 1,
+                limit
+            ),
+            BooruKind::Moebooru => {
+                format!(
+                    "{}/post.json?tags={}&page={}&limit={}",
+                    base, tags, page, limit
+                )
+            }
+        };
+
+        if let (Some(user), Some(api_key)) = (user, api_key) {
+            let user_param = match self.config.kind {
+                BooruKind::Gelbooru => "user_id",
+                _ => "login",
+            };
+            url.push_str(&format!(
+                "&{}={}&api_key={}",
+                user_param,
+                urlencoding::encode(user),
+                urlencoding::encode(api_key)
+            ));
+        }
+        url
+    }
+
+    fn post_url(&self, id: &str) -> String {
+        let base = self.base();
+        match self.config.kind {
+            BooruKind::Danbooru => format!("{}/posts/{}", base, id),
+            BooruKind::Gelbooru => format!("{}/index.php?page=post&s=view&id={}", base, id),
+            BooruKind::Moebooru => format!("{}/post/show/{}", base, id),
+        }
+    }
+
+    fn post_to_item(&self, post: Value, params: &SearchParams) -> Option<WallpaperItem> {
+        let kind = self.config.kind;
+        let base = self.base();
+
+        let id = as_id(post.get("id"))?;
+        // danbooru leaves file_url out on posts anonymous users can't see
+        let file_url = absolute_url(as_str(&post, "file_url")?, &base);
+
+        let rating = as_str(&post, "rating").unwrap_or("e");
+        if !purity_allows(&params.purity, purity_class(kind, rating)) {
+            return None;
+        }
+
+        let (width, height) = match kind {
+            BooruKind::Danbooru => (
+                as_u32(post.get("image_width")),
+                as_u32(post.get("image_height")),
+            ),
+            _ => (as_u32(post.get("width")), as_u32(post.get("height"))),
+        };
+        if width.unwrap_or(0) < self.config.min_width
+            || height.unwrap_or(0) < self.config.min_height
+        {
+            return None;
+        }
+
+        let extension = as_str(&post, "file_ext")
+            .map(|ext| ext.to_lowercase())
+            .or_else(|| {
+                file_url
+                    .rsplit('.')
+                    .next()
+                    .map(|ext| ext.split('?').next().unwrap_or(ext).to_lowercase())
+            })
+            .unwrap_or_default();
+        let media_type = match extension.as_str() {
+            "jpg" | "jpeg" | "png" | "webp" => "image",
+            "mp4" | "webm" if params.include_videos => "video",
+            // gifs, ugoira zips and unwanted videos
+            _ => return None,
+        };
+
+        let tags_key = match kind {
+            BooruKind::Danbooru => "tag_string",
+            _ => "tags",
+        };
+        let tags: Vec<String> = as_str(&post, tags_key)
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|tag| tag.to_string())
+            .collect();
+
+        let preview_keys: &[&str] = match kind {
+            BooruKind::Danbooru => &["large_file_url", "preview_file_url"],
+            _ => &["sample_url", "preview_url"],
+        };
+        let thumbnail = preview_keys
+            .iter()
+            .find_map(|key| as_str(&post, key))
+            .map(|url| absolute_url(url, &base));
+
+        let title = if tags.is_empty() {
+            format!("{} #{}", self.config.name, id)
+        } else {
+            tags.iter()
+                .take(3)
+                .map(|tag| tag.replace('_', " "))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        Some(WallpaperItem {
+            id: format!("{}-{}", self.config.id, id),
+            source: self.config.id.clone(),
+            title: Some(title),
+            image_url: file_url,
+            thumbnail_url: thumbnail,
+            media_type: Some(media_type.to_string()),
+            width,
+            height,
+            tags: if tags.is_empty() { None } else { Some(tags) },
+            detail_url: Some(self.post_url(&id)),
+            original: Some(post),
+        })
+    }
+
+    /// one api page as items, rating/size filtering can leave fewer than `page_size`
+    fn parse_page(
+        &self,
+        body: &str,
+        params: &SearchParams,
+        page_size: usize,
+    ) -> Result<SearchPage, ScrapeError> {
+        // gelbooru wraps posts in {"post": [...]}, and drops the key entirely when empty
+        let json: Value = serde_json::from_str(body)
+            .map_err(|e| ScrapeError::ParseChanged(format!("{} api: {}", self.config.id, e)))?;
+        let posts = match json {
+            Value::Array(posts) => posts,
+            Value::Object(mut object) => match object.remove("post") {
+                Some(Value::Array(posts)) => posts,
+                Some(post @ Value::Object(_)) => vec![post],
+                _ if self.config.kind == BooruKind::Gelbooru => Vec::new(),
+                _ => {
+                    return Err(ScrapeError::ParseChanged(format!(
+                        "{} api returned an object without posts",
+                        self.config.id
+                    )))
+                }
+            },
+            _ => {
+                return Err(ScrapeError::ParseChanged(format!(
+                    "{} api returned unexpected json",
+                    self.config.id
+                )))
+            }
+        };
+
+        // a short page is the last one, filtering doesn't matter here
+        let has_more = posts.len() >= page_size;
+        let items: Vec<WallpaperItem> = posts
+            .into_iter()
+            .filter_map(|post| self.post_to_item(post, params))
+            .collect();
+
+        // a full page the filters emptied isn't the end of the listing
+        if items.is_empty() && !has_more {
+            return Err(ScrapeError::Empty);
+        }
+        Ok(SearchPage { items, has_more })
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for Booru {
+    fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    fn display_name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: true,
+            videos: true,
+            nsfw: true,
+            pagination: true,
+            resolve: false,
+        }
+    }
+
+    fn filters_purity(&self) -> bool {
+        true
+    }
+
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        vec![BrowseMode::Latest, BrowseMode::Toplist, BrowseMode::Random]
+    }
+
+    /// an empty tag search is the newest posts, the others add an ordering metatag
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        _range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        if !self.browse_modes().contains(&mode) {
+            return Err(unsupported_browse(self.id(), mode));
+        }
+        let mut params = params.clone();
+        params.query = order_tag(self.config.kind, mode)
+            .unwrap_or_default()
+            .to_string();
+        self.search(&params).await
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        let mut tags = params.query.trim().to_string();
+        // booru tags use underscores, so an excluded phrase still maps to one tag
+        for word in &params.exclude {
+            tags = format!("{} -{}", tags, word.replace(' ', "_"))
+                .trim()
+                .to_string();
+        }
+        if !purity_allows(&params.purity, 1) && !purity_allows(&params.purity, 2) {
+            tags = format!("{} {}", tags, sfw_tag(self.config.kind))
+                .trim()
+                .to_string();
+        }
+
+        // the api pages by `limit`, every post of a page is looked at so the next page
+        // starts right after it. 100 is the most gelbooru and moebooru hand out
+        let page_size = params.limit.clamp(1, 100);
+        let url = self.search_url(&tags, params.page, page_size);
+        let body = http::get_text(http::get(&url)).await?;
+        self.parse_page(&body, params, page_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn booru(kind: BooruKind) -> Booru {
+        Booru::new(BooruConfig {
+            id: "test-booru".to_string(),
+            name: "Test booru".to_string(),
+            kind,
+            base_url: "https://booru.example.com".to_string(),
+            user: None,
+            api_key: None,
+            min_width: 1920,
+            min_height: 1080,
+            enabled: true,
+        })
+    }
+
+    fn params(purity: &str) -> SearchParams {
+        SearchParams {
+            page: 1,
+            limit: 3,
+            purity: purity.to_string(),
+            include_videos: true,
+            ..Default::default()
+        }
+    }
+
+    fn ids(page: &SearchPage) -> Vec<&str> {
+        page.items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    const DANBOORU: &str = r#"[
+  {"id": 1, "rating": "g", "file_url": "https://cdn.example.com/1.jpg", "file_ext": "jpg",
+   "image_width": 3840, "image_height": 2160, "tag_string": "sky cloud blue_sky",
+   "large_file_url": "https://cdn.example.com/sample/1.jpg"},
+  {"id": 2, "rating": "q", "file_url": "https://cdn.example.com/2.png", "file_ext": "png",
+   "image_width": 2560, "image_height": 1440, "tag_string": "city"},
+  {"id": 3, "rating": "g", "file_ext": "jpg", "image_width": 3840, "image_height": 2160}
+]"#;
+
+    #[test]
+    fn danbooru_posts() {
+        let booru = booru(BooruKind::Danbooru);
+        let page = booru.parse_page(DANBOORU, &params("110"), 3).unwrap();
+        // the post without a file_url is one anonymous users can't see
+        assert_eq!(ids(&page), ["test-booru-1", "test-booru-2"]);
+        assert!(page.has_more);
+
+        let item = &page.items[0];
+        assert_eq!(item.title.as_deref(), Some("sky, cloud, blue sky"));
+        assert_eq!(item.image_url, "https://cdn.example.com/1.jpg");
+        assert_eq!(
+            item.thumbnail_url.as_deref(),
+            Some("https://cdn.example.com/sample/1.jpg")
+        );
+        assert_eq!((item.width, item.height), (Some(3840), Some(2160)));
+        assert_eq!(
+            item.detail_url.as_deref(),
+            Some("https://booru.example.com/posts/1")
+        );
+        assert_eq!(item.media_type.as_deref(), Some("image"));
+    }
+
+    #[test]
+    fn gelbooru_posts() {
+        let gelbooru = booru(BooruKind::Gelbooru);
+        let body = r#"{"@attributes": {"limit": 3, "offset": 0, "count": 2}, "post": [
+  {"id": 10, "rating": "general", "file_url": "https://img.example.com/10.webm",
+   "width": "1920", "height": "1080", "tags": "rain night", "preview_url": "/thumbs/10.jpg"},
+  {"id": 11, "rating": "explicit", "file_url": "https://img.example.com/11.jpg",
+   "width": 1920, "height": 1080, "tags": "x"}
+]}"#;
+        let page = gelbooru.parse_page(body, &params("100"), 3).unwrap();
+        assert_eq!(ids(&page), ["test-booru-10"]);
+        assert!(!page.has_more);
+        let item = &page.items[0];
+        assert_eq!(item.media_type.as_deref(), Some("video"));
+        assert_eq!((item.width, item.height), (Some(1920), Some(1080)));
+        assert_eq!(
+            item.thumbnail_url.as_deref(),
+            Some("https://booru.example.com/thumbs/10.jpg")
+        );
+        assert_eq!(
+            item.detail_url.as_deref(),
+            Some("https://booru.example.com/index.php?page=post&s=view&id=10")
+        );
+
+        let mut no_videos = params("100");
+        no_videos.include_videos = false;
+        assert!(matches!(
+            gelbooru.parse_page(body, &no_videos, 3),
+            Err(ScrapeError::Empty)
+        ));
+
+        // a single result comes as an object, no results drop the key
+        let single = r#"{"post": {"id": 12, "rating": "general", "file_url": "https://img.example.com/12.png",
+            "width": 2560, "height": 1440}}"#;
+        let page = gelbooru.parse_page(single, &params("100"), 3).unwrap();
+        assert_eq!(ids(&page), ["test-booru-12"]);
+        assert!(matches!(
+            gelbooru.parse_page(r#"{"@attributes": {"count": 0}}"#, &params("100"), 3),
+            Err(ScrapeError::Empty)
+        ));
+
+        // other kinds don't get that leniency
+        let danbooru = booru(BooruKind::Danbooru);
+        assert!(matches!(
+            danbooru.parse_page(r#"{"success": false}"#, &params("100"), 3),
+            Err(ScrapeError::ParseChanged(_))
+        ));
+    }
+
+    #[test]
+    fn moebooru_posts() {
+        let booru = booru(BooruKind::Moebooru);
+        let body = r#"[
+  {"id": 20, "rating": "s", "file_url": "https://files.example.com/20.jpg", "width": 1920,
+   "height": 1080, "tags": "landscape", "sample_url": "https://files.example.com/s/20.jpg"},
+  {"id": 21, "rating": "s", "file_url": "https://files.example.com/21.jpg", "width": 1280,
+   "height": 720, "tags": "small"},
+  {"id": 22, "rating": "q", "file_url": "https://files.example.com/22.gif", "width": 1920,
+   "height": 1080}
+]"#;
+        let page = booru.parse_page(body, &params("111"), 3).unwrap();
+        // too small, and gifs aren't wallpapers
+        assert_eq!(ids(&page), ["test-booru-20"]);
+        assert!(page.has_more);
+        assert_eq!(
+            page.items[0].detail_url.as_deref(),
+            Some("https://booru.example.com/post/show/20")
+        );
+    }
+
+    #[test]
+    fn ratings_map_onto_purity() {
+        let questionable = [
+            (BooruKind::Danbooru, "q"),
+            (BooruKind::Gelbooru, "questionable"),
+            (BooruKind::Moebooru, "q"),
+        ];
+        for (kind, rating) in questionable {
+            assert_eq!(purity_class(kind, rating), 1, "{:?} {}", kind, rating);
+        }
+        assert_eq!(purity_class(BooruKind::Danbooru, "g"), 0);
+        assert_eq!(purity_class(BooruKind::Danbooru, "s"), 1);
+        assert_eq!(purity_class(BooruKind::Gelbooru, "general"), 0);
+        assert_eq!(purity_class(BooruKind::Gelbooru, "sensitive"), 1);
+        assert_eq!(purity_class(BooruKind::Moebooru, "s"), 0);
+        for (kind, rating) in [
+            (BooruKind::Danbooru, "e"),
+            (BooruKind::Gelbooru, "explicit"),
+            (BooruKind::Moebooru, "e"),
+            (BooruKind::Moebooru, "whatever"),
+        ] {
+            assert_eq!(purity_class(kind, rating), 2, "{:?} {}", kind, rating);
+        }
+
+        assert!(purity_allows("110", 1));
+        assert!(!purity_allows("110", 2));
+        assert!(!purity_allows("", 0));
+    }
+
+    #[test]
+    fn filtered_out_page_keeps_paging() {
+        let booru = booru(BooruKind::Danbooru);
+        let body = r#"[
+  {"id": 1, "rating": "e", "file_url": "https://cdn.example.com/1.jpg", "image_width": 3840, "image_height": 2160},
+  {"id": 2, "rating": "q", "file_url": "https://cdn.example.com/2.jpg", "image_width": 3840, "image_height": 2160}
+]"#;
+        // full page, nothing allowed: more may follow
+        let page = booru.parse_page(body, &params("100"), 2).unwrap();
+        assert!(page.items.is_empty());
+        assert!(page.has_more);
+        // short page, nothing allowed: that's the end
+        assert!(matches!(
+            booru.parse_page(body, &params("100"), 3),
+            Err(ScrapeError::Empty)
+        ));
+        // every post on a page is kept, none are skipped for the next one
+        let page = booru.parse_page(body, &params("111"), 2).unwrap();
+        assert_eq!(ids(&page), ["test-booru-1", "test-booru-2"]);
+    }
+}
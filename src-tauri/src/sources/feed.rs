@@ -0,0 +1,671 @@
+/// rss/atom/json feeds as sources - subreddits, blogs, flickr groups, photo of the day sites.
+/// every feed in `AppSettings.feeds` becomes its own source. search filters entries by
+/// keyword, an empty query lists the whole feed
+use super::{unsupported_browse, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::http;
+use crate::models::{BrowseMode, FeedConfig, SearchPage, SourceCapabilities, WallpaperItem};
+use async_trait::async_trait;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp"];
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "webm"];
+
+/// one enclosure / media:content / attachment
+#[derive(Debug, Clone, Default)]
+struct FeedMedia {
+    url: String,
+    mime: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+/// format independent entry, filled by the xml and json parsers
+#[derive(Debug, Clone, Default)]
+struct FeedEntry {
+    guid: Option<String>,
+    title: Option<String>,
+    link: Option<String>,
+    date: Option<String>,
+    tags: Vec<String>,
+    media: Vec<FeedMedia>,
+    thumbnail: Option<String>,
+    /// description / content / content:encoded, searched for <img> and image links
+    html: String,
+}
+
+fn extension(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// "image" / "video" from the mime type, falling back to the url extension
+fn media_kind(url: &str, mime: Option<&str>) -> Option<&'static str> {
+    match mime.map(|mime| mime.to_lowercase()) {
+        Some(mime) if mime.starts_with("image/") => return Some("image"),
+        Some(mime) if mime.starts_with("video/") => return Some("video"),
+        _ => {}
+    }
+    let ext = extension(url);
+    if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        Some("image")
+    } else if VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+        Some("video")
+    } else {
+        None
+    }
+}
+
+fn attr(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.as_ref() == name)
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// attribute-only elements inside an entry (enclosure, media:*, atom link/category)
+fn apply_element(entry: &mut FeedEntry, element: &BytesStart) {
+    let parse_u32 = |name: &[u8]| attr(element, name).and_then(|value| value.parse().ok());
+
+    match element.name().as_ref() {
+        b"enclosure" | b"media:content" => {
+            if let Some(url) = attr(element, b"url") {
+                let mime = attr(element, b"type").or_else(|| {
+                    // media:content medium="image" without a type
+                    attr(element, b"medium").map(|medium| format!("{}/", medium))
+                });
+                entry.media.push(FeedMedia {
+                    url,
+                    mime,
+                    width: parse_u32(b"width"),
+                    height: parse_u32(b"height"),
+                });
+            }
+        }
+        b"media:thumbnail" if entry.thumbnail.is_none() => {
+            entry.thumbnail = attr(element, b"url");
+        }
+        // atom
+        b"link" => {
+            let Some(href) = attr(element, b"href") else {
+                return;
+            };
+            match attr(element, b"rel").as_deref() {
+                Some("enclosure") => entry.media.push(FeedMedia {
+                    url: href,
+                    mime: attr(element, b"type"),
+                    ..Default::default()
+                }),
+                None | Some("alternate") if entry.link.is_none() => entry.link = Some(href),
+                _ => {}
+            }
+        }
+        b"category" => {
+            if let Some(term) = attr(element, b"term") {
+                entry.tags.push(term);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// text content of a direct child of the entry
+fn apply_text(entry: &mut FeedEntry, field: &[u8], text: String) {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return;
+    }
+
+    match field {
+        b"title" => entry.title = Some(text),
+        b"link" if entry.link.is_none() => entry.link = Some(text),
+        b"guid" | b"id" => entry.guid = Some(text),
+        b"pubDate" | b"published" | b"dc:date" => entry.date = Some(text),
+        b"updated" if entry.date.is_none() => entry.date = Some(text),
+        b"category" | b"media:keywords" => entry.tags.extend(
+            text.split(',')
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty()),
+        ),
+        b"description" | b"summary" | b"content" | b"content:encoded" | b"media:description" => {
+            entry.html.push_str(&text);
+            entry.html.push('\n');
+        }
+        _ => {}
+    }
+}
+
+/// rss 2.0 <item> and atom <entry>, namespaced children keep their prefix (media:content)
+fn parse_xml_feed(body: &str) -> Result<Vec<FeedEntry>, ScrapeError> {
+    let mut reader = Reader::from_str(body);
+    let mut entries = Vec::new();
+    let mut entry: Option<FeedEntry> = None;
+    // depth inside the current entry, 1 = direct child
+    let mut depth = 0usize;
+    let mut field: Option<Vec<u8>> = None;
+    let mut text = String::new();
+    let mut saw_feed = false;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| ScrapeError::ParseChanged(format!("invalid feed xml: {}", e)))?;
+
+        match event {
+            Event::Start(element) => {
+                let name = element.name().as_ref().to_vec();
+                if matches!(name.as_slice(), b"rss" | b"feed" | b"rdf:RDF" | b"channel") {
+                    saw_feed = true;
+                }
+
+                match entry.as_mut() {
+                    None if name == b"item" || name == b"entry" => {
+                        entry = Some(FeedEntry::default());
+                        depth = 0;
+                    }
+                    Some(current) => {
+                        depth += 1;
+                        apply_element(current, &element);
+                        if depth == 1 {
+                            field = Some(name);
+                            text.clear();
+                        }
+                    }
+                    None => {}
+                }
+            }
+            Event::Empty(element) => {
+                if let Some(current) = entry.as_mut() {
+                    apply_element(current, &element);
+                }
+            }
+            Event::Text(content) if field.is_some() => {
+                text.push_str(&content.unescape().unwrap_or_default());
+            }
+            Event::CData(content) if field.is_some() => {
+                text.push_str(&String::from_utf8_lossy(&content));
+            }
+            Event::End(element) => match entry.as_mut() {
+                Some(_) if depth == 0 && matches!(element.name().as_ref(), b"item" | b"entry") => {
+                    entries.extend(entry.take());
+                }
+                Some(_) if depth == 0 => {}
+                Some(current) => {
+                    if depth == 1 {
+                        if let Some(name) = field.take() {
+                            apply_text(current, &name, std::mem::take(&mut text));
+                        }
+                    }
+                    depth -= 1;
+                }
+                None => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if entries.is_empty() && !saw_feed {
+        return Err(ScrapeError::ParseChanged(
+            "not an rss or atom feed".to_string(),
+        ));
+    }
+    Ok(entries)
+}
+
+/// https://www.jsonfeed.org/version/1.1/
+fn parse_json_feed(body: &str) -> Result<Vec<FeedEntry>, ScrapeError> {
+    let feed: Value = serde_json::from_str(body)
+        .map_err(|e| ScrapeError::ParseChanged(format!("invalid json feed: {}", e)))?;
+    let items = feed
+        .get("items")
+        .and_then(|items| items.as_array())
+        .ok_or_else(|| ScrapeError::ParseChanged("json feed has no items".to_string()))?;
+
+    let text = |item: &Value, key: &str| {
+        item.get(key)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            let mut media: Vec<FeedMedia> = item
+                .get("attachments")
+                .and_then(|attachments| attachments.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|attachment| {
+                    Some(FeedMedia {
+                        url: text(attachment, "url")?,
+                        mime: text(attachment, "mime_type"),
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            for key in ["image", "banner_image"] {
+                if let Some(url) = text(item, key) {
+                    media.push(FeedMedia {
+                        url,
+                        ..Default::default()
+                    });
+                }
+            }
+
+            FeedEntry {
+                guid: text(item, "id"),
+                title: text(item, "title"),
+                link: text(item, "url"),
+                date: text(item, "date_published").or_else(|| text(item, "date_modified")),
+                tags: item
+                    .get("tags")
+                    .and_then(|tags| tags.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|tag| tag.as_str().map(|tag| tag.to_string()))
+                    .collect(),
+                media,
+                thumbnail: None,
+                html: text(item, "content_html")
+                    .or_else(|| text(item, "summary"))
+                    .unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+/// (links to image files, <img> sources) found in the entry html
+fn html_images(html: &str) -> (Vec<String>, Vec<String>) {
+    if html.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let fragment = Html::parse_fragment(html);
+    let anchor_selector = Selector::parse("a[href]").unwrap();
+    let img_selector = Selector::parse("img[src]").unwrap();
+
+    let links = fragment
+        .select(&anchor_selector)
+        .filter_map(|anchor| anchor.value().attr("href"))
+        .filter(|href| media_kind(href, None) == Some("image"))
+        .map(|href| href.to_string())
+        .collect();
+    let images = fragment
+        .select(&img_selector)
+        .filter_map(|img| img.value().attr("src"))
+        .map(|src| src.to_string())
+        .collect();
+    (links, images)
+}
+
+pub struct Feed {
+    config: FeedConfig,
+}
+
+impl Feed {
+    pub fn new(config: FeedConfig) -> Self {
+        Self { config }
+    }
+
+    fn entry_to_item(&self, entry: FeedEntry, include_videos: bool) -> Option<WallpaperItem> {
+        let (links, images) = html_images(&entry.html);
+
+        // enclosures first, then linked image files (reddit), then inline images
+        let media = entry
+            .media
+            .iter()
+            .filter_map(|media| {
+                media_kind(&media.url, media.mime.as_deref()).map(|kind| (kind, media.clone()))
+            })
+            .chain(links.iter().chain(images.iter()).map(|url| {
+                (
+                    "image",
+                    FeedMedia {
+                        url: url.clone(),
+                        ..Default::default()
+                    },
+                )
+            }))
+            .find(|(kind, _)| *kind == "image" || include_videos)?;
+        let (media_type, media) = media;
+
+        let thumbnail = entry
+            .thumbnail
+            .clone()
+            .or_else(|| images.first().cloned())
+            .unwrap_or_else(|| media.url.clone());
+        let key = entry
+            .guid
+            .clone()
+            .or_else(|| entry.link.clone())
+            .unwrap_or_else(|| media.url.clone());
+
+        Some(WallpaperItem {
+            id: format!("{}-{:x}", self.config.id, md5::compute(key.as_bytes())),
+            source: self.config.id.clone(),
+            title: entry
+                .title
+                .clone()
+                .or_else(|| Some(self.config.name.clone())),
+            image_url: media.url.clone(),
+            thumbnail_url: Some(thumbnail),
+            media_type: Some(media_type.to_string()),
+            width: media.width,
+            height: media.height,
+            tags: if entry.tags.is_empty() {
+                None
+            } else {
+                Some(entry.tags.clone())
+            },
+            detail_url: entry.link.clone(),
+            original: Some(json!({
+                "feed": self.config.url,
+                "date": entry.date,
+                "link": entry.link,
+            })),
+        })
+    }
+}
+
+/// every query word has to show up in the title or tags
+fn matches_query(item: &WallpaperItem, query: &str) -> bool {
+    let haystack = format!(
+        "{} {}",
+        item.title.as_deref().unwrap_or(""),
+        item.tags.as_deref().unwrap_or(&[]).join(" ")
+    )
+    .to_lowercase();
+
+    query
+        .to_lowercase()
+        .split_whitespace()
+        .all(|word| haystack.contains(word))
+}
+
+#[async_trait]
+impl WallpaperSource for Feed {
+    fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    fn display_name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: true,
+            videos: true,
+            nsfw: false,
+            pagination: false,
+            resolve: false,
+        }
+    }
+
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        vec![BrowseMode::Latest]
+    }
+
+    /// the feed in its own order, newest entries first
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        _range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        if mode != BrowseMode::Latest {
+            return Err(unsupported_browse(self.id(), mode));
+        }
+        let mut params = params.clone();
+        params.query.clear();
+        self.search(&params).await
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        // a feed is a single page
+        if params.page > 1 {
+            return Err(ScrapeError::Empty);
+        }
+
+        println!(
+            "[SOURCES:FEED] {} - query: '{}', url: {}",
+            self.config.id, params.query, self.config.url
+        );
+        let body = http::get_text(http::get(&self.config.url)).await?;
+        let entries = if body.trim_start().starts_with('{') {
+            parse_json_feed(&body)?
+        } else {
+            parse_xml_feed(&body)?
+        };
+
+        let total = entries.len();
+        let items: Vec<WallpaperItem> = entries
+            .into_iter()
+            .filter_map(|entry| self.entry_to_item(entry, params.include_videos))
+            .filter(|item| matches_query(item, &params.query))
+            .take(params.limit)
+            .collect();
+
+        println!(
+            "[SOURCES:FEED] {} - {} of {} entries matched",
+            self.config.id,
+            items.len(),
+            total
+        );
+        if items.is_empty() {
+            return Err(ScrapeError::Empty);
+        }
+        Ok(SearchPage {
+            items,
+            has_more: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:media="http://search.yahoo.com/mrss/" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+  <channel>
+    <title>r/wallpapers</title>
+    <item>
+      <title>Misty forest [3840x2160]</title>
+      <link>https://www.reddit.com/r/wallpapers/comments/abc/misty_forest/</link>
+      <guid isPermaLink="false">t3_abc</guid>
+      <pubDate>Mon, 06 May 2024 10:00:00 +0000</pubDate>
+      <category>nature</category>
+      <media:thumbnail url="https://b.thumbs.redditmedia.com/forest.jpg" />
+      <content:encoded><![CDATA[<a href="https://i.redd.it/forest.png">[link]</a> <a href="https://www.reddit.com/user/someone">someone</a>]]></content:encoded>
+    </item>
+    <item>
+      <title>Rain &amp; neon</title>
+      <link>https://example.com/rain</link>
+      <enclosure url="https://example.com/rain.mp4" type="video/mp4" length="1000" />
+      <media:content url="https://example.com/rain.jpg" medium="image" width="2560" height="1440" />
+    </item>
+    <item>
+      <title>No picture here</title>
+      <description>just text</description>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>photo of the day</title>
+  <entry>
+    <title>Dunes</title>
+    <id>tag:example.com,2024:dunes</id>
+    <link href="https://example.com/dunes" />
+    <link rel="enclosure" type="image/jpeg" href="https://example.com/dunes.jpg" />
+    <category term="desert" />
+    <updated>2024-05-06T10:00:00Z</updated>
+    <published>2024-05-05T10:00:00Z</published>
+  </entry>
+  <entry>
+    <title>Harbour</title>
+    <id>tag:example.com,2024:harbour</id>
+    <link rel="alternate" href="https://example.com/harbour" />
+    <content type="html">&lt;p&gt;&lt;img src="https://example.com/harbour.webp"&gt;&lt;/p&gt;</content>
+  </entry>
+</feed>"#;
+
+    const JSON_FEED: &str = r#"{
+  "version": "https://jsonfeed.org/version/1.1",
+  "title": "walls",
+  "items": [
+    {
+      "id": "1",
+      "title": "Aurora",
+      "url": "https://example.com/aurora",
+      "tags": ["sky", "night"],
+      "date_published": "2024-05-06T10:00:00Z",
+      "attachments": [{"url": "https://example.com/aurora.webm", "mime_type": "video/webm"}],
+      "image": "https://example.com/aurora.jpg"
+    },
+    {
+      "id": "2",
+      "content_html": "<p>no media</p>"
+    }
+  ]
+}"#;
+
+    fn feed() -> Feed {
+        Feed::new(FeedConfig {
+            id: "feed-test".to_string(),
+            name: "Test feed".to_string(),
+            url: "https://example.com/feed".to_string(),
+            enabled: true,
+        })
+    }
+
+    fn items(entries: Vec<FeedEntry>, include_videos: bool) -> Vec<WallpaperItem> {
+        let feed = feed();
+        entries
+            .into_iter()
+            .filter_map(|entry| feed.entry_to_item(entry, include_videos))
+            .collect()
+    }
+
+    #[test]
+    fn rss() {
+        let entries = parse_xml_feed(RSS).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].guid.as_deref(), Some("t3_abc"));
+        assert_eq!(
+            entries[0].date.as_deref(),
+            Some("Mon, 06 May 2024 10:00:00 +0000")
+        );
+        assert_eq!(entries[1].title.as_deref(), Some("Rain & neon"));
+
+        // the entry without any image is dropped
+        let items = items(entries, true);
+        assert_eq!(items.len(), 2);
+
+        // reddit: the linked image file, not the user link, thumbnail from media:thumbnail
+        let forest = &items[0];
+        assert_eq!(forest.image_url, "https://i.redd.it/forest.png");
+        assert_eq!(
+            forest.thumbnail_url.as_deref(),
+            Some("https://b.thumbs.redditmedia.com/forest.jpg")
+        );
+        assert_eq!(forest.tags.as_deref(), Some(&["nature".to_string()][..]));
+        assert_eq!(forest.id, format!("feed-test-{:x}", md5::compute("t3_abc")));
+
+        // the enclosure comes first
+        let rain = &items[1];
+        assert_eq!(rain.image_url, "https://example.com/rain.mp4");
+        assert_eq!(rain.media_type.as_deref(), Some("video"));
+        assert_eq!(rain.detail_url.as_deref(), Some("https://example.com/rain"));
+    }
+
+    #[test]
+    fn rss_without_videos() {
+        let items = items(parse_xml_feed(RSS).unwrap(), false);
+        let rain = &items[1];
+        assert_eq!(rain.image_url, "https://example.com/rain.jpg");
+        assert_eq!(rain.media_type.as_deref(), Some("image"));
+        assert_eq!((rain.width, rain.height), (Some(2560), Some(1440)));
+    }
+
+    #[test]
+    fn atom() {
+        let entries = parse_xml_feed(ATOM).unwrap();
+        // published wins over updated
+        assert_eq!(entries[0].date.as_deref(), Some("2024-05-05T10:00:00Z"));
+
+        let items = items(entries, true);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].image_url, "https://example.com/dunes.jpg");
+        assert_eq!(
+            items[0].detail_url.as_deref(),
+            Some("https://example.com/dunes")
+        );
+        assert_eq!(items[0].tags.as_deref(), Some(&["desert".to_string()][..]));
+
+        // inline <img> in the escaped html content
+        assert_eq!(items[1].image_url, "https://example.com/harbour.webp");
+        assert_eq!(
+            items[1].thumbnail_url.as_deref(),
+            Some("https://example.com/harbour.webp")
+        );
+        assert_eq!(
+            items[1].detail_url.as_deref(),
+            Some("https://example.com/harbour")
+        );
+    }
+
+    #[test]
+    fn json() {
+        let entries = parse_json_feed(JSON_FEED).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let videos = items(entries.clone(), true);
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].image_url, "https://example.com/aurora.webm");
+        assert_eq!(videos[0].media_type.as_deref(), Some("video"));
+        assert_eq!(
+            videos[0].tags.as_deref(),
+            Some(&["sky".to_string(), "night".to_string()][..])
+        );
+
+        let images = items(entries, false);
+        assert_eq!(images[0].image_url, "https://example.com/aurora.jpg");
+    }
+
+    #[test]
+    fn not_a_feed() {
+        assert!(matches!(
+            parse_xml_feed("<html><body>moved</body></html>"),
+            Err(ScrapeError::ParseChanged(_))
+        ));
+        assert!(matches!(
+            parse_json_feed(r#"{"version": "1.1"}"#),
+            Err(ScrapeError::ParseChanged(_))
+        ));
+        // an empty feed is fine, just nothing in it
+        assert!(parse_xml_feed("<rss><channel></channel></rss>")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn query_matches_title_and_tags() {
+        let items = items(parse_json_feed(JSON_FEED).unwrap(), true);
+        assert!(matches_query(&items[0], "Aurora night"));
+        assert!(matches_query(&items[0], ""));
+        assert!(!matches_query(&items[0], "aurora day"));
+    }
+}
@@ -0,0 +1,337 @@
+/// local folders (nas mounts, archive drives) as a search source. the configured
+/// directories are walked recursively into an in-memory index that's rebuilt when
+/// stale, queries match file names, folder names and tags stored in `local_tags.json`
+use super::{unsupported_browse, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::models::{BrowseMode, SearchPage, SourceCapabilities, WallpaperItem};
+use crate::storage::{get_app_data_dir, load_settings, media_type_for_extension};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// rebuild the index on the next search after this long, or right away through `reindex`
+const INDEX_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone)]
+struct LocalFile {
+    path: PathBuf,
+    /// file stem with separators turned into spaces
+    name: String,
+    /// folders between the configured root and the file, root name included
+    folders: Vec<String>,
+    media_type: &'static str,
+    modified: i64,
+}
+
+struct LocalIndex {
+    roots: Vec<String>,
+    built_at: Instant,
+    files: Vec<LocalFile>,
+}
+
+lazy_static::lazy_static! {
+    static ref INDEX: RwLock<Option<LocalIndex>> = RwLock::new(None);
+    /// path -> user tags, loaded lazily from AppData
+    static ref TAGS: RwLock<Option<HashMap<String, Vec<String>>>> = RwLock::new(None);
+}
+
+fn get_tags_file() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("local_tags.json"))
+}
+
+fn all_tags() -> HashMap<String, Vec<String>> {
+    if let Some(tags) = TAGS.read().unwrap().as_ref() {
+        return tags.clone();
+    }
+
+    let tags: HashMap<String, Vec<String>> = get_tags_file()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    *TAGS.write().unwrap() = Some(tags.clone());
+    tags
+}
+
+/// replace the stored tags of one file, empty `tags` removes the entry
+pub fn set_tags(path: &str, tags: Vec<String>) -> Result<(), String> {
+    let mut all = all_tags();
+    let tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    if tags.is_empty() {
+        all.remove(path);
+    } else {
+        all.insert(path.to_string(), tags);
+    }
+
+    let json = serde_json::to_string_pretty(&all).map_err(|e| e.to_string())?;
+    std::fs::write(get_tags_file()?, json).map_err(|e| format!("failed to save tags: {}", e))?;
+    *TAGS.write().unwrap() = Some(all);
+    Ok(())
+}
+
+fn readable_name(name: &str) -> String {
+    name.replace(['_', '-', '.'], " ")
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<LocalFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        println!("[SOURCES:LOCAL] can't read {}", dir.display());
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        // file_type doesn't follow symlinks, so linked folders can't loop us forever
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if !hidden {
+                walk(root, &path, files);
+            }
+            continue;
+        }
+
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        let Some(media_type) = media_type_for_extension(extension) else {
+            continue;
+        };
+        let modified = entry
+            .metadata()
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+        let folders = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root.parent().unwrap_or(root)).ok())
+            .map(|relative| {
+                relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        files.push(LocalFile {
+            name: readable_name(
+                &path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            ),
+            path,
+            folders,
+            media_type,
+            modified,
+        });
+    }
+}
+
+fn build_index(roots: Vec<String>) -> LocalIndex {
+    let started = Instant::now();
+    let mut files = Vec::new();
+    for root in &roots {
+        let root = Path::new(root);
+        if root.is_dir() {
+            walk(root, root, &mut files);
+        } else {
+            println!(
+                "[SOURCES:LOCAL] {} is not a directory, skipping",
+                root.display()
+            );
+        }
+    }
+    // newest first, like the user wallpapers list
+    files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+
+    println!(
+        "[SOURCES:LOCAL] indexed {} files from {} folders in {}ms",
+        files.len(),
+        roots.len(),
+        started.elapsed().as_millis()
+    );
+    LocalIndex {
+        roots,
+        built_at: Instant::now(),
+        files,
+    }
+}
+
+/// rebuild the index now, returns the number of indexed files
+pub async fn reindex() -> Result<usize, String> {
+    let roots = load_settings().local_folders;
+    let index = tokio::task::spawn_blocking(move || build_index(roots))
+        .await
+        .map_err(|e| e.to_string())?;
+    let count = index.files.len();
+    *INDEX.write().unwrap() = Some(index);
+    Ok(count)
+}
+
+/// index that matches the current folder list and isn't older than `INDEX_MAX_AGE`
+async fn fresh_files() -> Result<Vec<LocalFile>, String> {
+    let roots = load_settings().local_folders;
+    {
+        let index = INDEX.read().unwrap();
+        if let Some(index) = index.as_ref() {
+            if index.roots == roots && index.built_at.elapsed() < INDEX_MAX_AGE {
+                return Ok(index.files.clone());
+            }
+        }
+    }
+
+    reindex().await?;
+    Ok(INDEX
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|index| index.files.clone())
+        .unwrap_or_default())
+}
+
+/// the webview can only load local files through the asset protocol,
+/// so configured folders get added to its scope (startup + settings save)
+pub fn allow_asset_access(app: &AppHandle) {
+    for folder in load_settings().local_folders {
+        if let Err(e) = app.asset_protocol_scope().allow_directory(&folder, true) {
+            println!("[SOURCES:LOCAL] failed to allow {}: {}", folder, e);
+        }
+    }
+}
+
+/// same url `convertFileSrc` builds on the frontend
+fn asset_url(path: &Path) -> String {
+    let encoded = urlencoding::encode(&path.to_string_lossy()).into_owned();
+    if cfg!(windows) {
+        format!("http://asset.localhost/{}", encoded)
+    } else {
+        format!("asset://localhost/{}", encoded)
+    }
+}
+
+pub struct Local;
+
+#[async_trait]
+impl WallpaperSource for Local {
+    fn id(&self) -> &str {
+        "local"
+    }
+
+    fn display_name(&self) -> &str {
+        "Local Folders"
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: true,
+            videos: true,
+            nsfw: false,
+            pagination: true,
+            resolve: false,
+        }
+    }
+
+    // the index already lives in memory, a disk copy would only go out of date
+    fn cache_results(&self) -> bool {
+        false
+    }
+
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        vec![BrowseMode::Latest]
+    }
+
+    /// every indexed file, newest first
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        _range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        if mode != BrowseMode::Latest {
+            return Err(unsupported_browse(self.id(), mode));
+        }
+        let mut params = params.clone();
+        params.query.clear();
+        self.search(&params).await
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        let files = fresh_files().await.map_err(ScrapeError::Network)?;
+        let tags = all_tags();
+        let words: Vec<String> = params
+            .query
+            .to_lowercase()
+            .split_whitespace()
+            .map(|word| word.to_string())
+            .collect();
+
+        let matching = files.into_iter().filter_map(|file| {
+            if file.media_type == "video" && !params.include_videos {
+                return None;
+            }
+
+            let path = file.path.to_string_lossy().to_string();
+            let mut file_tags: Vec<String> = file
+                .folders
+                .iter()
+                .map(|folder| folder.to_lowercase())
+                .collect();
+            file_tags.extend(tags.get(&path).cloned().unwrap_or_default());
+
+            let haystack = format!("{} {}", file.name, file_tags.join(" ")).to_lowercase();
+            if !words.iter().all(|word| haystack.contains(word.as_str())) {
+                return None;
+            }
+            Some((file, path, file_tags))
+        });
+
+        let page = params.page.max(1) as usize;
+        let matching: Vec<_> = matching.collect();
+        let has_more = matching.len() > page * params.limit;
+        let items: Vec<WallpaperItem> = matching
+            .into_iter()
+            .skip((page - 1) * params.limit)
+            .take(params.limit)
+            .map(|(file, path, file_tags)| WallpaperItem {
+                id: format!("local-{:x}", md5::compute(path.as_bytes())),
+                source: "local".to_string(),
+                title: Some(file.name.clone()),
+                image_url: url::Url::from_file_path(&file.path)
+                    .map(|url| url.to_string())
+                    .unwrap_or_else(|_| format!("file://{}", path)),
+                thumbnail_url: Some(asset_url(&file.path)),
+                media_type: Some(file.media_type.to_string()),
+                width: None,
+                height: None,
+                tags: Some(file_tags),
+                detail_url: None,
+                original: Some(serde_json::json!({
+                    "path": path,
+                    "modified": file.modified,
+                })),
+            })
+            .collect();
+
+        println!(
+            "[SOURCES:LOCAL] query: '{}', page: {} - {} items",
+            params.query,
+            page,
+            items.len()
+        );
+        if items.is_empty() {
+            return Err(ScrapeError::Empty);
+        }
+        Ok(SearchPage { items, has_more })
+    }
+}
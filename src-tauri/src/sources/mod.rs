@@ -0,0 +1,645 @@
+/// pluggable wallpaper sources - every site is one module implementing `WallpaperSource`
+/// or a declarative site definition (see site.rs),
+/// search/live2d/resolve commands only ever talk to the registry below
+use crate::error::ScrapeError;
+use crate::health::HealthProbe;
+use crate::models::{
+    BrowseMode, HiddenItems, MediaVariant, SearchPage, SourceCapabilities, SourceError, SourceInfo,
+    WallhavenOptions, WallpaperDetails, WallpaperItem,
+};
+use crate::palette;
+use crate::probe;
+use crate::query;
+use crate::safety::Policy;
+use crate::search_cache;
+use crate::storage::{get_app_data_dir, load_settings, media_type_for_extension};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+mod booru;
+mod feed;
+pub mod local;
+mod moewalls;
+mod motionbgs;
+pub mod site;
+mod wallhaven;
+mod wallpaperflare;
+
+#[cfg(test)]
+mod replay_tests;
+
+/// normalized search input, each source picks what it understands
+#[derive(Debug, Clone, Default)]
+pub struct SearchParams {
+    /// empty query = source homepage/featured listing where supported
+    pub query: String,
+    pub page: u32,
+    pub limit: usize,
+    /// wallhaven style purity bits ("100" sfw, "110" sketchy, "111" all)
+    pub purity: String,
+    pub ai_art: bool,
+    /// return video items where the source has both (moewalls)
+    pub include_videos: bool,
+    /// only used by wallhaven, other sources ignore it
+    pub wallhaven: WallhavenOptions,
+    /// size/aspect filters, applied to every source's results after probing (see probe.rs)
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// "16:9,21:9", "landscape", "portrait", ...
+    pub aspect: Option<String>,
+    /// `-word`s from the query, sent natively where the site has exclusion (wallhaven,
+    /// boorus) and dropped from everyone's results after (see query.rs)
+    pub exclude: Vec<String>,
+    /// "image" or "video" from `type:`, other media types are dropped from results
+    pub media_type: Option<String>,
+    /// hex color ("ff6600") the results should be dominated by, see palette.rs
+    pub color: Option<String>,
+}
+
+impl SearchParams {
+    pub fn has_size_filters(&self) -> bool {
+        self.min_width.is_some()
+            || self.min_height.is_some()
+            || self.max_width.is_some()
+            || self.max_height.is_some()
+            || self.aspect.is_some()
+    }
+}
+
+/// output of a resolver, `url4k` is only set by sources that offer a separate 4k file
+#[derive(Debug, Clone)]
+pub struct ResolvedMedia {
+    pub url: String,
+    pub url4k: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// all files the source lists, empty when it only has the one
+    pub variants: Vec<MediaVariant>,
+}
+
+#[async_trait]
+pub trait WallpaperSource: Send + Sync {
+    /// stable id, used as `WallpaperItem.source` and in the `sources` search param
+    fn id(&self) -> &str;
+
+    fn display_name(&self) -> &str;
+
+    fn capabilities(&self) -> SourceCapabilities;
+
+    /// one page of results, `has_more` from the site's next page link where it has one
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError>;
+
+    /// resolve a listing item's `detail_url` into the real media url
+    async fn resolve(&self, detail_url: &str) -> Result<ResolvedMedia, ScrapeError> {
+        let _ = detail_url;
+        Err(ScrapeError::ParseChanged(format!(
+            "{} has no resolver",
+            self.id()
+        )))
+    }
+
+    /// native listings `browse` understands, empty = the source can only be searched
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        Vec::new()
+    }
+
+    /// one page of a native listing (newest, toplist, featured, ...), no query involved.
+    /// only called with a mode from `browse_modes`, `range` is the toplist period where
+    /// the site has one and ignored otherwise
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        let _ = (range, params);
+        Err(unsupported_browse(self.id(), mode))
+    }
+
+    /// tags, uploader, size and license from the item's detail page. the default has nothing
+    /// beyond the listing, missing fields get filled from the item by `details::get`
+    async fn details(&self, item: &WallpaperItem) -> Result<WallpaperDetails, ScrapeError> {
+        let _ = item;
+        Ok(WallpaperDetails::default())
+    }
+
+    /// page + selectors the health check verifies, `None` = nothing to check
+    fn health_probe(&self) -> Option<HealthProbe> {
+        None
+    }
+
+    /// whether `params.color` is handled by the site, otherwise results get filtered
+    /// by their thumbnails' palettes
+    fn filters_color(&self) -> bool {
+        false
+    }
+
+    /// whether `params.purity` is handled by the source. nsfw sources that can't are
+    /// left out unless the content policy allows nsfw
+    fn filters_purity(&self) -> bool {
+        false
+    }
+
+    /// whether results go through the on-disk search cache, off for sources that are local anyway
+    fn cache_results(&self) -> bool {
+        true
+    }
+
+    fn info(&self) -> SourceInfo {
+        SourceInfo {
+            id: self.id().to_string(),
+            name: self.display_name().to_string(),
+            capabilities: self.capabilities(),
+            browse_modes: self.browse_modes(),
+        }
+    }
+}
+
+/// for `browse` implementations handed a mode they don't list
+pub fn unsupported_browse(source: &str, mode: BrowseMode) -> ScrapeError {
+    ScrapeError::ParseChanged(format!("{} has no {} listing", source, mode.as_str()))
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<Vec<Arc<dyn WallpaperSource>>> = RwLock::new(builtin_sources());
+}
+
+/// built-in declarative site, shipped in the same format users write
+fn builtin_site(file: &str, content: &str) -> Arc<dyn WallpaperSource> {
+    site::SiteDefinition::from_toml(content)
+        .and_then(site::SiteSource::new)
+        .map(|site| Arc::new(site) as Arc<dyn WallpaperSource>)
+        .unwrap_or_else(|e| panic!("built-in site {} is invalid: {}", file, e))
+}
+
+fn builtin_sources() -> Vec<Arc<dyn WallpaperSource>> {
+    // order here is the default search order
+    let mut sources: Vec<Arc<dyn WallpaperSource>> = vec![
+        Arc::new(wallhaven::Wallhaven),
+        Arc::new(moewalls::Moewalls),
+        builtin_site("wallpapers.toml", include_str!("sites/wallpapers.toml")),
+        Arc::new(wallpaperflare::WallpaperFlare),
+        Arc::new(motionbgs::MotionBgs),
+    ];
+
+    let settings = load_settings();
+    if !settings.local_folders.is_empty() {
+        sources.push(Arc::new(local::Local));
+    }
+
+    let configured = settings
+        .booru_sources
+        .into_iter()
+        .filter(|config| config.enabled)
+        .map(|config| Arc::new(booru::Booru::new(config)) as Arc<dyn WallpaperSource>)
+        .chain(
+            settings
+                .feeds
+                .into_iter()
+                .filter(|config| config.enabled)
+                .map(|config| Arc::new(feed::Feed::new(config)) as Arc<dyn WallpaperSource>),
+        );
+    for source in configured {
+        if sources.iter().any(|existing| existing.id() == source.id()) {
+            println!("[SOURCES] id {} is already taken, skipping", source.id());
+            continue;
+        }
+        sources.push(source);
+    }
+
+    sources
+}
+
+/// `<app data>/sites`, where users drop their own .toml/.json site definitions
+pub fn get_sites_dir() -> Result<std::path::PathBuf, String> {
+    let dir = get_app_data_dir()?.join("sites");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("failed to create sites directory: {}", e))?;
+    Ok(dir)
+}
+
+fn load_site_file(path: &std::path::Path) -> Result<site::SiteSource, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let def = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => site::SiteDefinition::from_json(&content)?,
+        _ => site::SiteDefinition::from_toml(&content)?,
+    };
+    site::SiteSource::new(def)
+}
+
+/// rebuild the registry from the built-ins, configured boorus/feeds and every site file in the sites dir
+pub fn reload_sites() -> Vec<String> {
+    let mut sources = builtin_sources();
+    let errors = match get_sites_dir() {
+        Ok(dir) => load_sites(&dir, &mut sources),
+        Err(e) => vec![format!("sites: {}", e)],
+    };
+
+    *REGISTRY.write().unwrap() = sources;
+    errors
+}
+
+/// add the site files in `dir` to `sources`. a site with an id already there replaces it
+/// in place, new ids go to the end. broken files are skipped and returned as
+/// "file: error" messages, the rest still load
+fn load_sites(dir: &std::path::Path, sources: &mut Vec<Arc<dyn WallpaperSource>>) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let mut paths: Vec<_> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(e) => {
+            errors.push(format!("sites: {}", e));
+            Vec::new()
+        }
+    };
+    paths.retain(|path| {
+        path.is_file()
+            && matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("toml" | "json")
+            )
+    });
+    paths.sort();
+
+    for path in paths {
+        let file = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        match load_site_file(&path) {
+            Ok(site) => {
+                let site: Arc<dyn WallpaperSource> = Arc::new(site);
+                match sources.iter().position(|source| source.id() == site.id()) {
+                    Some(index) => {
+                        println!("[SOURCES] {} overrides {}", file, site.id());
+                        sources[index] = site;
+                    }
+                    None => {
+                        println!("[SOURCES] loaded site {} from {}", site.id(), file);
+                        sources.push(site);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("[SOURCES] skipping {}: {}", file, e);
+                errors.push(format!("{}: {}", file, e));
+            }
+        }
+    }
+    errors
+}
+
+/// every registered source
+pub fn all() -> Vec<Arc<dyn WallpaperSource>> {
+    REGISTRY.read().unwrap().clone()
+}
+
+pub fn get(id: &str) -> Option<Arc<dyn WallpaperSource>> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .iter()
+        .find(|source| source.id() == id)
+        .cloned()
+}
+
+/// "image" / "video" by the url's file extension, `None` when it doesn't tell
+pub fn url_media_type(url: &str) -> Option<&'static str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let (_, extension) = file.rsplit_once('.')?;
+    media_type_for_extension(extension)
+}
+
+fn item_media_type(item: &WallpaperItem) -> &str {
+    match item.media_type.as_deref() {
+        Some("video") => "video",
+        _ => "image",
+    }
+}
+
+/// url of the file to actually fetch for an item, through its source's resolver when it
+/// has one (4k first). a failed resolve falls back to `fallback`, the url the caller had
+pub async fn full_resolution_url(item: &WallpaperItem, fallback: &str) -> String {
+    let (Some(source), Some(detail_url)) = (get(&item.source), item.detail_url.as_deref()) else {
+        return fallback.to_string();
+    };
+    if !source.capabilities().resolve {
+        return fallback.to_string();
+    }
+
+    let wanted = item_media_type(item);
+    match source.resolve(detail_url).await {
+        // moewalls/motionbgs resolve to the video even for their still previews,
+        // an image item has to stay an image
+        Ok(resolved) if url_media_type(&resolved.url).is_some_and(|kind| kind != wanted) => {
+            println!(
+                "[SOURCES] {}: {} resolved to a different media type, using {}",
+                item.source, detail_url, fallback
+            );
+            fallback.to_string()
+        }
+        Ok(resolved) => resolved.url4k.unwrap_or(resolved.url),
+        Err(e) => {
+            println!(
+                "[SOURCES] {}: resolving {} failed ({}), using {}",
+                item.source, detail_url, e, fallback
+            );
+            fallback.to_string()
+        }
+    }
+}
+
+/// ids searched when the frontend doesn't pass any
+pub fn default_ids() -> Vec<String> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .iter()
+        .map(|source| source.id().to_string())
+        .collect()
+}
+
+/// one source's share of a fan-out search
+#[derive(Debug)]
+pub struct SourceOutcome {
+    pub source: String,
+    pub result: Result<Vec<WallpaperItem>, SourceError>,
+    pub elapsed: Duration,
+    /// set when `result` holds cached items because the live search failed
+    pub stale: Option<StaleFallback>,
+    /// page to ask this source for next, `None` once it has nothing more to give
+    pub next_page: Option<u32>,
+    /// what the content policy took out of `result`
+    pub hidden: HiddenItems,
+}
+
+/// why a source was answered from the cache, and how old that page is
+#[derive(Debug)]
+pub struct StaleFallback {
+    pub age: Duration,
+    pub error: SourceError,
+}
+
+/// content policy and blocklist first, then size/aspect filters, the query language's, and color for
+/// sources that can't filter by it themselves
+async fn filter_items(
+    source: &Arc<dyn WallpaperSource>,
+    items: &mut Vec<WallpaperItem>,
+    params: &SearchParams,
+    policy: &Policy,
+) -> HiddenItems {
+    let hidden = policy.apply(items).await;
+    probe::apply_filters(items, params);
+    query::apply_filters(items, params);
+    if let Some(color) = params.color.as_deref().and_then(palette::parse_hex) {
+        if !source.filters_color() {
+            palette::filter_by_color(items, color).await;
+        }
+    }
+    hidden
+}
+
+/// start every requested source concurrently, outcomes arrive in completion order.
+/// unknown ids are skipped, each source gets its own `timeout` for searching, probing
+/// and filtering together and its page from `pages`
+/// (`params.page` otherwise). fresh cached pages skip the network, failures fall back
+/// to stale ones. dropping the receiver cancels whatever is still running
+pub fn spawn_search(
+    ids: &[String],
+    params: &SearchParams,
+    pages: &HashMap<String, u32>,
+    timeout: Duration,
+) -> mpsc::UnboundedReceiver<SourceOutcome> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    // probing costs a request per item, skip it when nobody asked for sizes
+    let probe_dimensions = load_settings().probe_dimensions || params.has_size_filters();
+    let policy = Policy::current();
+
+    for id in ids {
+        let Some(source) = get(id) else {
+            println!("[SOURCES] Unknown source: {}", id);
+            continue;
+        };
+
+        let tx = tx.clone();
+        let policy = policy.clone();
+        let mut params = params.clone();
+        if let Some(page) = pages.get(id) {
+            params.page = *page;
+        }
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let deadline = tokio::time::Instant::now() + timeout;
+            let source_id = source.id().to_string();
+            let cacheable = source.cache_results();
+            let next_page = |has_more: bool| has_more.then_some(params.page.max(1) + 1);
+
+            // search, probe and filter share the one deadline
+            let work = async {
+                let fresh = cacheable
+                    .then(|| search_cache::fresh(&source_id, &params))
+                    .flatten();
+                let (mut page, from_network) = match fresh {
+                    Some(cached) => {
+                        println!(
+                            "[CACHE] {}: fresh hit, {} items from {}s ago",
+                            source_id,
+                            cached.items.len(),
+                            cached.age.as_secs()
+                        );
+                        let page = SearchPage {
+                            items: cached.items,
+                            has_more: cached.has_more,
+                        };
+                        (page, false)
+                    }
+                    None => (source.search(&params).await?, true),
+                };
+                // sizes of cached pages usually come straight from the probe cache
+                if probe_dimensions {
+                    probe::fill_dimensions(&source, &mut page.items, params.has_size_filters())
+                        .await;
+                }
+                // cached unfiltered, filters aren't part of the cache key
+                if from_network && cacheable {
+                    search_cache::store(&source_id, &params, &page);
+                }
+                let hidden = filter_items(&source, &mut page.items, &params, &policy).await;
+                Ok::<_, ScrapeError>((page, hidden))
+            };
+            let result = tokio::select! {
+                _ = tx.closed() => return,
+                result = tokio::time::timeout_at(deadline, work) => result,
+            };
+
+            let error = match result {
+                Ok(Ok((page, hidden))) => {
+                    // receiver may be gone if the caller stopped listening, nothing to do then
+                    let _ = tx.send(SourceOutcome {
+                        source: source_id,
+                        result: Ok(page.items),
+                        elapsed: started.elapsed(),
+                        stale: None,
+                        next_page: next_page(page.has_more),
+                        hidden,
+                    });
+                    return;
+                }
+                // ran out of pages, that's the end of this source rather than an error
+                Ok(Err(ScrapeError::Empty)) if params.page > 1 => {
+                    println!(
+                        "[SOURCES] {}: no more results after page {}",
+                        source_id,
+                        params.page - 1
+                    );
+                    let _ = tx.send(SourceOutcome {
+                        source: source_id,
+                        result: Ok(Vec::new()),
+                        elapsed: started.elapsed(),
+                        stale: None,
+                        next_page: None,
+                        hidden: HiddenItems::default(),
+                    });
+                    return;
+                }
+                Ok(Err(e)) => SourceError::new(&source_id, &e),
+                Err(_) => {
+                    let mut error = SourceError::new(&source_id, &ScrapeError::Timeout);
+                    error.message = format!("no results after {}ms", timeout.as_millis());
+                    error
+                }
+            };
+
+            // "no results" is a real answer, anything else gets the last cached page if there is one
+            let fallback = (cacheable && error.kind != ScrapeError::Empty.kind())
+                .then(|| search_cache::stale(&source_id, &params))
+                .flatten();
+            let outcome = match fallback {
+                Some(mut page) => {
+                    let hidden = filter_items(&source, &mut page.items, &params, &policy).await;
+                    println!(
+                        "[CACHE] {}: {} - serving {} stale items from {}s ago",
+                        source_id,
+                        error.kind,
+                        page.items.len(),
+                        page.age.as_secs()
+                    );
+                    SourceOutcome {
+                        source: source_id,
+                        result: Ok(page.items),
+                        elapsed: started.elapsed(),
+                        stale: Some(StaleFallback {
+                            age: page.age,
+                            error,
+                        }),
+                        next_page: next_page(page.has_more),
+                        hidden,
+                    }
+                }
+                // worth asking for the same page again next time if the failure was temporary
+                None => SourceOutcome {
+                    next_page: error.retryable.then_some(params.page.max(1)),
+                    source: source_id,
+                    result: Err(error),
+                    elapsed: started.elapsed(),
+                    stale: None,
+                    hidden: HiddenItems::default(),
+                },
+            };
+            let _ = tx.send(outcome);
+        });
+    }
+
+    rx
+}
+
+/// fan out and wait for everything, outcomes come back in the requested source order
+pub async fn search_all(
+    ids: &[String],
+    params: &SearchParams,
+    pages: &HashMap<String, u32>,
+    timeout: Duration,
+) -> Vec<SourceOutcome> {
+    let mut rx = spawn_search(ids, params, pages, timeout);
+    let mut outcomes = Vec::new();
+    while let Some(outcome) = rx.recv().await {
+        outcomes.push(outcome);
+    }
+
+    outcomes.sort_by_key(|outcome| ids.iter().position(|id| *id == outcome.source));
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITE: &str = r#"
+id = "example"
+name = "Example"
+base_url = "https://example.com"
+item = "li"
+search = { url = "{base}/?q={query}" }
+fields = { id = { attrs = ["data-id"] } }
+"#;
+
+    #[test]
+    fn broken_site_files_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("colorwall-sites-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = [
+            ("a-broken.toml", SITE.replace("[", "")),
+            ("b-bad-selector.toml", SITE.replace("\"li\"", "\"li[\"")),
+            ("c-example.toml", SITE.to_string()),
+            (
+                "d-override.json",
+                r#"{"id": "wallhaven", "name": "My wallhaven", "base_url": "https://wallhaven.cc",
+                    "item": "figure", "search": {"url": "{base}/search?q={query}"},
+                    "fields": {"id": {"attrs": ["data-wallpaper-id"]}}}"#
+                    .to_string(),
+            ),
+            ("e-empty.json", String::new()),
+            ("notes.txt", "not a site".to_string()),
+        ];
+        for (file, content) in &files {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+
+        let mut sources: Vec<Arc<dyn WallpaperSource>> = vec![
+            Arc::new(wallhaven::Wallhaven),
+            Arc::new(motionbgs::MotionBgs),
+        ];
+        let errors = load_sites(&dir, &mut sources);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let files: Vec<&str> = errors
+            .iter()
+            .map(|e| e.split_once(": ").unwrap().0)
+            .collect();
+        assert_eq!(
+            files,
+            ["a-broken.toml", "b-bad-selector.toml", "e-empty.json"]
+        );
+        let ids: Vec<&str> = sources.iter().map(|source| source.id()).collect();
+        assert_eq!(ids, ["wallhaven", "motionbgs", "example"]);
+        assert_eq!(sources[0].display_name(), "My wallhaven");
+    }
+
+    #[test]
+    fn missing_sites_dir_keeps_the_sources() {
+        let mut sources: Vec<Arc<dyn WallpaperSource>> = vec![Arc::new(wallhaven::Wallhaven)];
+        let errors = load_sites(
+            std::path::Path::new("/nonexistent/colorwall-sites"),
+            &mut sources,
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(sources.len(), 1);
+    }
+}
@@ -0,0 +1,109 @@
+/// moewalls.com - live2d/anime live wallpapers, can list the homepage without a query.
+/// listings only link the short preview clip, the resolver follows the post to the real file
+use super::{unsupported_browse, ResolvedMedia, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::health::{DetailProbe, HealthProbe, SelectorCheck};
+use crate::http;
+use crate::models::{BrowseMode, SearchPage, SourceCapabilities, WallpaperDetails, WallpaperItem};
+use crate::scraper::{
+    resolve_moewalls_download, scrape_detail_page, scrape_moewalls, MOEWALLS_BASE,
+    MOEWALLS_DETAILS, MOEWALLS_DOWNLOAD, MOEWALLS_ITEM,
+};
+use async_trait::async_trait;
+
+pub struct Moewalls;
+
+#[async_trait]
+impl WallpaperSource for Moewalls {
+    fn id(&self) -> &str {
+        "moewalls"
+    }
+
+    fn display_name(&self) -> &str {
+        "MoeWalls"
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: true,
+            videos: true,
+            nsfw: false,
+            pagination: true,
+            resolve: true,
+        }
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        let query = params.query.trim();
+        let query = if query.is_empty() { None } else { Some(query) };
+        scrape_moewalls(query, params.limit, params.include_videos, params.page).await
+    }
+
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        vec![BrowseMode::Latest]
+    }
+
+    /// the homepage lists the newest posts
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        _range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        if mode != BrowseMode::Latest {
+            return Err(unsupported_browse(self.id(), mode));
+        }
+        scrape_moewalls(None, params.limit, params.include_videos, params.page).await
+    }
+
+    /// `url4k` is the 4k file when the post has one, `url` the best of the rest
+    async fn resolve(&self, detail_url: &str) -> Result<ResolvedMedia, ScrapeError> {
+        let variants = resolve_moewalls_download(detail_url).await?;
+        let is_4k = |width: Option<u32>, height: Option<u32>| {
+            width.unwrap_or(0) >= 3840 || height.unwrap_or(0) >= 2160
+        };
+
+        let url4k = variants
+            .iter()
+            .find(|variant| is_4k(variant.width, variant.height))
+            .map(|variant| variant.url.clone());
+        // sorted largest first, so this is the biggest one below 4k
+        let best = variants
+            .iter()
+            .find(|variant| !is_4k(variant.width, variant.height))
+            .unwrap_or(&variants[0]);
+
+        Ok(ResolvedMedia {
+            url: best.url.clone(),
+            url4k,
+            width: best.width,
+            height: best.height,
+            variants,
+        })
+    }
+
+    /// post pages list tags and categories as rel=tag links
+    async fn details(&self, item: &WallpaperItem) -> Result<WallpaperDetails, ScrapeError> {
+        let Some(detail_url) = item.detail_url.as_deref() else {
+            return Ok(WallpaperDetails::default());
+        };
+        scrape_detail_page("moewalls", MOEWALLS_BASE, detail_url, &MOEWALLS_DETAILS).await
+    }
+
+    fn health_probe(&self) -> Option<HealthProbe> {
+        Some(HealthProbe {
+            url: format!("{}/?s=anime", http::base_url("moewalls", MOEWALLS_BASE)),
+            checks: vec![
+                SelectorCheck::new("item", MOEWALLS_ITEM),
+                SelectorCheck::new("title", format!("{} a[title]", MOEWALLS_ITEM)),
+                SelectorCheck::new("detail link", format!("{} a[href]", MOEWALLS_ITEM)),
+                SelectorCheck::new("thumbnail", format!("{} img[src]", MOEWALLS_ITEM)),
+            ],
+            // the full video is only linked from the post
+            detail: Some(DetailProbe {
+                link: format!("{} a[href]", MOEWALLS_ITEM),
+                checks: vec![SelectorCheck::new("download", MOEWALLS_DOWNLOAD)],
+            }),
+        })
+    }
+}
@@ -0,0 +1,90 @@
+/// motionbgs.com - video only, falls back to the "featured" tag for empty queries
+use super::{unsupported_browse, ResolvedMedia, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::health::{DetailProbe, HealthProbe, SelectorCheck};
+use crate::http;
+use crate::models::{BrowseMode, SearchPage, SourceCapabilities, WallpaperDetails, WallpaperItem};
+use crate::scraper::{
+    scrape_detail_page, scrape_motionbgs, scrape_motionbgs_detail, MOTIONBGS_BASE,
+    MOTIONBGS_DETAILS, MOTIONBGS_ITEM, MOTIONBGS_TITLE, MOTIONBGS_VIDEO,
+};
+use async_trait::async_trait;
+
+pub struct MotionBgs;
+
+#[async_trait]
+impl WallpaperSource for MotionBgs {
+    fn id(&self) -> &str {
+        "motionbgs"
+    }
+
+    fn display_name(&self) -> &str {
+        "Live Wallpapers"
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: false,
+            videos: true,
+            nsfw: false,
+            pagination: true,
+            resolve: true,
+        }
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        scrape_motionbgs(&params.query, params.limit, params.page).await
+    }
+
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        vec![BrowseMode::Featured]
+    }
+
+    /// the "featured" tag, same listing an empty search lands on
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        _range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        if mode != BrowseMode::Featured {
+            return Err(unsupported_browse(self.id(), mode));
+        }
+        scrape_motionbgs("", params.limit, params.page).await
+    }
+
+    async fn resolve(&self, detail_url: &str) -> Result<ResolvedMedia, ScrapeError> {
+        let (url, url4k) = scrape_motionbgs_detail(detail_url).await?;
+        Ok(ResolvedMedia {
+            url,
+            url4k,
+            width: None,
+            height: None,
+            variants: Vec::new(),
+        })
+    }
+
+    /// resolution and size come from the download buttons
+    async fn details(&self, item: &WallpaperItem) -> Result<WallpaperDetails, ScrapeError> {
+        let Some(detail_url) = item.detail_url.as_deref() else {
+            return Ok(WallpaperDetails::default());
+        };
+        scrape_detail_page("motionbgs", MOTIONBGS_BASE, detail_url, &MOTIONBGS_DETAILS).await
+    }
+
+    fn health_probe(&self) -> Option<HealthProbe> {
+        Some(HealthProbe {
+            url: format!("{}/tag:anime/", http::base_url("motionbgs", MOTIONBGS_BASE)),
+            checks: vec![
+                SelectorCheck::new("item", MOTIONBGS_ITEM),
+                SelectorCheck::new("thumbnail", format!("{} img", MOTIONBGS_ITEM)),
+                SelectorCheck::new("title", format!("{} {}", MOTIONBGS_ITEM, MOTIONBGS_TITLE)),
+            ],
+            // the video url only lives on the detail page
+            detail: Some(DetailProbe {
+                link: MOTIONBGS_ITEM.to_string(),
+                checks: vec![SelectorCheck::new("video", MOTIONBGS_VIDEO)],
+            }),
+        })
+    }
+}
@@ -0,0 +1,180 @@
+/// scrapers run against responses recorded in `fixtures/replay` (http.rs replay mode),
+/// so a selector change shows up here instead of as an empty store. re-record with
+/// COLORWALL_HTTP_MODE=record COLORWALL_FIXTURES_DIR=src-tauri/fixtures/replay
+use super::moewalls::Moewalls;
+use super::motionbgs::MotionBgs;
+use super::wallhaven::Wallhaven;
+use super::wallpaperflare::WallpaperFlare;
+use super::{full_resolution_url, SearchParams, WallpaperSource};
+use crate::http;
+use crate::models::AppSettings;
+
+fn replay() {
+    http::configure(&AppSettings {
+        http_mode: Some("replay".to_string()),
+        http_fixtures_dir: Some(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/replay").to_string(),
+        ),
+        http_max_retries: 0,
+        ..AppSettings::default()
+    });
+}
+
+fn params(query: &str) -> SearchParams {
+    SearchParams {
+        query: query.to_string(),
+        page: 1,
+        limit: 24,
+        purity: "100".to_string(),
+        include_videos: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn wallhaven_search_and_resolve() {
+    replay();
+    let page = Wallhaven.search(&params("nature")).await.unwrap();
+    assert!(page.has_more);
+    assert_eq!(page.items.len(), 2);
+
+    let item = &page.items[0];
+    assert_eq!(item.id, "wallhaven-8oev1j");
+    assert_eq!(
+        item.image_url,
+        "https://w.wallhaven.cc/full/8o/wallhaven-8oev1j.jpg"
+    );
+    assert_eq!(
+        item.thumbnail_url.as_deref(),
+        Some("https://th.wallhaven.cc/lg/8o/8oev1j.jpg")
+    );
+    assert_eq!((item.width, item.height), (Some(3840), Some(2160)));
+    assert_eq!(
+        item.detail_url.as_deref(),
+        Some("https://wallhaven.cc/w/8oev1j")
+    );
+
+    let resolved = Wallhaven
+        .resolve("https://wallhaven.cc/w/8oev1j")
+        .await
+        .unwrap();
+    assert_eq!(
+        resolved.url,
+        "https://w.wallhaven.cc/full/8o/wallhaven-8oev1j.jpg"
+    );
+    assert_eq!((resolved.width, resolved.height), (Some(3840), Some(2160)));
+}
+
+#[tokio::test]
+async fn wallpaperflare_search_and_resolve() {
+    replay();
+    let page = WallpaperFlare.search(&params("nature")).await.unwrap();
+    assert!(page.has_more);
+    // the tag and pagination links aren't wallpapers
+    assert_eq!(page.items.len(), 2);
+
+    let item = &page.items[0];
+    assert_eq!(item.id, "wallpaperflare-pqwzy");
+    assert_eq!(item.title.as_deref(), Some("lake surrounded by mountains"));
+    assert_eq!(
+        item.thumbnail_url.as_deref(),
+        Some("https://c4.wallpaperflare.com/wallpaper/411/706/433/lake-mountains-wallpaper-preview.jpg")
+    );
+    let detail_url = item.detail_url.clone().unwrap();
+    assert_eq!(
+        detail_url,
+        "https://www.wallpaperflare.com/lake-surrounded-by-mountains-wallpaper-pqwzy"
+    );
+
+    let resolved = WallpaperFlare.resolve(&detail_url).await.unwrap();
+    assert_eq!(
+        resolved.url,
+        "https://c4.wallpaperflare.com/wallpaper/411/706/433/lake-mountains-wallpaper.jpg"
+    );
+    assert_eq!((resolved.width, resolved.height), (Some(5120), Some(2880)));
+}
+
+#[tokio::test]
+async fn moewalls_search_and_resolve() {
+    replay();
+    let page = Moewalls.search(&params("anime")).await.unwrap();
+    assert!(page.has_more);
+    assert_eq!(page.items.len(), 2);
+
+    let item = &page.items[0];
+    assert_eq!(item.id, "moewalls-ganyu-snowy-night");
+    assert_eq!(item.media_type.as_deref(), Some("video"));
+    assert_eq!(
+        item.image_url,
+        "https://static.moewalls.com/videos/preview/2024/ganyu-snowy-night-preview.mp4"
+    );
+    let detail_url = item.detail_url.clone().unwrap();
+    assert_eq!(
+        detail_url,
+        "https://moewalls.com/anime/ganyu-snowy-night-live-wallpaper/"
+    );
+
+    // the preview clip is skipped, the 4k file goes to url4k
+    let resolved = Moewalls.resolve(&detail_url).await.unwrap();
+    assert_eq!(resolved.variants.len(), 2);
+    assert_eq!(
+        resolved.url4k.as_deref(),
+        Some("https://go.moewalls.com/download.php?video=/2024/ganyu-snowy-night.mp4")
+    );
+    assert_eq!(
+        resolved.url,
+        "https://static.moewalls.com/videos/2024/ganyu-snowy-night-1920x1080.mp4"
+    );
+    assert_eq!((resolved.width, resolved.height), (Some(1920), Some(1080)));
+}
+
+#[tokio::test]
+async fn moewalls_still_keeps_its_image() {
+    replay();
+    let mut params = params("anime");
+    params.include_videos = false;
+    let page = Moewalls.search(&params).await.unwrap();
+
+    let item = &page.items[0];
+    assert_eq!(item.media_type.as_deref(), Some("image"));
+    assert_eq!(
+        item.image_url,
+        "https://static.moewalls.com/wp-content/uploads/2024/05/ganyu-snowy-night.jpg"
+    );
+    // the resolver only knows the video
+    let url = full_resolution_url(item, &item.image_url).await;
+    assert_eq!(url, item.image_url);
+}
+
+#[tokio::test]
+async fn motionbgs_search_and_resolve() {
+    replay();
+    let page = MotionBgs.search(&params("anime")).await.unwrap();
+    assert!(page.has_more);
+    // the absolute link to another site is skipped
+    assert_eq!(page.items.len(), 2);
+
+    let item = &page.items[0];
+    assert_eq!(item.id, "motionbgs-kamado-tanjiro-sunrise");
+    assert_eq!(item.title.as_deref(), Some("Kamado Tanjiro Sunrise"));
+    assert_eq!((item.width, item.height), (Some(3840), Some(2160)));
+    assert_eq!(
+        item.thumbnail_url.as_deref(),
+        Some("https://motionbgs.com/i/c/364x205/media/7311/kamado-tanjiro-sunrise.jpg")
+    );
+    assert_eq!(
+        (page.items[1].width, page.items[1].height),
+        (Some(1920), Some(1080))
+    );
+
+    let detail_url = item.detail_url.clone().unwrap();
+    let resolved = MotionBgs.resolve(&detail_url).await.unwrap();
+    assert_eq!(
+        resolved.url,
+        "https://motionbgs.com/media/7311/kamado-tanjiro-sunrise.960x540.mp4"
+    );
+    assert_eq!(
+        resolved.url4k.as_deref(),
+        Some("https://motionbgs.com/dl/4k/7311")
+    );
+}
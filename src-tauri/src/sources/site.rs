@@ -0,0 +1,845 @@
+/// declarative sources - a site described in toml/json instead of rust. covers the usual
+/// listing scraper: url templates, pagination, item/field selectors with attribute
+/// fallbacks, regex rewrites and media type, plus an optional detail page resolver.
+/// see `sites/wallpapers.toml` for a full example
+use super::{unsupported_browse, ResolvedMedia, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::health::{DetailProbe, HealthProbe, SelectorCheck};
+use crate::http;
+use crate::models::{BrowseMode, SearchPage, SourceCapabilities, WallpaperDetails, WallpaperItem};
+use crate::scraper::{
+    absolute_url, empty_or_changed, has_next_page, parse_detail_page, parse_resolution,
+    pick_image_source, GENERIC_DETAILS,
+};
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::RequestBuilder;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SiteDefinition {
+    /// source id, also the `WallpaperItem.id` prefix. reusing a built-in id replaces it
+    pub id: String,
+    pub name: String,
+    /// `{base}` in templates, still overridable through `http::base_url`
+    pub base_url: String,
+    /// "image" or "video", what a listing item is when no `video` field applies
+    #[serde(default = "default_media_type")]
+    pub media_type: String,
+    #[serde(default)]
+    pub nsfw: bool,
+    pub search: UrlTemplates,
+    #[serde(default)]
+    pub pagination: Pagination,
+    /// native listings for `browse_wallpapers`, keyed by mode ("latest", "toplist", ...)
+    #[serde(default)]
+    pub browse: HashMap<BrowseMode, BrowseTemplates>,
+    /// extra request headers, values can use `{base}`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// selector for one result on the listing page
+    pub item: String,
+    /// still present on an empty results page, tells "no results" apart from broken selectors
+    #[serde(default)]
+    pub empty_container: Option<String>,
+    /// link to the next listing page, when it's there the source has more. without it
+    /// rel=next / .next links and links to the next page's url are looked for
+    #[serde(default)]
+    pub next_page: Option<String>,
+    pub fields: Fields,
+    /// where the full file is on the detail page, for listings that only have thumbnails
+    #[serde(default)]
+    pub resolve: Option<ResolveRules>,
+    /// query the health check runs, should always have results
+    #[serde(default = "default_health_query")]
+    pub health_query: String,
+}
+
+/// placeholders: `{base}`, `{query}` (url encoded) and `{page}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UrlTemplates {
+    pub url: String,
+    /// used for page > 1, falls back to `url`
+    #[serde(default)]
+    pub page_url: Option<String>,
+    /// used for an empty query, falls back to `url` with an empty `{query}`
+    #[serde(default)]
+    pub browse_url: Option<String>,
+    #[serde(default)]
+    pub browse_page_url: Option<String>,
+}
+
+/// placeholders: `{base}`, `{page}` and `{range}` (the requested toplist range, may be empty)
+#[derive(Debug, Clone, Deserialize)]
+pub struct BrowseTemplates {
+    pub url: String,
+    /// used for page > 1, falls back to `url`
+    #[serde(default)]
+    pub page_url: Option<String>,
+}
+
+/// what `{page}` means: the 1-based page, an item offset, or no pagination at all
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "style", rename_all = "lowercase")]
+pub enum Pagination {
+    #[default]
+    Page,
+    Offset {
+        per_page: u32,
+    },
+    None,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fields {
+    pub id: FieldRule,
+    #[serde(default)]
+    pub title: Option<FieldRule>,
+    #[serde(default)]
+    pub thumbnail: Option<FieldRule>,
+    /// full image, falls back to the thumbnail
+    #[serde(default)]
+    pub image: Option<FieldRule>,
+    #[serde(default)]
+    pub detail: Option<FieldRule>,
+    /// makes the item a video when the caller wants videos (or the site is video only)
+    #[serde(default)]
+    pub video: Option<FieldRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldRule {
+    /// relative to the item, none = the item element itself
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// tried in order, "text" = text content. empty = text content
+    #[serde(default)]
+    pub attrs: Vec<String>,
+    /// value is a srcset / css url(), keep the first candidate
+    #[serde(default)]
+    pub srcset: bool,
+    #[serde(default)]
+    pub rewrite: Vec<RewriteRule>,
+}
+
+/// rules run against the item's detail page (`fields.detail`), makes the source resolvable
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResolveRules {
+    /// full file url, tried in order - e.g. the download button, then the big preview
+    pub image: Vec<FieldRule>,
+    /// text holding a "1920x1080", without it the file gets probed for its size
+    #[serde(default)]
+    pub resolution: Option<FieldRule>,
+}
+
+/// regex replace, `replace` can use `$1` style captures
+#[derive(Debug, Clone, Deserialize)]
+pub struct RewriteRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub replace: String,
+    /// drop the field when the pattern doesn't match instead of keeping the value as-is
+    #[serde(default)]
+    pub required: bool,
+}
+
+fn default_media_type() -> String {
+    "image".to_string()
+}
+
+fn default_health_query() -> String {
+    "nature".to_string()
+}
+
+impl SiteDefinition {
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|e| e.to_string())
+    }
+}
+
+struct CompiledRewrite {
+    pattern: Regex,
+    replace: String,
+    required: bool,
+}
+
+struct CompiledField {
+    rule: FieldRule,
+    selector: Option<Selector>,
+    rewrites: Vec<CompiledRewrite>,
+}
+
+impl CompiledField {
+    fn new(name: &str, rule: &FieldRule) -> Result<Self, String> {
+        let selector = match &rule.selector {
+            Some(selector) => Some(
+                Selector::parse(selector)
+                    .map_err(|e| format!("{}: bad selector `{}`: {:?}", name, selector, e))?,
+            ),
+            None => None,
+        };
+        let rewrites = rule
+            .rewrite
+            .iter()
+            .map(|rewrite| {
+                Regex::new(&rewrite.pattern)
+                    .map(|pattern| CompiledRewrite {
+                        pattern,
+                        replace: rewrite.replace.clone(),
+                        required: rewrite.required,
+                    })
+                    .map_err(|e| format!("{}: bad pattern `{}`: {}", name, rewrite.pattern, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            rule: rule.clone(),
+            selector,
+            rewrites,
+        })
+    }
+
+    fn extract(&self, item: ElementRef) -> Option<String> {
+        let element = match &self.selector {
+            Some(selector) => item.select(selector).next()?,
+            None => item,
+        };
+
+        let raw = if self.rule.attrs.is_empty() {
+            element.text().collect::<String>()
+        } else {
+            self.rule.attrs.iter().find_map(|attr| {
+                if attr == "text" {
+                    Some(element.text().collect::<String>())
+                } else {
+                    element.value().attr(attr).map(|value| value.to_string())
+                }
+            })?
+        };
+
+        let mut value = raw.trim().to_string();
+        if self.rule.srcset {
+            value = pick_image_source(&value);
+        }
+        for rewrite in &self.rewrites {
+            if rewrite.pattern.is_match(&value) {
+                value = rewrite
+                    .pattern
+                    .replace_all(&value, rewrite.replace.as_str())
+                    .into_owned();
+            } else if rewrite.required {
+                return None;
+            }
+        }
+
+        if value.is_empty() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// selector for the health check, the attribute is included when there's only one
+    fn check_selector(&self, item: &str) -> String {
+        let target = match &self.rule.selector {
+            Some(selector) => format!("{} {}", item, selector),
+            None => item.to_string(),
+        };
+        match self.rule.attrs.as_slice() {
+            [attr] if attr != "text" => format!("{}[{}]", target, attr),
+            _ => target,
+        }
+    }
+}
+
+/// a loaded definition with selectors and regexes compiled up front,
+/// so a typo fails at load time instead of on every search
+pub struct SiteSource {
+    def: SiteDefinition,
+    item: Selector,
+    id: CompiledField,
+    title: Option<CompiledField>,
+    thumbnail: Option<CompiledField>,
+    image: Option<CompiledField>,
+    detail: Option<CompiledField>,
+    video: Option<CompiledField>,
+    next_page: Option<Selector>,
+    resolve: Option<CompiledResolve>,
+}
+
+struct CompiledResolve {
+    image: Vec<CompiledField>,
+    resolution: Option<CompiledField>,
+}
+
+impl CompiledResolve {
+    fn new(rules: &ResolveRules) -> Result<Self, String> {
+        if rules.image.is_empty() {
+            return Err("resolve.image needs at least one rule".to_string());
+        }
+        Ok(Self {
+            image: rules
+                .image
+                .iter()
+                .map(|rule| CompiledField::new("resolve.image", rule))
+                .collect::<Result<Vec<_>, _>>()?,
+            resolution: compile_optional("resolve.resolution", &rules.resolution)?,
+        })
+    }
+}
+
+fn compile_optional(name: &str, rule: &Option<FieldRule>) -> Result<Option<CompiledField>, String> {
+    rule.as_ref()
+        .map(|rule| CompiledField::new(name, rule))
+        .transpose()
+}
+
+impl SiteSource {
+    pub fn new(def: SiteDefinition) -> Result<Self, String> {
+        if def.id.trim().is_empty() {
+            return Err("id is empty".to_string());
+        }
+        if def.media_type != "image" && def.media_type != "video" {
+            return Err(format!(
+                "media_type must be \"image\" or \"video\", got {}",
+                def.media_type
+            ));
+        }
+        if let Some(container) = &def.empty_container {
+            Selector::parse(container)
+                .map_err(|e| format!("bad empty_container `{}`: {:?}", container, e))?;
+        }
+        let item = Selector::parse(&def.item)
+            .map_err(|e| format!("bad item selector `{}`: {:?}", def.item, e))?;
+        let next_page = def
+            .next_page
+            .as_deref()
+            .map(|next| {
+                Selector::parse(next).map_err(|e| format!("bad next_page `{}`: {:?}", next, e))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            item,
+            id: CompiledField::new("fields.id", &def.fields.id)?,
+            title: compile_optional("fields.title", &def.fields.title)?,
+            thumbnail: compile_optional("fields.thumbnail", &def.fields.thumbnail)?,
+            image: compile_optional("fields.image", &def.fields.image)?,
+            detail: compile_optional("fields.detail", &def.fields.detail)?,
+            video: compile_optional("fields.video", &def.fields.video)?,
+            next_page,
+            resolve: def.resolve.as_ref().map(CompiledResolve::new).transpose()?,
+            def,
+        })
+    }
+
+    fn base(&self) -> String {
+        http::base_url(&self.def.id, &self.def.base_url)
+    }
+
+    /// GET with the definition's extra headers
+    fn get(&self, url: &str) -> RequestBuilder {
+        let base = self.base();
+        let mut request = http::get(url);
+        for (name, value) in &self.def.headers {
+            request = request.header(name.as_str(), value.replace("{base}", &base));
+        }
+        request
+    }
+
+    /// what `{page}` becomes for a 1-based page, `None` past the only page
+    fn page_value(&self, page: u32) -> Option<u32> {
+        let page = page.max(1);
+        match &self.def.pagination {
+            Pagination::Page => Some(page),
+            Pagination::Offset { per_page } => Some((page - 1) * per_page),
+            Pagination::None if page > 1 => None,
+            Pagination::None => Some(1),
+        }
+    }
+
+    fn search_url(&self, query: &str, page: u32) -> Option<String> {
+        let templates = &self.def.search;
+        let page_value = self.page_value(page)?;
+        let page = page.max(1);
+
+        let template = match (query.is_empty(), page > 1) {
+            (true, true) => templates
+                .browse_page_url
+                .as_ref()
+                .or(templates.browse_url.as_ref())
+                .or(templates.page_url.as_ref()),
+            (true, false) => templates.browse_url.as_ref(),
+            (false, true) => templates.page_url.as_ref(),
+            (false, false) => None,
+        }
+        .unwrap_or(&templates.url);
+
+        Some(
+            template
+                .replace("{base}", &self.base())
+                .replace("{query}", &urlencoding::encode(query))
+                .replace("{page}", &page_value.to_string()),
+        )
+    }
+
+    fn browse_url(&self, mode: BrowseMode, range: Option<&str>, page: u32) -> Option<String> {
+        let templates = self.def.browse.get(&mode)?;
+        let page_value = self.page_value(page)?;
+        let template = if page > 1 {
+            templates.page_url.as_ref().unwrap_or(&templates.url)
+        } else {
+            &templates.url
+        };
+
+        Some(
+            template
+                .replace("{base}", &self.base())
+                .replace("{range}", &urlencoding::encode(range.unwrap_or("")))
+                .replace("{page}", &page_value.to_string()),
+        )
+    }
+
+    /// `next_page` selector when the definition has one, otherwise a generic next link
+    /// or any link to `next_url`, the url the next page would have
+    fn has_next_page(&self, document: &Html, next_url: Option<String>) -> bool {
+        if let Some(next_page) = &self.next_page {
+            return document.select(next_page).next().is_some();
+        }
+        let Some(next_url) = next_url else {
+            return false;
+        };
+        // links are usually relative, so only match what comes after the origin
+        let base = self.base();
+        let next_href = next_url.strip_prefix(base.as_str()).unwrap_or(&next_url);
+        has_next_page(document, next_href)
+    }
+
+    fn parse_listing(
+        &self,
+        html: &str,
+        params: &SearchParams,
+        next_url: Option<String>,
+    ) -> Result<SearchPage, ScrapeError> {
+        let (limit, include_videos) = (params.limit, params.include_videos);
+        let base = self.base();
+        let document = Html::parse_document(html);
+        let field = |field: &Option<CompiledField>, element: ElementRef| {
+            field.as_ref().and_then(|field| field.extract(element))
+        };
+
+        let mut items = Vec::new();
+        for element in document.select(&self.item) {
+            if items.len() >= limit {
+                break;
+            }
+
+            let Some(id) = self.id.extract(element) else {
+                continue;
+            };
+            let thumbnail = field(&self.thumbnail, element).map(|url| absolute_url(&url, &base));
+            let image = field(&self.image, element)
+                .map(|url| absolute_url(&url, &base))
+                .or_else(|| thumbnail.clone());
+            let video = field(&self.video, element).map(|url| absolute_url(&url, &base));
+
+            let wants_video = include_videos || self.def.media_type == "video";
+            let (media_type, image_url) = match (video, image) {
+                (Some(video), _) if wants_video => ("video", video),
+                // video-only site and this item has no video, nothing to show
+                (_, _) if self.def.media_type == "video" => continue,
+                (_, Some(image)) => ("image", image),
+                (_, None) => continue,
+            };
+
+            items.push(WallpaperItem {
+                id: format!("{}-{}", self.def.id, id),
+                source: self.def.id.clone(),
+                title: Some(field(&self.title, element).unwrap_or_else(|| self.def.name.clone())),
+                image_url,
+                thumbnail_url: thumbnail,
+                media_type: Some(media_type.to_string()),
+                width: None,
+                height: None,
+                tags: None,
+                detail_url: field(&self.detail, element).map(|url| absolute_url(&url, &base)),
+                original: None,
+            });
+        }
+
+        if items.is_empty() {
+            let container = self
+                .def
+                .empty_container
+                .as_deref()
+                .unwrap_or(&self.def.item);
+            return Err(empty_or_changed(&document, container));
+        }
+        Ok(SearchPage {
+            has_more: self.has_next_page(&document, next_url),
+            items,
+        })
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for SiteSource {
+    fn id(&self) -> &str {
+        &self.def.id
+    }
+
+    fn display_name(&self) -> &str {
+        &self.def.name
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: self.def.media_type == "image",
+            videos: self.def.media_type == "video" || self.video.is_some(),
+            nsfw: self.def.nsfw,
+            pagination: !matches!(self.def.pagination, Pagination::None),
+            resolve: self.resolve.is_some() && self.detail.is_some(),
+        }
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        println!(
+            "[SOURCES:SITE] {} - query: '{}', page: {}, limit: {}",
+            self.def.id, params.query, params.page, params.limit
+        );
+        let Some(url) = self.search_url(&params.query, params.page) else {
+            return Err(ScrapeError::Empty);
+        };
+
+        let html = http::get_text(self.get(&url)).await?;
+
+        let next_url = self.search_url(&params.query, params.page.max(1) + 1);
+        let page = self.parse_listing(&html, params, next_url)?;
+        println!(
+            "[SOURCES:SITE] {} - found {} items, more: {}",
+            self.def.id,
+            page.items.len(),
+            page.has_more
+        );
+        Ok(page)
+    }
+
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        let mut modes: Vec<BrowseMode> = self.def.browse.keys().copied().collect();
+        modes.sort();
+        modes
+    }
+
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        if !self.def.browse.contains_key(&mode) {
+            return Err(unsupported_browse(&self.def.id, mode));
+        }
+        println!(
+            "[SOURCES:SITE] {} - browsing {}, page: {}",
+            self.def.id,
+            mode.as_str(),
+            params.page
+        );
+        let Some(url) = self.browse_url(mode, range, params.page) else {
+            return Err(ScrapeError::Empty);
+        };
+
+        let html = http::get_text(self.get(&url)).await?;
+        let next_url = self.browse_url(mode, range, params.page.max(1) + 1);
+        self.parse_listing(&html, params, next_url)
+    }
+
+    async fn resolve(&self, detail_url: &str) -> Result<ResolvedMedia, ScrapeError> {
+        let Some(resolve) = &self.resolve else {
+            return Err(ScrapeError::ParseChanged(format!(
+                "{} has no resolver",
+                self.def.id
+            )));
+        };
+
+        let base = self.base();
+        let url = absolute_url(detail_url, &base);
+        println!("[SOURCES:SITE] {} - resolving {}", self.def.id, url);
+        let html = http::get_text(self.get(&url).header("Referer", format!("{}/", base))).await?;
+
+        let document = Html::parse_document(&html);
+        let root = document.root_element();
+        let image = resolve
+            .image
+            .iter()
+            .find_map(|rule| rule.extract(root))
+            .map(|image| absolute_url(&image, &base))
+            .ok_or_else(|| ScrapeError::ParseChanged(format!("no full image on {}", url)))?;
+        let (width, height) = resolve
+            .resolution
+            .as_ref()
+            .and_then(|rule| rule.extract(root))
+            .map(|text| parse_resolution(&text))
+            .unwrap_or((None, None));
+
+        println!("[SOURCES:SITE] {} - resolved to {}", self.def.id, image);
+        Ok(ResolvedMedia {
+            url: image,
+            url4k: None,
+            width,
+            height,
+            variants: Vec::new(),
+        })
+    }
+
+    /// only what any page has in common, rel=tag links and schema.org markup
+    async fn details(&self, item: &WallpaperItem) -> Result<WallpaperDetails, ScrapeError> {
+        let Some(detail_url) = item.detail_url.as_deref() else {
+            return Ok(WallpaperDetails::default());
+        };
+        let url = absolute_url(detail_url, &self.base());
+        let html = http::get_text(self.get(&url)).await?;
+        Ok(parse_detail_page(&html, &GENERIC_DETAILS))
+    }
+
+    fn health_probe(&self) -> Option<HealthProbe> {
+        let item = &self.def.item;
+        let mut checks = vec![
+            SelectorCheck::new("item", item.clone()),
+            SelectorCheck::new("id", self.id.check_selector(item)),
+        ];
+        let fields = [
+            ("title", &self.title),
+            ("thumbnail", &self.thumbnail),
+            ("image", &self.image),
+            ("detail link", &self.detail),
+            ("video", &self.video),
+        ];
+        for (name, field) in fields {
+            if let Some(field) = field {
+                checks.push(SelectorCheck::new(name, field.check_selector(item)));
+            }
+        }
+
+        // any of the fallbacks finding the full image is enough
+        let detail = match (&self.resolve, &self.detail) {
+            (Some(resolve), Some(detail)) => Some(DetailProbe {
+                link: detail.check_selector(item),
+                checks: vec![SelectorCheck::new(
+                    "full image",
+                    resolve
+                        .image
+                        .iter()
+                        .map(|rule| rule.check_selector("").trim().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )],
+            }),
+            _ => None,
+        };
+
+        Some(HealthProbe {
+            url: self.search_url(&self.def.health_query, 1)?,
+            checks,
+            detail,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+id = "example"
+name = "Example"
+base_url = "https://example.com"
+item = "ul.results li"
+empty_container = "ul.results"
+
+[search]
+url = "{base}/search?q={query}"
+page_url = "{base}/search?q={query}&start={page}"
+
+[pagination]
+style = "offset"
+per_page = 24
+
+[fields.id]
+attrs = ["data-id"]
+
+[fields.thumbnail]
+selector = "img"
+attrs = ["data-src", "src"]
+
+[fields.image]
+selector = "img"
+attrs = ["src"]
+rewrite = [{ pattern = "/thumb/", replace = "/full/", required = true }]
+
+[fields.detail]
+selector = "a"
+attrs = ["href"]
+"#;
+
+    const JSON: &str = r#"{
+  "id": "example-video",
+  "name": "Example video",
+  "base_url": "https://video.example.com",
+  "media_type": "video",
+  "item": "article",
+  "search": { "url": "{base}/?s={query}&paged={page}" },
+  "browse": { "latest": { "url": "{base}/new/{page}" } },
+  "fields": {
+    "id": { "selector": "a", "attrs": ["href"], "rewrite": [{ "pattern": "^.*/([^/]+)/$", "replace": "$1" }] },
+    "title": { "selector": "h2" },
+    "detail": { "selector": "a", "attrs": ["href"] },
+    "thumbnail": { "selector": "img", "attrs": ["src"] },
+    "video": { "selector": "video source", "attrs": ["src"] }
+  },
+  "resolve": { "image": [{ "selector": "video source", "attrs": ["src"] }] }
+}"#;
+
+    const LISTING: &str = r#"<html><body><ul class="results">
+<li data-id="1"><a href="/w/1"><img data-src="/thumb/1.jpg" src="/thumb/1.jpg"></a></li>
+<li data-id="2"><a href="/w/2"><img src="/other/2.jpg"></a></li>
+<li><a href="/w/3"><img src="/thumb/3.jpg"></a></li>
+</ul></body></html>"#;
+
+    fn params() -> SearchParams {
+        SearchParams {
+            query: "forest".to_string(),
+            page: 1,
+            limit: 24,
+            purity: "100".to_string(),
+            include_videos: true,
+            ..Default::default()
+        }
+    }
+
+    fn site(content: &str) -> Result<SiteSource, String> {
+        SiteDefinition::from_toml(content).and_then(SiteSource::new)
+    }
+
+    #[test]
+    fn builtin_definition_loads() {
+        let site = site(include_str!("sites/wallpapers.toml")).unwrap();
+        assert_eq!(site.id(), "wallpapers");
+        assert!(site.capabilities().resolve);
+    }
+
+    #[test]
+    fn toml_definition() {
+        let site = site(TOML).unwrap();
+        assert_eq!(site.display_name(), "Example");
+        assert_eq!(site.def.media_type, "image");
+        assert_eq!(site.def.health_query, "nature");
+        assert!(!site.capabilities().resolve);
+
+        // offset pagination, page 2 starts after the first 24
+        assert_eq!(
+            site.search_url("misty forest", 1).as_deref(),
+            Some("https://example.com/search?q=misty%20forest")
+        );
+        assert_eq!(
+            site.search_url("misty forest", 2).as_deref(),
+            Some("https://example.com/search?q=misty%20forest&start=24")
+        );
+        assert_eq!(site.browse_url(BrowseMode::Latest, None, 1), None);
+    }
+
+    #[test]
+    fn toml_listing() {
+        let site = site(TOML).unwrap();
+        let page = site.parse_listing(LISTING, &params(), None).unwrap();
+        // the third item has no id
+        assert_eq!(page.items.len(), 2);
+        assert!(!page.has_more);
+
+        let first = &page.items[0];
+        assert_eq!(first.id, "example-1");
+        assert_eq!(first.title.as_deref(), Some("Example"));
+        assert_eq!(first.image_url, "https://example.com/full/1.jpg");
+        assert_eq!(
+            first.thumbnail_url.as_deref(),
+            Some("https://example.com/thumb/1.jpg")
+        );
+        assert_eq!(first.detail_url.as_deref(), Some("https://example.com/w/1"));
+
+        // the required rewrite didn't match, the thumbnail stands in
+        assert_eq!(page.items[1].image_url, "https://example.com/other/2.jpg");
+    }
+
+    #[test]
+    fn json_definition() {
+        let site = SiteDefinition::from_json(JSON)
+            .and_then(SiteSource::new)
+            .unwrap();
+        assert!(site.capabilities().resolve);
+        assert!(site.capabilities().videos);
+        assert_eq!(site.browse_modes(), vec![BrowseMode::Latest]);
+        assert_eq!(
+            site.browse_url(BrowseMode::Latest, None, 3).as_deref(),
+            Some("https://video.example.com/new/3")
+        );
+
+        let html = r#"<article><a href="https://video.example.com/clip/rain/"></a><h2>Rain</h2>
+<img src="/rain.jpg"><video><source src="/rain.mp4"></video></article>
+<article><a href="https://video.example.com/clip/still/"></a><img src="/still.jpg"></article>"#;
+        let mut params = params();
+        params.include_videos = false;
+        let page = site.parse_listing(html, &params, None).unwrap();
+        // a video-only site skips items without a video, even when videos aren't asked for
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "example-video-rain");
+        assert_eq!(page.items[0].media_type.as_deref(), Some("video"));
+        assert_eq!(
+            page.items[0].image_url,
+            "https://video.example.com/rain.mp4"
+        );
+    }
+
+    #[test]
+    fn invalid_definitions_are_errors() {
+        let broken = [
+            (
+                "missing field",
+                TOML.replace("item = \"ul.results li\"", ""),
+            ),
+            ("toml syntax", TOML.replace("[search]", "[search")),
+            ("bad item selector", TOML.replace("ul.results li", "ul[[")),
+            (
+                "bad field selector",
+                TOML.replace("selector = \"a\"", "selector = \"a[\""),
+            ),
+            ("bad regex", TOML.replace("/thumb/", "(thumb")),
+            (
+                "media type",
+                TOML.replace("base_url", "media_type = \"gif\"\nbase_url"),
+            ),
+            ("empty id", TOML.replace("id = \"example\"", "id = \" \"")),
+            (
+                "pagination style",
+                TOML.replace("style = \"offset\"", "style = \"cursor\""),
+            ),
+            (
+                "empty resolve",
+                format!("{}\n[resolve]\nimage = []\n", TOML),
+            ),
+        ];
+        for (what, content) in broken {
+            assert!(site(&content).is_err(), "{} should fail", what);
+        }
+
+        let e = site(&TOML.replace("/thumb/", "(thumb")).err().unwrap();
+        assert!(e.starts_with("fields.image: bad pattern"), "{}", e);
+        assert!(SiteDefinition::from_json("{\"id\": \"x\"}").is_err());
+        assert!(SiteDefinition::from_json("not json").is_err());
+    }
+}
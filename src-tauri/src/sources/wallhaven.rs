@@ -0,0 +1,183 @@
+/// wallhaven.cc - images only, the only source with purity/ai art filters.
+/// goes through the official json api, html scraping is kept as a fallback
+use super::{unsupported_browse, ResolvedMedia, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::health::{HealthProbe, SelectorCheck};
+use crate::http;
+use crate::models::{
+    BrowseMode, SearchPage, SourceCapabilities, WallhavenOptions, WallpaperDetails, WallpaperItem,
+};
+use crate::scraper::{
+    fetch_wallhaven_wallpaper, scrape_wallhaven, search_wallhaven_api, WALLHAVEN_BASE,
+    WALLHAVEN_ITEM,
+};
+use crate::storage::load_settings;
+use async_trait::async_trait;
+
+pub struct Wallhaven;
+
+/// wallhaven's `q` understands `-tag`, so excluded words go along with the search text
+fn wallhaven_query(params: &SearchParams) -> String {
+    let mut query = params.query.trim().to_string();
+    for word in params.exclude.iter().filter(|word| !word.contains(' ')) {
+        query = format!("{} -{}", query, word).trim().to_string();
+    }
+    query
+}
+
+#[async_trait]
+impl WallpaperSource for Wallhaven {
+    fn id(&self) -> &str {
+        "wallhaven"
+    }
+
+    fn display_name(&self) -> &str {
+        "WallHaven"
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: true,
+            videos: false,
+            nsfw: true,
+            pagination: true,
+            resolve: true,
+        }
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        let api_key = load_settings().wallhaven_api_key;
+        let query = wallhaven_query(params);
+
+        match search_wallhaven_api(
+            &query,
+            params.page,
+            params.ai_art,
+            &params.purity,
+            params.limit,
+            &params.wallhaven,
+            api_key.as_deref(),
+        )
+        .await
+        {
+            Ok(items) => Ok(items),
+            // the api answered and there's nothing, html would say the same
+            Err(ScrapeError::Empty) => Err(ScrapeError::Empty),
+            Err(e) => {
+                println!(
+                    "[SOURCES:WALLHAVEN] api failed ({}), falling back to html",
+                    e
+                );
+                scrape_wallhaven(
+                    &query,
+                    params.page,
+                    params.ai_art,
+                    &params.purity,
+                    params.limit,
+                )
+                .await
+            }
+        }
+    }
+
+    // `colors` is set from `params.color` when the search is prepared
+    fn filters_color(&self) -> bool {
+        true
+    }
+
+    fn filters_purity(&self) -> bool {
+        true
+    }
+
+    fn browse_modes(&self) -> Vec<BrowseMode> {
+        vec![
+            BrowseMode::Latest,
+            BrowseMode::Toplist,
+            BrowseMode::Random,
+            BrowseMode::Hot,
+        ]
+    }
+
+    /// the api's own sortings, "hot" is the toplist of the last day
+    async fn browse(
+        &self,
+        mode: BrowseMode,
+        range: Option<&str>,
+        params: &SearchParams,
+    ) -> Result<SearchPage, ScrapeError> {
+        let (sorting, top_range) = match mode {
+            BrowseMode::Latest => ("date_added", None),
+            BrowseMode::Toplist => ("toplist", Some(range.unwrap_or("1M"))),
+            BrowseMode::Hot => ("toplist", Some(range.unwrap_or("1d"))),
+            BrowseMode::Random => ("random", None),
+            BrowseMode::Featured => return Err(unsupported_browse(self.id(), mode)),
+        };
+
+        let mut params = params.clone();
+        params.query.clear();
+        params.wallhaven = WallhavenOptions {
+            sorting: Some(sorting.to_string()),
+            top_range: top_range.map(|range| range.to_string()),
+            ..params.wallhaven
+        };
+        self.search(&params).await
+    }
+
+    /// `detail_url` is the wallhaven.cc/w/{id} page, the id is all the api needs
+    async fn resolve(&self, detail_url: &str) -> Result<ResolvedMedia, ScrapeError> {
+        let id = detail_url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| {
+                ScrapeError::ParseChanged(format!("no wallhaven id in {}", detail_url))
+            })?;
+
+        let api_key = load_settings().wallhaven_api_key;
+        let wallpaper = fetch_wallhaven_wallpaper(id, api_key.as_deref()).await?;
+        Ok(ResolvedMedia {
+            url: wallpaper.path,
+            url4k: None,
+            width: Some(wallpaper.dimension_x),
+            height: Some(wallpaper.dimension_y),
+            variants: Vec::new(),
+        })
+    }
+
+    /// the single wallpaper endpoint has everything, no page to scrape
+    async fn details(&self, item: &WallpaperItem) -> Result<WallpaperDetails, ScrapeError> {
+        let id = item.id.trim_start_matches("wallhaven-");
+        let api_key = load_settings().wallhaven_api_key;
+        let wallpaper = fetch_wallhaven_wallpaper(id, api_key.as_deref()).await?;
+        Ok(WallpaperDetails {
+            tags: wallpaper
+                .tags
+                .unwrap_or_default()
+                .into_iter()
+                .map(|tag| tag.name)
+                .collect(),
+            uploader: wallpaper.uploader.map(|uploader| uploader.username),
+            width: Some(wallpaper.dimension_x),
+            height: Some(wallpaper.dimension_y),
+            file_size: Some(wallpaper.file_size),
+            ..Default::default()
+        })
+    }
+
+    fn health_probe(&self) -> Option<HealthProbe> {
+        // the html listing is what the fallback scraper depends on
+        Some(HealthProbe {
+            url: format!(
+                "{}/search?q=nature",
+                http::base_url("wallhaven", WALLHAVEN_BASE)
+            ),
+            checks: vec![
+                SelectorCheck::new("item", WALLHAVEN_ITEM),
+                SelectorCheck::new("detail link", format!("{} .preview[href]", WALLHAVEN_ITEM)),
+                SelectorCheck::new("thumbnail", format!("{} img", WALLHAVEN_ITEM)),
+            ],
+            detail: None,
+        })
+    }
+}
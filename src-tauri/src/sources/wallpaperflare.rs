@@ -0,0 +1,78 @@
+/// wallpaperflare.com - static images, listing gives thumbnails, high-res comes from the resolver
+use super::{ResolvedMedia, SearchParams, WallpaperSource};
+use crate::error::ScrapeError;
+use crate::health::{HealthProbe, SelectorCheck};
+use crate::http;
+use crate::models::{SearchPage, SourceCapabilities, WallpaperDetails, WallpaperItem};
+use crate::scraper::{
+    resolve_wallpaperflare_download, scrape_detail_page, scrape_wallpaperflare,
+    WALLPAPERFLARE_BASE, WALLPAPERFLARE_DETAILS,
+};
+use async_trait::async_trait;
+
+pub struct WallpaperFlare;
+
+#[async_trait]
+impl WallpaperSource for WallpaperFlare {
+    fn id(&self) -> &str {
+        "wallpaperflare"
+    }
+
+    fn display_name(&self) -> &str {
+        "WallpaperFlare"
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            images: true,
+            videos: false,
+            nsfw: false,
+            pagination: true,
+            resolve: true,
+        }
+    }
+
+    async fn search(&self, params: &SearchParams) -> Result<SearchPage, ScrapeError> {
+        scrape_wallpaperflare(&params.query, params.limit, params.page).await
+    }
+
+    async fn resolve(&self, detail_url: &str) -> Result<ResolvedMedia, ScrapeError> {
+        let (url, width, height) = resolve_wallpaperflare_download(detail_url).await?;
+        Ok(ResolvedMedia {
+            url,
+            url4k: None,
+            width,
+            height,
+            variants: Vec::new(),
+        })
+    }
+
+    /// detail pages carry schema.org markup, keywords, size and license included
+    async fn details(&self, item: &WallpaperItem) -> Result<WallpaperDetails, ScrapeError> {
+        let Some(detail_url) = item.detail_url.as_deref() else {
+            return Ok(WallpaperDetails::default());
+        };
+        scrape_detail_page(
+            "wallpaperflare",
+            WALLPAPERFLARE_BASE,
+            detail_url,
+            &WALLPAPERFLARE_DETAILS,
+        )
+        .await
+    }
+
+    fn health_probe(&self) -> Option<HealthProbe> {
+        // listing links are picked by href, so only check that linked thumbnails exist
+        Some(HealthProbe {
+            url: format!(
+                "{}/search?wallpaper=nature",
+                http::base_url("wallpaperflare", WALLPAPERFLARE_BASE)
+            ),
+            checks: vec![
+                SelectorCheck::new("thumbnail", "a[href*=\"wallpaper\"] img[data-src]"),
+                SelectorCheck::new("title", "a[href*=\"wallpaper\"] img[alt]"),
+            ],
+            detail: None,
+        })
+    }
+}
@@ -1,5 +1,6 @@
 /// store utilities for file paths and directories - for wallpapers, settings, etc
-use std::path::PathBuf;
+use crate::models::AppSettings;
+use std::path::{Path, PathBuf};
 
 /// persistent app data directory (AppData on Windows, ~/.config on Linux)
 pub fn get_app_data_dir() -> Result<PathBuf, String> {
@@ -55,3 +56,76 @@ pub fn get_settings_file() -> Result<PathBuf, String> {
     Ok(dir.join("settings.json"))
 }
 
+/// current settings for backend use, falls back to defaults if missing or unreadable
+pub fn load_settings() -> AppSettings {
+    get_settings_file()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// "image" / "video" for the file types the app can show, by extension
+pub fn media_type_for_extension(extension: &str) -> Option<&'static str> {
+    match extension.to_lowercase().as_str() {
+        "mp4" | "mkv" => Some("video"),
+        "jpg" | "jpeg" | "png" | "gif" => Some("image"),
+        _ => None,
+    }
+}
+
+/// local path behind a `file://` url, for items that come from the local source.
+/// `Ok(None)` for any other url. the frontend hands these urls back to us, so only
+/// files inside `AppSettings.local_folders` are let through
+pub fn local_path_from_url(url: &str) -> Result<Option<PathBuf>, String> {
+    if !url.starts_with("file://") {
+        return Ok(None);
+    }
+    let path = url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.to_file_path().ok())
+        .ok_or_else(|| format!("bad file url: {}", url))?;
+    confine_to_folders(&path, &load_settings().local_folders).map(Some)
+}
+
+/// `path` resolved (symlinks, `..`), as long as it ends up inside one of `folders`
+fn confine_to_folders(path: &Path, folders: &[String]) -> Result<PathBuf, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("can't open {}: {}", path.display(), e))?;
+    let inside = folders
+        .iter()
+        .filter_map(|folder| Path::new(folder).canonicalize().ok())
+        .any(|folder| path.starts_with(folder));
+    if !inside {
+        return Err(format!("{} is outside the local folders", path.display()));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_files_inside_the_folders_are_allowed() {
+        let root = std::env::temp_dir().join(format!("colorwall-confine-{}", std::process::id()));
+        let folder = root.join("wallpapers");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("a.png"), b"png").unwrap();
+        std::fs::write(root.join("secret.txt"), b"secret").unwrap();
+        let folders = vec![folder.to_string_lossy().to_string()];
+
+        let inside = confine_to_folders(&folder.join("a.png"), &folders);
+        let escaped = confine_to_folders(&folder.join("../secret.txt"), &folders);
+        let sibling = confine_to_folders(&root.join("secret.txt"), &folders);
+        let missing = confine_to_folders(&folder.join("gone.png"), &folders);
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert!(inside.unwrap().ends_with("a.png"));
+        assert!(escaped.is_err());
+        assert!(sibling.is_err());
+        assert!(missing.is_err());
+        assert!(confine_to_folders(&folder.join("a.png"), &[]).is_err());
+    }
+}
@@ -18,7 +18,8 @@ lazy_static::lazy_static! {
 }
 
 // storage module for wallpaper state file location
-use crate::storage::{get_app_data_dir, get_settings_file};
+use crate::http;
+use crate::storage::{get_app_data_dir, get_settings_file, local_path_from_url};
 
 /// wallpaper cache directory (temp for downloaded videos, can be cleared)
 fn get_wallpaper_dir() -> Result<PathBuf, String> {
@@ -63,15 +64,12 @@ fn load_wallpaper_state() -> Option<VideoWallpaperState> {
 }
 
 pub async fn download_video(url: &str) -> Result<PathBuf, String> {
-    let client = reqwest::Client::builder()
-        .user_agent("WallpaperApp/1.0")
-        .timeout(std::time::Duration::from_secs(300))
-        .build()
-        .map_err(|e| format!("failed to create HTTP client: {}", e))?;
-
-    let response = client
-        .get(url)
-        .send()
+    // local source items are already on disk
+    if let Some(path) = local_path_from_url(url)? {
+        return Ok(path);
+    }
+
+    let response = http::send(http::get(url))
         .await
         .map_err(|e| format!("failed to download video: {}", e))?;
 
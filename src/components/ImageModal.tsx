@@ -1,7 +1,7 @@
 import React, { useCallback, useEffect, useRef, useState } from 'react';
-import { Download, Loader2, Play, X, ZoomIn, ZoomOut, CheckCircle } from 'lucide-react';
+import { Download, Loader2, Play, X, ZoomIn, ZoomOut, CheckCircle, Sparkles, EyeOff } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
-import { WallpaperItem } from '../types/wallpaper';
+import { MediaVariant, ResolveHighResResponse, WallpaperDetails, WallpaperDetailsResponse, WallpaperItem } from '../types/wallpaper';
 // import { getSourceIcon } from './icons';
 import { motion, AnimatePresence } from 'framer-motion';
 
@@ -10,9 +10,18 @@ interface ImageModalProps {
     onClose: () => void;
     onSetWallpaper: (url: string) => void;
     isLoading: boolean;
+    // start a new search for a clicked tag, tags are plain labels without it
+    onTagClick?: (tag: string) => void;
+    // search for wallpapers like this one (same wallhaven neighbours, tags and colors)
+    onFindSimilar?: (item: WallpaperItem) => void;
+    // put this wallpaper (and the same picture on other sites) on the blocklist
+    onBlock?: (item: WallpaperItem) => void;
 }
 
-const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalProps) => {
+const formatFileSize = (bytes: number) =>
+    bytes >= 1024 * 1024 ? `${(bytes / (1024 * 1024)).toFixed(1)} MB` : `${Math.round(bytes / 1024)} KB`;
+
+const ImageModal = ({ image, onClose, onSetWallpaper, isLoading, onTagClick, onFindSimilar, onBlock }: ImageModalProps) => {
     const [zoom, setZoom] = useState(1);
     const [imgLoaded, setImgLoaded] = useState(false);
     const [displayUrl, setDisplayUrl] = useState<string>(image.thumbnailUrl || image.imageUrl);
@@ -22,20 +31,32 @@ const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalPro
     const [isSettingVideo, setIsSettingVideo] = useState(false);
     const [isDownloading, setIsDownloading] = useState(false);
     const [progressMessage, setProgressMessage] = useState<string>('');
+    const [details, setDetails] = useState<WallpaperDetails | null>(null);
+    const [variants, setVariants] = useState<MediaVariant[]>([]);
+    // size of the resolved file, for listings that don't give one
+    const [resolvedSize, setResolvedSize] = useState<{ width: number; height: number } | null>(null);
     const hasResolvedRef = useRef(false);
     const abortControllerRef = useRef<AbortController | null>(null);
 
     useEffect(() => {
         hasResolvedRef.current = false;
         setIsResolving(false);
+        setVariants([]);
+        setResolvedSize(null);
+        const keepSize = (result: ResolveHighResResponse) => {
+            if (result.width && result.height) {
+                setResolvedSize({ width: result.width, height: result.height });
+            }
+        };
 
         if (image.source === 'wallpaperflare' && image.detailUrl) {
             setIsResolving(true);
             (async () => {
                 try {
-                    const result: any = await invoke('resolve_wallpaperflare_highres', { detailUrl: image.detailUrl });
+                    const result = await invoke<ResolveHighResResponse>('resolve_wallpaperflare_highres', { detailUrl: image.detailUrl });
                     if (result?.success && result?.url && !hasResolvedRef.current) {
                         hasResolvedRef.current = true;
+                        keepSize(result);
                         setHighResUrl(result.url);
                         setDisplayUrl(result.url);
                         setImgLoaded(false);
@@ -46,16 +67,19 @@ const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalPro
                     setIsResolving(false);
                 }
             })();
-        } else if (image.source === 'motionbgs' && image.detailUrl) {
+        } else if ((image.source === 'motionbgs' || (image.source === 'moewalls' && image.type === 'video')) && image.detailUrl) {
             setIsResolving(true);
             (async () => {
                 try {
-                    const result: any = await invoke('resolve_motionbgs_video', { detailUrl: image.detailUrl });
+                    // moewalls listings only link the preview clip, the post has the real file
+                    const command = image.source === 'moewalls' ? 'resolve_moewalls_video' : 'resolve_motionbgs_video';
+                    const result: any = await invoke(command, { detailUrl: image.detailUrl });
                     if (result?.success && result?.url && !hasResolvedRef.current) {
                         hasResolvedRef.current = true;
                         setHighResUrl(result.url);
                         setDisplayUrl(result.url);
                         setUrl4k(result.url4k || null);
+                        setVariants(result.variants || []);
                         setImgLoaded(false);
                         setIsResolving(false);
                     }
@@ -64,11 +88,51 @@ const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalPro
                     setIsResolving(false);
                 }
             })();
+        } else if (image.detailUrl && image.imageUrl === image.thumbnailUrl) {
+            // thumbnail-only listing (wallpapers.com, site definitions with a resolve section)
+            setHighResUrl(image.imageUrl);
+            setIsResolving(true);
+            (async () => {
+                try {
+                    const result = await invoke<ResolveHighResResponse>('resolve_wallpaper', { source: image.source, detailUrl: image.detailUrl });
+                    if (result?.success && result?.url && !hasResolvedRef.current) {
+                        hasResolvedRef.current = true;
+                        keepSize(result);
+                        setHighResUrl(result.url);
+                        setDisplayUrl(result.url);
+                        setImgLoaded(false);
+                    }
+                } catch (e) {
+                    console.error('[ERROR] Failed to resolve full image:', e);
+                } finally {
+                    setIsResolving(false);
+                }
+            })();
         } else {
             setHighResUrl(image.imageUrl);
         }
     }, [image.id, image.detailUrl, image.source, image.thumbnailUrl, image.imageUrl]);
 
+    // tags/uploader/size/license live on the detail page, the backend caches them per item
+    useEffect(() => {
+        let cancelled = false;
+        setDetails(null);
+        invoke<WallpaperDetailsResponse>('get_wallpaper_details', { item: image })
+            .then((result) => {
+                if (!cancelled && result?.success && result.details) {
+                    setDetails(result.details);
+                }
+            })
+            .catch((e) => console.error('[ERROR] Failed to load details:', e));
+        return () => {
+            cancelled = true;
+        };
+    }, [image.id]);
+
+    const width = image.width ?? resolvedSize?.width ?? details?.width;
+    const height = image.height ?? resolvedSize?.height ?? details?.height;
+    const tags = details?.tags.length ? details.tags : image.tags;
+
     useEffect(() => {
         const handleEsc = (e: KeyboardEvent) => {
             if (e.key === 'Escape') onClose();
@@ -219,27 +283,80 @@ const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalPro
                             </div>
                         </div>
 
-                        {image.width && image.height && (
+                        {width && height && (
                             <div className="bg-gray-900/50 rounded-lg p-3 border border-gray-800/50">
                                 <div className="text-xs text-gray-500 font-semibold uppercase tracking-wider mb-1.5">Dimensions</div>
                                 <div className="text-xl font-bold text-gray-200 font-mono">
-                                    {image.width} × {image.height}
+                                    {width} × {height}
                                 </div>
-                                <div className="text-xs text-gray-600 mt-1">{(image.width / image.height).toFixed(2)} aspect ratio</div>
+                                <div className="text-xs text-gray-600 mt-1">{(width / height).toFixed(2)} aspect ratio</div>
+                            </div>
+                        )}
+
+                        {details && (details.uploader || details.fileSize || details.license) && (
+                            <div className="bg-gray-900/50 rounded-lg p-3 border border-gray-800/50 space-y-1.5 text-xs">
+                                {details.uploader && (
+                                    <div className="flex justify-between gap-2">
+                                        <span className="text-gray-500 font-semibold uppercase tracking-wider">Uploader</span>
+                                        <span className="text-gray-300 truncate">{details.uploader}</span>
+                                    </div>
+                                )}
+                                {details.fileSize && (
+                                    <div className="flex justify-between gap-2">
+                                        <span className="text-gray-500 font-semibold uppercase tracking-wider">File size</span>
+                                        <span className="text-gray-300 font-mono">{formatFileSize(details.fileSize)}</span>
+                                    </div>
+                                )}
+                                {details.license && (
+                                    <div className="flex justify-between gap-2">
+                                        <span className="text-gray-500 font-semibold uppercase tracking-wider">License</span>
+                                        <span className="text-gray-300 truncate">{details.license}</span>
+                                    </div>
+                                )}
                             </div>
                         )}
 
-                        {image.tags && image.tags.length > 0 && (
+                        {tags && tags.length > 0 && (
                             <div className="bg-gray-900/50 rounded-lg p-3 border border-gray-800/50">
                                 <div className="text-xs text-gray-500 font-semibold uppercase tracking-wider mb-2">Tags</div>
                                 <div className="flex flex-wrap gap-1.5">
-                                    {image.tags.slice(0, 10).map((tag, idx) => (
-                                        <span
-                                            key={idx}
-                                            className="px-2 py-1 bg-gray-800/50 text-gray-400 rounded-md text-xs font-medium border border-gray-700/50"
-                                        >
-                                            {tag}
-                                        </span>
+                                    {tags.slice(0, 20).map((tag, idx) =>
+                                        onTagClick ? (
+                                            <button
+                                                key={idx}
+                                                onClick={(e: React.MouseEvent<HTMLButtonElement>) => {
+                                                    e.stopPropagation();
+                                                    onTagClick(tag);
+                                                }}
+                                                className="px-2 py-1 bg-gray-800/50 hover:bg-blue-500/20 text-gray-400 hover:text-blue-300 rounded-md text-xs font-medium border border-gray-700/50 hover:border-blue-500/30 transition-all cursor-pointer"
+                                                title={`Search for ${tag}`}
+                                            >
+                                                {tag}
+                                            </button>
+                                        ) : (
+                                            <span
+                                                key={idx}
+                                                className="px-2 py-1 bg-gray-800/50 text-gray-400 rounded-md text-xs font-medium border border-gray-700/50"
+                                            >
+                                                {tag}
+                                            </span>
+                                        )
+                                    )}
+                                </div>
+                            </div>
+                        )}
+
+                        {variants.length > 1 && (
+                            <div className="bg-gray-900/50 rounded-lg p-3 border border-gray-800/50">
+                                <div className="text-xs text-gray-500 font-semibold uppercase tracking-wider mb-2">Available files</div>
+                                <div className="space-y-1 text-xs">
+                                    {variants.map((variant) => (
+                                        <div key={variant.url} className="flex justify-between gap-2 text-gray-300">
+                                            <span className="font-mono">
+                                                {variant.width && variant.height ? `${variant.width} × ${variant.height}` : variant.label || 'Video'}
+                                            </span>
+                                            {variant.fileSize && <span className="text-gray-500 font-mono">{formatFileSize(variant.fileSize)}</span>}
+                                        </div>
                                     ))}
                                 </div>
                             </div>
@@ -280,7 +397,8 @@ const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalPro
                                     // Invoke Rust command to download and save
                                     const result: any = await invoke('download_wallpaper', {
                                         url: downloadUrl,
-                                        suggestedFilename: filename
+                                        suggestedFilename: filename,
+                                        item: image,
                                     });
 
                                     if (result?.success) {
@@ -312,7 +430,33 @@ const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalPro
                             )}
                         </button>
 
-                        {image.type === 'video' && image.source === 'motionbgs' ? (
+                        {onFindSimilar && (
+                            <button
+                                onClick={(e: React.MouseEvent<HTMLButtonElement>) => {
+                                    e.stopPropagation();
+                                    onFindSimilar(image);
+                                }}
+                                className="w-full flex items-center justify-center gap-2 bg-gray-900/80 hover:bg-gray-800 text-white px-4 py-3 rounded-lg transition-all font-medium shadow-lg cursor-pointer border border-gray-800/50 text-sm"
+                            >
+                                <Sparkles className="w-4 h-4" />
+                                More Like This
+                            </button>
+                        )}
+
+                        {onBlock && (
+                            <button
+                                onClick={(e: React.MouseEvent<HTMLButtonElement>) => {
+                                    e.stopPropagation();
+                                    onBlock(image);
+                                }}
+                                className="w-full flex items-center justify-center gap-2 bg-gray-900/80 hover:bg-gray-800 text-white px-4 py-3 rounded-lg transition-all font-medium shadow-lg cursor-pointer border border-gray-800/50 text-sm"
+                            >
+                                <EyeOff className="w-4 h-4" />
+                                Block This Wallpaper
+                            </button>
+                        )}
+
+                        {image.type === 'video' && (image.source === 'motionbgs' || image.source === 'moewalls') ? (
                             <button
                                 onClick={(e: React.MouseEvent<HTMLButtonElement>) => {
                                     e.stopPropagation();
@@ -348,7 +492,7 @@ const ImageModal = ({ image, onClose, onSetWallpaper, isLoading }: ImageModalPro
                                         }
                                     }, 1500);
 
-                                    invoke('set_video_wallpaper', { videoUrl: videoUrlToUse })
+                                    invoke('set_video_wallpaper', { videoUrl: videoUrlToUse, item: image })
                                         .then((result: any) => {
                                             clearInterval(progressInterval);
                                             if (!abortControllerRef.current?.signal.aborted) {
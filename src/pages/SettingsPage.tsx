@@ -2,13 +2,57 @@
 import React from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { motion } from 'framer-motion';
-import { Settings, Video, Volume2, HardDrive, Trash2, Info, Folder } from 'lucide-react';
+import { Settings, Video, Volume2, HardDrive, Trash2, Info, Folder, ShieldCheck, X } from 'lucide-react';
+import { BlockEntry, BlockKind, BlocklistResponse } from '../types/wallpaper';
 
 interface AppSettings {
     audioEnabled: boolean;
     liveWallpaperEnabled: boolean;
     videoPlayer: 'wmf' | 'mpv';
     mpvPath: string | null;
+    // wallhaven api key, unlocks nsfw purity
+    wallhavenApiKey?: string | null;
+    // hours between background source health checks, 0 = off
+    healthCheckIntervalHours?: number;
+    // danbooru/gelbooru/konachan/yande.re style sources
+    booruSources?: BooruConfig[];
+    // rss/atom/json feeds, each one is its own source
+    feeds?: FeedConfig[];
+    // folders searched by the "local" source
+    localFolders?: string[];
+    // minutes cached search pages stay fresh, 0 = always refetch
+    searchCacheTtlMinutes?: number;
+    // per-source overrides of the above
+    sourceCacheTtlMinutes?: Record<string, number>;
+    // collapse the same picture found on several sources, alternates go to original.alternates
+    dedupSimilar?: boolean;
+    // read image headers to fill in missing sizes
+    probeDimensions?: boolean;
+    // highest content level any source may return
+    contentPurity?: 'sfw' | 'sketchy' | 'nsfw';
+    // off filters ai art on every source, by keyword where a site has no filter of its own
+    allowAiArt?: boolean;
+    // items with one of these words in their title or tags are hidden
+    blockedKeywords?: string[];
+}
+
+interface FeedConfig {
+    id: string;
+    name: string;
+    url: string;
+    enabled: boolean;
+}
+
+interface BooruConfig {
+    id: string;
+    name: string;
+    kind: 'danbooru' | 'gelbooru' | 'moebooru';
+    baseUrl: string;
+    user?: string | null;
+    apiKey?: string | null;
+    minWidth: number;
+    minHeight: number;
+    enabled: boolean;
 }
 
 interface VideoState {
@@ -29,6 +73,8 @@ export default function SettingsPage() {
     const [videoState, setVideoState] = React.useState<VideoState>({ isActive: false });
     const [loading, setLoading] = React.useState(true);
     const [saving, setSaving] = React.useState(false);
+    const [blocklist, setBlocklist] = React.useState<BlockEntry[]>([]);
+    const [newBlock, setNewBlock] = React.useState<{ kind: BlockKind; value: string }>({ kind: 'tag', value: '' });
 
     const loadData = React.useCallback(async () => {
         try {
@@ -63,8 +109,63 @@ export default function SettingsPage() {
 
     React.useEffect(() => {
         loadData();
+        invoke<BlocklistResponse>('list_blocklist')
+            .then((result) => setBlocklist(result.entries))
+            .catch((error) => console.error('Failed to load blocklist:', error));
     }, [loadData]);
 
+    // every blocklist command answers with the whole list
+    const runBlocklistCommand = async (command: string, args: Record<string, unknown>) => {
+        try {
+            const result = await invoke<BlocklistResponse>(command, args);
+            setBlocklist(result.entries);
+            if (!result.success) {
+                alert('Blocklist: ' + result.error);
+            }
+            return result;
+        } catch (error) {
+            console.error(`${command} failed:`, error);
+            alert('Error: ' + error);
+            return null;
+        }
+    };
+
+    const handleAddBlock = async () => {
+        if (!newBlock.value.trim()) return;
+        const result = await runBlocklistCommand('add_blocklist_entry', { kind: newBlock.kind, value: newBlock.value });
+        if (result?.success) {
+            setNewBlock({ ...newBlock, value: '' });
+        }
+    };
+
+    const handleImportBlocklist = async () => {
+        const { open } = await import('@tauri-apps/plugin-dialog');
+        const selected = await open({
+            multiple: false,
+            filters: [{ name: 'Blocklist', extensions: ['json'] }],
+            title: 'Import blocklist',
+        });
+        if (selected && typeof selected === 'string') {
+            const result = await runBlocklistCommand('import_blocklist', { path: selected });
+            if (result?.success) {
+                alert(`Imported ${result.added} new entries`);
+            }
+        }
+    };
+
+    const handleExportBlocklist = async () => {
+        const { save } = await import('@tauri-apps/plugin-dialog');
+        const path = await save({
+            defaultPath: 'blocklist.json',
+            filters: [{ name: 'Blocklist', extensions: ['json'] }],
+            title: 'Export blocklist',
+        });
+        if (path) {
+            const result: any = await invoke('export_blocklist', { path });
+            alert(result.success ? result.message : 'Export failed: ' + result.error);
+        }
+    };
+
     const handleSaveSettings = async (newSettings: AppSettings) => {
         setSaving(true);
         try {
@@ -492,6 +593,245 @@ export default function SettingsPage() {
                 {/* Divider */}
                 <div style={{ height: '1px', background: 'var(--border-subtle)' }} />
 
+                {/* Content Settings */}
+                <motion.div
+                    initial={{ y: 20, opacity: 0 }}
+                    animate={{ y: 0, opacity: 1 }}
+                    transition={{ delay: 0.18, duration: 0.5 }}
+                >
+                    <div style={{ display: 'flex', alignItems: 'center', gap: '12px', marginBottom: '24px' }}>
+                        <ShieldCheck size={24} style={{ color: 'var(--accent)' }} />
+                        <h2 style={{ fontSize: '20px', fontWeight: 700 }}>Content</h2>
+                    </div>
+
+                    <div style={{ display: 'flex', flexDirection: 'column', gap: '16px' }}>
+                        {/* Safety Level */}
+                        <div
+                            style={{
+                                padding: '16px',
+                                background: 'rgba(0, 0, 0, 0.2)',
+                                borderRadius: 'var(--radius-md)',
+                                border: '1px solid var(--border-subtle)',
+                            }}
+                        >
+                            <div style={{ fontSize: '15px', fontWeight: 600, marginBottom: '6px' }}>
+                                Safety Level
+                            </div>
+                            <div style={{ fontSize: '13px', color: 'var(--text-secondary)', marginBottom: '16px' }}>
+                                The most any source may show. Sources that can't filter NSFW themselves are only searched at NSFW.
+                            </div>
+
+                            <div style={{ display: 'flex', gap: '12px' }}>
+                                {(['sfw', 'sketchy', 'nsfw'] as const).map((level) => {
+                                    const selected = (settings.contentPurity ?? 'sfw') === level;
+                                    return (
+                                        <label
+                                            key={level}
+                                            style={{
+                                                flex: 1,
+                                                display: 'flex',
+                                                alignItems: 'center',
+                                                gap: '10px',
+                                                padding: '12px 16px',
+                                                background: selected ? 'rgba(0, 120, 212, 0.15)' : 'rgba(0,0,0,0.2)',
+                                                border: selected ? '1px solid rgba(0, 120, 212, 0.4)' : '1px solid var(--border-subtle)',
+                                                borderRadius: 'var(--radius-md)',
+                                                cursor: 'pointer',
+                                                transition: 'var(--transition)',
+                                            }}
+                                        >
+                                            <input
+                                                type="radio"
+                                                name="contentPurity"
+                                                value={level}
+                                                checked={selected}
+                                                onChange={() => handleSaveSettings({ ...settings, contentPurity: level })}
+                                                disabled={saving}
+                                                style={{ accentColor: 'var(--accent)' }}
+                                            />
+                                            <div style={{ fontWeight: 600, fontSize: '14px' }}>
+                                                {level === 'sfw' ? 'SFW' : level === 'sketchy' ? 'Sketchy' : 'NSFW'}
+                                            </div>
+                                        </label>
+                                    );
+                                })}
+                            </div>
+
+                            <label style={{ display: 'flex', alignItems: 'center', gap: '10px', marginTop: '16px', cursor: 'pointer' }}>
+                                <input
+                                    type="checkbox"
+                                    checked={settings.allowAiArt ?? true}
+                                    onChange={(e) => handleSaveSettings({ ...settings, allowAiArt: e.target.checked })}
+                                    disabled={saving}
+                                    style={{ accentColor: 'var(--accent)' }}
+                                />
+                                <span style={{ fontSize: '14px' }}>Allow AI generated art</span>
+                            </label>
+                        </div>
+
+                        {/* Blocked Keywords */}
+                        <div
+                            style={{
+                                padding: '16px',
+                                background: 'rgba(0, 0, 0, 0.2)',
+                                borderRadius: 'var(--radius-md)',
+                                border: '1px solid var(--border-subtle)',
+                            }}
+                        >
+                            <div style={{ fontSize: '15px', fontWeight: 600, marginBottom: '6px' }}>
+                                Blocked Keywords
+                            </div>
+                            <div style={{ fontSize: '13px', color: 'var(--text-secondary)', marginBottom: '12px' }}>
+                                One per line. Results with one of these words in their title or tags are hidden.
+                            </div>
+                            <textarea
+                                defaultValue={(settings.blockedKeywords ?? []).join('\n')}
+                                onBlur={(e) => {
+                                    const blockedKeywords = e.target.value
+                                        .split(/[\n,]/)
+                                        .map((keyword) => keyword.trim())
+                                        .filter(Boolean);
+                                    if (blockedKeywords.join('\n') !== (settings.blockedKeywords ?? []).join('\n')) {
+                                        handleSaveSettings({ ...settings, blockedKeywords });
+                                    }
+                                }}
+                                rows={4}
+                                placeholder="gore"
+                                style={{
+                                    width: '100%',
+                                    padding: '10px 14px',
+                                    background: 'rgba(0, 0, 0, 0.3)',
+                                    border: '1px solid var(--border-subtle)',
+                                    borderRadius: 'var(--radius-md)',
+                                    color: 'var(--text-primary)',
+                                    fontSize: '13px',
+                                    resize: 'vertical',
+                                }}
+                            />
+                        </div>
+
+                        {/* Blocklist */}
+                        <div
+                            style={{
+                                padding: '16px',
+                                background: 'rgba(0, 0, 0, 0.2)',
+                                borderRadius: 'var(--radius-md)',
+                                border: '1px solid var(--border-subtle)',
+                            }}
+                        >
+                            <div style={{ fontSize: '15px', fontWeight: 600, marginBottom: '6px' }}>
+                                Blocklist
+                            </div>
+                            <div style={{ fontSize: '13px', color: 'var(--text-secondary)', marginBottom: '12px' }}>
+                                Wallpapers, tags, title patterns and sources that never show up. "Block This Wallpaper" in the preview adds entries here. Export the list to share it.
+                            </div>
+
+                            <div style={{ display: 'flex', gap: '8px', marginBottom: '12px' }}>
+                                <select
+                                    value={newBlock.kind}
+                                    onChange={(e) => setNewBlock({ ...newBlock, kind: e.target.value as BlockKind })}
+                                    style={{
+                                        padding: '10px',
+                                        background: 'rgba(0, 0, 0, 0.3)',
+                                        border: '1px solid var(--border-subtle)',
+                                        borderRadius: 'var(--radius-md)',
+                                        color: 'var(--text-primary)',
+                                        fontSize: '13px',
+                                    }}
+                                >
+                                    <option value="tag">Tag</option>
+                                    <option value="title">Title (regex)</option>
+                                    <option value="source">Source</option>
+                                    <option value="item">Wallpaper id</option>
+                                    <option value="hash">Image hash</option>
+                                </select>
+                                <input
+                                    type="text"
+                                    value={newBlock.value}
+                                    onChange={(e) => setNewBlock({ ...newBlock, value: e.target.value })}
+                                    onKeyDown={(e) => e.key === 'Enter' && handleAddBlock()}
+                                    placeholder={newBlock.kind === 'title' ? '^untitled' : newBlock.kind === 'source' ? 'wallpaperflare' : 'low quality'}
+                                    style={{
+                                        flex: 1,
+                                        padding: '10px 14px',
+                                        background: 'rgba(0, 0, 0, 0.3)',
+                                        border: '1px solid var(--border-subtle)',
+                                        borderRadius: 'var(--radius-md)',
+                                        color: 'var(--text-primary)',
+                                        fontSize: '13px',
+                                    }}
+                                />
+                                <button
+                                    onClick={handleAddBlock}
+                                    style={{
+                                        padding: '10px 16px',
+                                        background: 'var(--accent)',
+                                        border: 'none',
+                                        borderRadius: 'var(--radius-md)',
+                                        color: 'white',
+                                        fontSize: '13px',
+                                        fontWeight: 600,
+                                        cursor: 'pointer',
+                                    }}
+                                >
+                                    Block
+                                </button>
+                            </div>
+
+                            {blocklist.length > 0 && (
+                                <div style={{ display: 'flex', flexDirection: 'column', gap: '6px', maxHeight: '240px', overflowY: 'auto', marginBottom: '12px' }}>
+                                    {blocklist.map((entry) => (
+                                        <div
+                                            key={`${entry.kind}:${entry.value}`}
+                                            style={{ display: 'flex', alignItems: 'center', gap: '10px', fontSize: '13px' }}
+                                        >
+                                            <span style={{ color: 'var(--text-secondary)', minWidth: '56px' }}>{entry.kind}</span>
+                                            <span style={{ flex: 1, fontFamily: 'Consolas, Monaco, monospace', overflow: 'hidden', textOverflow: 'ellipsis', whiteSpace: 'nowrap' }} title={entry.note}>
+                                                {entry.value}
+                                                {entry.note && <span style={{ color: 'var(--text-secondary)', fontFamily: 'inherit' }}> - {entry.note}</span>}
+                                            </span>
+                                            <button
+                                                onClick={() => runBlocklistCommand('remove_blocklist_entry', { kind: entry.kind, value: entry.value })}
+                                                title="Unblock"
+                                                style={{ background: 'none', border: 'none', color: 'var(--text-secondary)', cursor: 'pointer', padding: '2px' }}
+                                            >
+                                                <X size={14} />
+                                            </button>
+                                        </div>
+                                    ))}
+                                </div>
+                            )}
+
+                            <div style={{ display: 'flex', gap: '8px' }}>
+                                {[
+                                    { label: 'Import...', onClick: handleImportBlocklist },
+                                    { label: 'Export...', onClick: handleExportBlocklist },
+                                ].map(({ label, onClick }) => (
+                                    <button
+                                        key={label}
+                                        onClick={() => onClick().catch((error) => console.error(`${label} failed:`, error))}
+                                        style={{
+                                            padding: '8px 16px',
+                                            background: 'rgba(139, 92, 246, 0.2)',
+                                            border: '1px solid rgba(139, 92, 246, 0.3)',
+                                            borderRadius: 'var(--radius-md)',
+                                            color: 'rgb(167, 139, 250)',
+                                            fontSize: '13px',
+                                            fontWeight: 600,
+                                            cursor: 'pointer',
+                                        }}
+                                    >
+                                        {label}
+                                    </button>
+                                ))}
+                            </div>
+                        </div>
+                    </div>
+                </motion.div>
+
+                {/* Divider */}
+                <div style={{ height: '1px', background: 'var(--border-subtle)' }} />
+
                 {/* Storage Settings */}
                 <motion.div
                     initial={{ y: 20, opacity: 0 }}
@@ -1,11 +1,55 @@
 import React from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { motion } from 'framer-motion';
 import { Search, ArrowLeft, ArrowLeftRight } from 'lucide-react';
 import WelcomeModal from '../components/WelcomeModal';
 import ImageModal from '../components/ImageModal';
 import { LoadingSpinner } from '../components/LoadingState';
-import { WallpaperItem } from '../types/wallpaper';
+import {
+    BlocklistResponse,
+    BrowseMode,
+    HiddenItems,
+    QueryError,
+    SearchDoneEvent,
+    SearchPartialEvent,
+    SearchResponse,
+    SourcesResponse,
+    StartSearchResponse,
+    WallpaperItem,
+} from '../types/wallpaper';
+
+// query split into plain and errored parts, errors come back sorted and don't overlap
+function splitQueryErrors(query: string, errors: QueryError[]) {
+    const parts: { text: string; error?: QueryError }[] = [];
+    let position = 0;
+    for (const error of errors) {
+        if (error.start < position) continue;
+        if (error.start > position) parts.push({ text: query.slice(position, error.start) });
+        // zero-width errors still need something to underline
+        parts.push({ text: query.slice(error.start, Math.max(error.end, error.start + 1)) || ' ', error });
+        position = Math.max(error.end, error.start + 1);
+    }
+    if (position < query.length) parts.push({ text: query.slice(position) });
+    return parts;
+}
+
+// add up hidden counts from several responses or pages
+function mergeHidden(a?: HiddenItems, b?: HiddenItems): HiddenItems | undefined {
+    if (!a || !b) return a ?? b;
+    const reasons = { ...a.reasons };
+    for (const [reason, count] of Object.entries(b.reasons)) {
+        reasons[reason] = (reasons[reason] ?? 0) + count;
+    }
+    return {
+        total: a.total + b.total,
+        reasons,
+        sources: [...new Set([...a.sources, ...b.sources])],
+    };
+}
+
+// which listing an empty search bar shows for a source, first one it offers wins
+const BROWSE_PREFERENCE: BrowseMode[] = ['featured', 'hot', 'latest', 'toplist'];
 
 interface StorePageProps {
     selectedSource: string;
@@ -18,6 +62,10 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
     const [wallpapers, setWallpapers] = React.useState<WallpaperItem[]>([]);
     const [loading, setLoading] = React.useState(false);
     const [searchQuery, setSearchQuery] = React.useState('');
+    // parse errors from the backend and the query they point into
+    const [queryErrors, setQueryErrors] = React.useState<{ query: string; errors: QueryError[] }>({ query: '', errors: [] });
+    // results the content settings kept out, summed over every loaded page
+    const [hidden, setHidden] = React.useState<HiddenItems | null>(null);
     const [hasMore, setHasMore] = React.useState(true);
     const [loadingMore, setLoadingMore] = React.useState(false);
     const [selectedImage, setSelectedImage] = React.useState<WallpaperItem | null>(null);
@@ -25,6 +73,96 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
     const [showWelcome, setShowWelcome] = React.useState(false);
     const [currentType, setCurrentType] = React.useState<'static' | 'live' | 'all'>(filterType as any);
     const pageRef = React.useRef(1);
+    // shuffle seed from the first page, passed back so later pages keep the same order
+    const seedRef = React.useRef<number | undefined>(undefined);
+    // where each source continues, sources that ran out are null and get skipped
+    const cursorsRef = React.useRef<Record<string, string | null>>({});
+    // item the grid is showing similar wallpapers for, cleared by a normal search
+    const similarRef = React.useRef<WallpaperItem | null>(null);
+    // source id -> browse modes, loaded once from list_sources
+    const browseModesRef = React.useRef<Record<string, BrowseMode[]> | null>(null);
+    // running streaming search, cancelled when a new one starts
+    const activeSearchRef = React.useRef<string | null>(null);
+
+    const loadBrowseModes = async () => {
+        if (!browseModesRef.current) {
+            const response = await invoke<SourcesResponse>('list_sources');
+            browseModesRef.current = Object.fromEntries(
+                response.sources.map((source) => [source.id, source.browseModes ?? []])
+            );
+        }
+        return browseModesRef.current;
+    };
+
+    // each source's own listing, merged like a search response. null when none can browse
+    const browseSources = async (sources: string[], pageNum: number): Promise<SearchResponse | null> => {
+        const modes = await loadBrowseModes();
+        const browsable = sources
+            .filter((source) => cursorsRef.current[source] !== null)
+            .map((source) => ({ source, mode: BROWSE_PREFERENCE.find((mode) => modes[source]?.includes(mode)) }))
+            .filter((entry): entry is { source: string; mode: BrowseMode } => entry.mode !== undefined);
+        if (browsable.length === 0) return null;
+
+        const results = await Promise.all(
+            browsable.map(({ source, mode }) =>
+                invoke<SearchResponse>('browse_wallpapers', { source, mode, page: pageNum, purity: '100', limit: 30 }).catch((error) => {
+                    console.warn(`Browsing ${source} failed:`, error);
+                    return null;
+                })
+            )
+        );
+        const answered = results.filter((result): result is SearchResponse => result !== null);
+        return {
+            success: answered.some((result) => result.success),
+            items: answered.flatMap((result) => result.items),
+            stale: false,
+            cursors: Object.assign({}, ...answered.map((result) => result.cursors)),
+            hasMore: answered.some((result) => result.hasMore),
+            hidden: answered.reduce<HiddenItems | undefined>((total, result) => mergeHidden(total, result.hidden), undefined),
+        };
+    };
+
+    // start_search: batches go to onBatch as each source finishes, resolves on search:done.
+    // events can beat start_search's reply, so they're held until the search id is known
+    const streamSearch = async (
+        request: Record<string, unknown>,
+        onBatch: (batch: SearchPartialEvent) => void
+    ): Promise<{ start: StartSearchResponse; done: SearchDoneEvent }> => {
+        if (activeSearchRef.current) {
+            invoke('cancel_search', { searchId: activeSearchRef.current }).catch(() => {});
+            activeSearchRef.current = null;
+        }
+
+        let searchId: string | null = null;
+        const held: (SearchPartialEvent | SearchDoneEvent)[] = [];
+        let finish: (done: SearchDoneEvent) => void = () => {};
+        const finished = new Promise<SearchDoneEvent>((resolve) => (finish = resolve));
+        const handle = (event: SearchPartialEvent | SearchDoneEvent) => {
+            if (searchId === null) {
+                held.push(event);
+            } else if (event.searchId === searchId) {
+                if ('items' in event) onBatch(event);
+                else finish(event);
+            }
+        };
+
+        const unlisten = await Promise.all([
+            listen<SearchPartialEvent>('search:partial', ({ payload }) => handle(payload)),
+            listen<SearchDoneEvent>('search:done', ({ payload }) => handle(payload)),
+        ]);
+        try {
+            const start = await invoke<StartSearchResponse>('start_search', { request });
+            searchId = start.searchId;
+            activeSearchRef.current = searchId;
+            held.splice(0).forEach(handle);
+            return { start, done: await finished };
+        } finally {
+            unlisten.forEach((stop) => stop());
+            if (activeSearchRef.current === searchId) {
+                activeSearchRef.current = null;
+            }
+        }
+    };
 
     React.useEffect(() => {
         if (isDirectNavigation) {
@@ -33,12 +171,15 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
     }, [isDirectNavigation]);
 
     const searchWallpapers = React.useCallback(
-        async (pageNum: number = 1, append: boolean = false) => {
+        async (pageNum: number = 1, append: boolean = false, queryOverride?: string) => {
             if (append) {
                 setLoadingMore(true);
             } else {
                 setLoading(true);
                 setWallpapers([]);
+                setHidden(null);
+                seedRef.current = undefined;
+                cursorsRef.current = {};
             }
 
             try {
@@ -69,18 +210,22 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
                     }
                 }
 
-                const result: any = await invoke('search_wallpapers', {
-                    query: searchQuery || 'anime',
-                    sources: sourcesToUse,
-                    limitPerSource: 30,
-                    randomize: true,
-                    page: pageNum,
-                    purity: '100',
-                    aiArt: false,
-                });
-
-                if (result.success && result.items) {
-                    const normalized = result.items.map((item: any): WallpaperItem => ({
+                const typed = (queryOverride ?? searchQuery).trim();
+                const query = typed || 'anime';
+                // "more like this" first, then with nothing typed what's new on the sites.
+                // a made-up query only if none of them can browse
+                const browsed = similarRef.current
+                    ? await invoke<SearchResponse>('find_similar', {
+                          item: similarRef.current,
+                          page: pageNum,
+                          cursors: cursorsRef.current,
+                      })
+                    : typed
+                      ? null
+                      : await browseSources(sourcesToUse, pageNum);
+                // result items as grid items, narrowed to the selected type
+                const toGridItems = (items: any[]) => {
+                    const normalized = items.map((item: any): WallpaperItem => ({
                         id: item.id,
                         source: item.source,
                         title: item.title,
@@ -96,20 +241,63 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
                     }));
 
                     // Dont filter when type is 'all' - show everything mixed!
-                    let filtered = normalized;
                     if (currentType === 'live') {
-                        filtered = normalized.filter((item: WallpaperItem) => item.type === 'video');
+                        return normalized.filter((item: WallpaperItem) => item.type === 'video');
                     } else if (currentType === 'static') {
-                        filtered = normalized.filter((item: WallpaperItem) => item.type === 'image');
+                        return normalized.filter((item: WallpaperItem) => item.type === 'image');
                     }
+                    return normalized;
+                };
+
+                if (!browsed) {
+                    // typed searches stream, each source's batch shows up as soon as it's in
+                    if (!append) setWallpapers([]);
+                    const { start, done } = await streamSearch(
+                        {
+                            query,
+                            sources: sourcesToUse,
+                            limitPerSource: 30,
+                            randomize: true,
+                            page: pageNum,
+                            purity: '100',
+                            aiArt: false,
+                            seed: seedRef.current,
+                            cursors: cursorsRef.current,
+                        },
+                        (batch) => {
+                            const items = toGridItems(batch.items);
+                            setWallpapers((prev) => [...prev, ...items]);
+                        }
+                    );
+                    // a newer search took over, the grid is its now
+                    if (done.cancelled) return;
+                    seedRef.current = start.seed ?? seedRef.current;
+                    setQueryErrors({ query, errors: start.queryErrors ?? [] });
+                    if (done.hidden) {
+                        setHidden((prev) => mergeHidden(prev ?? undefined, done.hidden) ?? null);
+                    }
+                    cursorsRef.current = { ...cursorsRef.current, ...done.cursors };
+                    setHasMore(done.hasMore);
+                    return;
+                }
+
+                const result = browsed;
+                seedRef.current = result.seed ?? seedRef.current;
+                setQueryErrors({ query, errors: result.queryErrors ?? [] });
+                if (result.hidden) {
+                    setHidden((prev) => mergeHidden(prev ?? undefined, result.hidden) ?? null);
+                }
+                cursorsRef.current = { ...cursorsRef.current, ...(result.cursors ?? {}) };
 
+                if (result.success && result.items) {
+                    const filtered = toGridItems(result.items);
                     if (append) {
                         setWallpapers((prev) => [...prev, ...filtered]);
                     } else {
                         setWallpapers(filtered);
                     }
 
-                    setHasMore(filtered.length >= 12);
+                    setHasMore(result.hasMore ?? filtered.length >= 12);
                 } else {
                     setHasMore(false);
                 }
@@ -147,11 +335,44 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
     }, [loadingMore, loading, hasMore, searchWallpapers]);
 
     const handleSearch = () => {
+        similarRef.current = null;
+        pageRef.current = 1;
+        setHasMore(true);
+        searchWallpapers(1, false);
+    };
+
+    // tags in the preview start a fresh search, the query state isn't updated yet when it runs
+    const handleTagSearch = (tag: string) => {
+        similarRef.current = null;
+        setSelectedImage(null);
+        setSearchQuery(tag);
+        pageRef.current = 1;
+        setHasMore(true);
+        searchWallpapers(1, false, tag);
+    };
+
+    const handleFindSimilar = (item: WallpaperItem) => {
+        setSelectedImage(null);
+        similarRef.current = item;
         pageRef.current = 1;
         setHasMore(true);
         searchWallpapers(1, false);
     };
 
+    const handleBlock = async (item: WallpaperItem) => {
+        try {
+            const result = await invoke<BlocklistResponse>('block_wallpaper', { item });
+            if (!result.success) {
+                alert('Failed to block wallpaper: ' + result.error);
+                return;
+            }
+            setSelectedImage(null);
+            setWallpapers((prev) => prev.filter((wallpaper) => wallpaper.id !== item.id));
+        } catch (error) {
+            console.error('Block failed:', error);
+        }
+    };
+
     const handleWelcomeChoice = (type: 'static' | 'live' | 'all') => {
         setCurrentType(type);
         setShowWelcome(false);
@@ -162,7 +383,8 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
 
         setSettingWallpaper(selectedImage.id);
         try {
-            const result: any = await invoke('set_wallpaper', { imageUrl: url });
+            // the backend runs the source's resolver again, so thumbnail-only listings get the original
+            const result: any = await invoke('set_wallpaper', { imageUrl: url, item: selectedImage });
 
             if (result.success) {
                 setSelectedImage(null);
@@ -256,14 +478,18 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
                         <input
                             type="text"
                             value={searchQuery}
-                            onChange={(e) => setSearchQuery(e.target.value)}
+                            onChange={(e) => {
+                                setSearchQuery(e.target.value);
+                                // offsets only fit the text they were reported for
+                                setQueryErrors({ query: '', errors: [] });
+                            }}
                             onKeyDown={(e) => e.key === 'Enter' && handleSearch()}
                             placeholder="Search wallpapers..."
                             style={{
                                 width: '100%',
                                 padding: '16px 20px 16px 52px',
                                 background: 'var(--bg-secondary)',
-                                border: '1px solid var(--border-color)',
+                                border: `1px solid ${queryErrors.errors.length > 0 ? '#ef4444' : 'var(--border-color)'}`,
                                 borderRadius: '14px',
                                 color: 'var(--text-primary)',
                                 fontSize: '15px',
@@ -290,6 +516,48 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
                         Search
                     </motion.button>
                 </div>
+
+                {queryErrors.errors.length > 0 && (
+                    <div style={{ maxWidth: '700px', marginTop: '10px', fontSize: '13px' }}>
+                        <div style={{ fontFamily: 'monospace', color: 'var(--text-secondary)', whiteSpace: 'pre-wrap' }}>
+                            {splitQueryErrors(queryErrors.query, queryErrors.errors).map((part, index) =>
+                                part.error ? (
+                                    <span
+                                        key={index}
+                                        title={part.error.message}
+                                        style={{
+                                            color: '#ef4444',
+                                            textDecoration: 'underline wavy #ef4444',
+                                        }}
+                                    >
+                                        {part.text}
+                                    </span>
+                                ) : (
+                                    <span key={index}>{part.text}</span>
+                                )
+                            )}
+                        </div>
+                        {queryErrors.errors.map((error, index) => (
+                            <div key={index} style={{ color: '#ef4444', marginTop: '4px' }}>
+                                {error.message}
+                            </div>
+                        ))}
+                    </div>
+                )}
+
+                {hidden && (
+                    <div
+                        title={[
+                            ...Object.entries(hidden.reasons).map(([reason, count]) => `${reason}: ${count}`),
+                            ...hidden.sources.map((source) => `${source}: not searched`),
+                        ].join('\n')}
+                        style={{ maxWidth: '700px', marginTop: '10px', fontSize: '13px', color: 'var(--text-secondary)' }}
+                    >
+                        {hidden.total > 0 && `${hidden.total} result${hidden.total === 1 ? '' : 's'} hidden by your content settings`}
+                        {hidden.total > 0 && hidden.sources.length > 0 && ', '}
+                        {hidden.sources.length > 0 && `${hidden.sources.join(', ')} left out`}
+                    </div>
+                )}
             </motion.div>
 
             {loading && wallpapers.length === 0 ? (
@@ -395,6 +663,9 @@ export default function StorePage({ selectedSource, filterType = 'all', isDirect
                     onClose={() => setSelectedImage(null)}
                     onSetWallpaper={handleSetWallpaper}
                     isLoading={settingWallpaper === selectedImage.id}
+                    onTagClick={handleTagSearch}
+                    onFindSimilar={handleFindSimilar}
+                    onBlock={handleBlock}
                 />
             )}
         </div>
@@ -1,4 +1,199 @@
-export type WallpaperSourceOption = 'all' | 'wallhaven' | 'moewalls' | 'wallpapers' | 'wallpaperflare' | 'motionbgs';
+// source ids come from the backend registry (`list_sources`), 'all' searches every registered source
+export type WallpaperSourceOption = 'all' | (string & {});
+
+export interface SourceCapabilities {
+  images: boolean;
+  videos: boolean;
+  nsfw: boolean;
+  pagination: boolean;
+  resolve: boolean;
+}
+
+// query-less listings for browse_wallpapers, toplist takes a range (1d, 3d, 1w, 1M, 3M, 6M, 1y)
+export type BrowseMode = 'latest' | 'toplist' | 'random' | 'featured' | 'hot';
+
+export interface SourceInfo {
+  id: string;
+  name: string;
+  capabilities: SourceCapabilities;
+  browseModes: BrowseMode[];
+}
+
+export interface SourcesResponse {
+  success: boolean;
+  sources: SourceInfo[];
+  errors?: string[];
+}
+
+// per-source failure reported in SearchResponse.errors
+export type ScrapeErrorKind = 'network' | 'http' | 'blocked' | 'parse_changed' | 'empty' | 'timeout';
+
+export interface SourceError {
+  source: string;
+  kind: ScrapeErrorKind;
+  message: string;
+  status?: number;
+  retryable: boolean;
+}
+
+export interface SearchResponse {
+  success: boolean;
+  items: WallpaperItem[];
+  errors?: SourceError[];
+  // sources the last health check flagged as broken
+  degraded?: string[];
+  // some sources failed and were answered from the search cache instead
+  stale: boolean;
+  cacheAgeSecs?: number;
+  // shuffle seed, send it back with the next page for a stable order
+  seed?: number;
+  // per-source position for the next page, null once a source is out of results
+  cursors: Record<string, string | null>;
+  hasMore: boolean;
+  // parts of the query that didn't parse, left out of the search
+  queryErrors?: QueryError[];
+  // what the content settings kept out, missing when nothing was
+  hidden?: HiddenItems;
+}
+
+// reasons look like "keyword: gore" or "ai art", sources are nsfw sites left out entirely
+export interface HiddenItems {
+  total: number;
+  reasons: Record<string, number>;
+  sources: string[];
+}
+
+// start/end are offsets into the query string as typed
+export interface QueryError {
+  message: string;
+  start: number;
+  end: number;
+}
+
+// how search_wallpapers / start_search order merged results
+export type SearchRanking = 'source' | 'interleave' | 'resolution' | 'video' | 'shuffle';
+
+export interface StartSearchResponse {
+  success: boolean;
+  searchId: string;
+  degraded?: string[];
+  seed?: number;
+  queryErrors?: QueryError[];
+}
+
+// streaming search events, filter on searchId so a cancelled/old search never leaks into the grid
+export interface SearchPartialEvent {
+  searchId: string;
+  source: string;
+  items: WallpaperItem[];
+  // cached fallback items, the matching search:source-error says why
+  stale: boolean;
+  cacheAgeSecs?: number;
+  hidden?: HiddenItems;
+}
+
+export interface SearchSourceErrorEvent {
+  searchId: string;
+  error: SourceError;
+}
+
+export interface SearchDoneEvent {
+  searchId: string;
+  totalItems: number;
+  errorCount: number;
+  cancelled: boolean;
+  cursors: Record<string, string | null>;
+  hasMore: boolean;
+  // totals over the whole search
+  hidden?: HiddenItems;
+}
+
+// user blocklist, see list_blocklist / add_blocklist_entry / block_wallpaper
+export type BlockKind = 'item' | 'tag' | 'title' | 'source' | 'hash';
+
+export interface BlockEntry {
+  kind: BlockKind;
+  // item id, tag, title regex, source id or 16 hex digit thumbnail hash
+  value: string;
+  note?: string;
+  addedAt: number;
+}
+
+export interface BlocklistResponse {
+  success: boolean;
+  entries: BlockEntry[];
+  // new entries from an import or block_wallpaper
+  added: number;
+  error?: string;
+}
+
+// check_sources / get_source_health
+export type SourceHealthStatus = 'ok' | 'degraded' | 'down' | 'unknown';
+
+export interface SelectorHealth {
+  name: string;
+  selector: string;
+  matched: number;
+}
+
+export interface SourceHealth {
+  source: string;
+  status: SourceHealthStatus;
+  checkedAt: number;
+  elapsedMs: number;
+  checks: SelectorHealth[];
+  error?: SourceError;
+}
+
+export interface SourceHealthResponse {
+  success: boolean;
+  sources: SourceHealth[];
+  error?: string;
+}
+
+// one file of a resolved wallpaper, resolve_moewalls_video lists every resolution a post offers
+export interface MediaVariant {
+  url: string;
+  label?: string;
+  width?: number;
+  height?: number;
+  // bytes
+  fileSize?: number;
+}
+
+// resolve_wallpaper and the per-site resolvers
+export interface ResolveHighResResponse {
+  success: boolean;
+  url?: string;
+  url4k?: string;
+  error?: string;
+  errorKind?: ScrapeErrorKind;
+  variants?: MediaVariant[];
+  // size of url, from the site or read from the image's header
+  width?: number;
+  height?: number;
+}
+
+// get_wallpaper_details, fetched when an item is opened and cached per item id
+export interface WallpaperDetails {
+  id: string;
+  source: string;
+  // detail page tags and categories first, then the listing's own
+  tags: string[];
+  uploader?: string;
+  width?: number;
+  height?: number;
+  // bytes
+  fileSize?: number;
+  license?: string;
+}
+
+export interface WallpaperDetailsResponse {
+  success: boolean;
+  details?: WallpaperDetails;
+  error?: string;
+  errorKind?: ScrapeErrorKind;
+}
 
 export interface PicReImage {
   _id: string;